@@ -0,0 +1,50 @@
+-- Core tables: students, their projects, analysis results and playground sessions
+
+CREATE TABLE IF NOT EXISTS students (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    email TEXT UNIQUE,
+    github_username TEXT,
+    cohort TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS projects (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    student_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    github_url TEXT NOT NULL,
+    technology_stack TEXT, -- JSON array as string
+    status TEXT DEFAULT 'pending',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (student_id) REFERENCES students(id)
+);
+
+CREATE TABLE IF NOT EXISTS analysis_results (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    code_quality_score INTEGER,
+    structure_score INTEGER,
+    documentation_score INTEGER,
+    functionality_score INTEGER,
+    total_score INTEGER,
+    feedback TEXT,
+    analysis_data TEXT, -- JSON as string
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE TABLE IF NOT EXISTS playground_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    container_id TEXT UNIQUE,
+    port INTEGER,
+    status TEXT DEFAULT 'starting',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_projects_student_id ON projects(student_id);
+CREATE INDEX IF NOT EXISTS idx_analysis_results_project_id ON analysis_results(project_id);
+CREATE INDEX IF NOT EXISTS idx_playground_sessions_project_id ON playground_sessions(project_id);
@@ -0,0 +1,32 @@
+-- Key/value application settings (values are JSON) and assignment smoke tests
+
+CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS smoke_scenarios (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    assignment_id INTEGER,
+    name TEXT NOT NULL,
+    steps TEXT NOT NULL, -- JSON array as string
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS smoke_test_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    playground_session_id INTEGER,
+    scenario_id INTEGER NOT NULL,
+    passed BOOLEAN NOT NULL DEFAULT 0,
+    total_steps INTEGER NOT NULL DEFAULT 0,
+    passed_steps INTEGER NOT NULL DEFAULT 0,
+    results TEXT, -- JSON as string
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id),
+    FOREIGN KEY (playground_session_id) REFERENCES playground_sessions(id),
+    FOREIGN KEY (scenario_id) REFERENCES smoke_scenarios(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_smoke_test_runs_project_id ON smoke_test_runs(project_id);
@@ -0,0 +1,19 @@
+-- Committed playground snapshots; sessions remember the snapshot they were restored from
+
+CREATE TABLE IF NOT EXISTS playground_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    session_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    image TEXT NOT NULL,
+    workspace_path TEXT NOT NULL,
+    environment TEXT NOT NULL, -- JSON as string
+    size_bytes INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id),
+    FOREIGN KEY (session_id) REFERENCES playground_sessions(id)
+);
+
+ALTER TABLE playground_sessions ADD COLUMN snapshot_id INTEGER REFERENCES playground_snapshots(id);
+
+CREATE INDEX IF NOT EXISTS idx_playground_snapshots_project_id ON playground_snapshots(project_id);
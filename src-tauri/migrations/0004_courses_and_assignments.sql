@@ -0,0 +1,46 @@
+-- Courses, their cohorts and assignments; projects become submissions to an assignment
+
+CREATE TABLE IF NOT EXISTS courses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    description TEXT,
+    term TEXT,
+    archived BOOLEAN NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS cohorts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    course_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    starts_on DATE,
+    ends_on DATE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (course_id) REFERENCES courses(id),
+    UNIQUE (course_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS assignments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    course_id INTEGER NOT NULL,
+    cohort_id INTEGER, -- NULL when the assignment applies to every cohort of the course
+    title TEXT NOT NULL,
+    description TEXT,
+    due_at DATETIME,
+    rubric TEXT, -- JSON as string
+    starter_repo_url TEXT,
+    allowed_stacks TEXT, -- JSON array as string, NULL allows any stack
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (course_id) REFERENCES courses(id),
+    FOREIGN KEY (cohort_id) REFERENCES cohorts(id)
+);
+
+-- The free-text students.cohort column is kept for display and sheet round-trips
+ALTER TABLE students ADD COLUMN cohort_id INTEGER REFERENCES cohorts(id);
+ALTER TABLE projects ADD COLUMN assignment_id INTEGER REFERENCES assignments(id);
+
+CREATE INDEX IF NOT EXISTS idx_cohorts_course_id ON cohorts(course_id);
+CREATE INDEX IF NOT EXISTS idx_assignments_course_id ON assignments(course_id);
+CREATE INDEX IF NOT EXISTS idx_students_cohort_id ON students(cohort_id);
+CREATE INDEX IF NOT EXISTS idx_projects_assignment_id ON projects(assignment_id);
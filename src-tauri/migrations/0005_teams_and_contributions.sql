@@ -0,0 +1,48 @@
+-- Teams owning group projects, their members and per-member git contribution stats
+
+CREATE TABLE IF NOT EXISTS teams (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    course_id INTEGER,
+    assignment_id INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (course_id) REFERENCES courses(id),
+    FOREIGN KEY (assignment_id) REFERENCES assignments(id)
+);
+
+CREATE TABLE IF NOT EXISTS team_members (
+    team_id INTEGER NOT NULL,
+    student_id INTEGER NOT NULL,
+    role TEXT,
+    grade_weight REAL NOT NULL DEFAULT 1.0, -- multiplier applied to the project score for this member
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (team_id, student_id),
+    FOREIGN KEY (team_id) REFERENCES teams(id),
+    FOREIGN KEY (student_id) REFERENCES students(id)
+);
+
+-- projects.student_id stays the submitting student; team_id marks a group project
+ALTER TABLE projects ADD COLUMN team_id INTEGER REFERENCES teams(id);
+
+CREATE TABLE IF NOT EXISTS project_contributions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    student_id INTEGER, -- NULL when the git author could not be matched to a student
+    author_name TEXT NOT NULL,
+    author_email TEXT NOT NULL,
+    commits INTEGER NOT NULL DEFAULT 0,
+    lines_added INTEGER NOT NULL DEFAULT 0,
+    lines_deleted INTEGER NOT NULL DEFAULT 0,
+    files_touched INTEGER NOT NULL DEFAULT 0,
+    share REAL NOT NULL DEFAULT 0, -- fraction of the project's changed lines
+    first_commit_at DATETIME,
+    last_commit_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id),
+    FOREIGN KEY (student_id) REFERENCES students(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_teams_assignment_id ON teams(assignment_id);
+CREATE INDEX IF NOT EXISTS idx_team_members_student_id ON team_members(student_id);
+CREATE INDEX IF NOT EXISTS idx_projects_team_id ON projects(team_id);
+CREATE INDEX IF NOT EXISTS idx_project_contributions_project_id ON project_contributions(project_id);
@@ -0,0 +1,43 @@
+-- Reviewer grade overrides, finalized grades and the project review workflow
+
+CREATE TABLE IF NOT EXISTS grade_adjustments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    criterion TEXT NOT NULL, -- analysis criterion or an assignment rubric criterion
+    automated_score INTEGER,
+    adjusted_score INTEGER NOT NULL,
+    max_score INTEGER,
+    justification TEXT NOT NULL,
+    reviewer TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id),
+    UNIQUE (project_id, criterion)
+);
+
+CREATE TABLE IF NOT EXISTS final_grades (
+    project_id INTEGER PRIMARY KEY,
+    automated_score INTEGER,
+    final_score INTEGER NOT NULL,
+    notes TEXT,
+    graded_by TEXT,
+    graded_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    released_at DATETIME,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE TABLE IF NOT EXISTS project_status_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    from_status TEXT NOT NULL,
+    to_status TEXT NOT NULL,
+    note TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+-- 'completed' predates the review workflow and meant the analysis finished
+UPDATE projects SET status = 'analyzed' WHERE status = 'completed';
+
+CREATE INDEX IF NOT EXISTS idx_grade_adjustments_project_id ON grade_adjustments(project_id);
+CREATE INDEX IF NOT EXISTS idx_project_status_history_project_id ON project_status_history(project_id);
@@ -0,0 +1,24 @@
+-- Reviewer comments anchored to a file, line range and commit of a project
+
+CREATE TABLE IF NOT EXISTS review_comments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project_id INTEGER NOT NULL,
+    file_path TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    commit_sha TEXT NOT NULL, -- commit the current anchor refers to
+    category TEXT NOT NULL CHECK (category IN ('bug', 'style', 'praise', 'question')),
+    body TEXT NOT NULL,
+    points_deducted INTEGER,
+    author TEXT,
+    outdated BOOLEAN NOT NULL DEFAULT 0, -- the anchored lines no longer exist at the latest analyzed commit
+    original_commit_sha TEXT NOT NULL,
+    original_file_path TEXT NOT NULL,
+    original_start_line INTEGER NOT NULL,
+    original_end_line INTEGER NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_comments_project_id ON review_comments(project_id);
@@ -0,0 +1,5 @@
+-- Record what each analysis run looked at so runs can be compared
+
+ALTER TABLE analysis_results ADD COLUMN commit_sha TEXT;
+ALTER TABLE analysis_results ADD COLUMN analyzer_version TEXT;
+ALTER TABLE analysis_results ADD COLUMN rubric_version TEXT; -- NULL when the project has no assignment rubric
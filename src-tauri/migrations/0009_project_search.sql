@@ -0,0 +1,77 @@
+-- Full-text search over projects, their students, latest feedback and analysis findings.
+-- project_search.rowid is the project id; triggers keep it in sync with the source tables.
+
+CREATE VIEW IF NOT EXISTS project_search_source AS
+SELECT
+    p.id AS project_id,
+    s.name AS student_name,
+    p.name AS project_name,
+    COALESCE(p.description, '') AS description,
+    COALESCE(a.feedback, '') AS feedback,
+    COALESCE((
+        SELECT group_concat(json_extract(issue.value, '$.description') || ' ' || json_extract(issue.value, '$.file_path'), ' ')
+        FROM json_each(a.analysis_data, '$.code_quality.security_issues') AS issue
+    ), '') || ' ' || COALESCE((
+        SELECT group_concat(recommendation.value, ' ')
+        FROM json_each(a.analysis_data, '$.recommendations') AS recommendation
+    ), '') AS findings
+FROM projects p
+JOIN students s ON p.student_id = s.id
+LEFT JOIN analysis_results a ON a.id = (
+    SELECT id FROM analysis_results
+    WHERE project_id = p.id
+    ORDER BY created_at DESC, id DESC
+    LIMIT 1
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS project_search USING fts5(
+    student_name,
+    project_name,
+    description,
+    feedback,
+    findings,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO project_search (rowid, student_name, project_name, description, feedback, findings)
+SELECT project_id, student_name, project_name, description, feedback, findings FROM project_search_source;
+
+CREATE TRIGGER IF NOT EXISTS project_search_project_insert AFTER INSERT ON projects BEGIN
+    INSERT INTO project_search (rowid, student_name, project_name, description, feedback, findings)
+    SELECT project_id, student_name, project_name, description, feedback, findings
+    FROM project_search_source WHERE project_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS project_search_project_update AFTER UPDATE OF name, description, student_id ON projects BEGIN
+    DELETE FROM project_search WHERE rowid = NEW.id;
+    INSERT INTO project_search (rowid, student_name, project_name, description, feedback, findings)
+    SELECT project_id, student_name, project_name, description, feedback, findings
+    FROM project_search_source WHERE project_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS project_search_project_delete AFTER DELETE ON projects BEGIN
+    DELETE FROM project_search WHERE rowid = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS project_search_student_update AFTER UPDATE OF name ON students BEGIN
+    DELETE FROM project_search WHERE rowid IN (SELECT id FROM projects WHERE student_id = NEW.id);
+    INSERT INTO project_search (rowid, student_name, project_name, description, feedback, findings)
+    SELECT project_id, student_name, project_name, description, feedback, findings
+    FROM project_search_source WHERE project_id IN (SELECT id FROM projects WHERE student_id = NEW.id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS project_search_analysis_insert AFTER INSERT ON analysis_results BEGIN
+    DELETE FROM project_search WHERE rowid = NEW.project_id;
+    INSERT INTO project_search (rowid, student_name, project_name, description, feedback, findings)
+    SELECT project_id, student_name, project_name, description, feedback, findings
+    FROM project_search_source WHERE project_id = NEW.project_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS project_search_analysis_delete AFTER DELETE ON analysis_results BEGIN
+    DELETE FROM project_search WHERE rowid = OLD.project_id;
+    INSERT INTO project_search (rowid, student_name, project_name, description, feedback, findings)
+    SELECT project_id, student_name, project_name, description, feedback, findings
+    FROM project_search_source WHERE project_id = OLD.project_id;
+END;
+
+CREATE INDEX IF NOT EXISTS idx_projects_status ON projects(status);
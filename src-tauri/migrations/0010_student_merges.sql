@@ -0,0 +1,12 @@
+-- Audit trail for merged duplicate students; merged_record keeps the removed row as JSON
+
+CREATE TABLE IF NOT EXISTS student_merges (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    surviving_student_id INTEGER NOT NULL,
+    merged_student_id INTEGER NOT NULL,
+    merged_record TEXT NOT NULL, -- JSON as string
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (surviving_student_id) REFERENCES students(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_students_github_username ON students(github_username COLLATE NOCASE);
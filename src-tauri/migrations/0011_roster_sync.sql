@@ -0,0 +1,56 @@
+-- Spreadsheets kept in sync with the local roster, and the rows last seen in each of them
+
+CREATE TABLE IF NOT EXISTS roster_sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    spreadsheet_id TEXT NOT NULL,
+    range TEXT NOT NULL, -- A1 notation, e.g. 'Roster'!A1:H
+    mapping TEXT NOT NULL, -- SheetMapping as JSON
+    course_id INTEGER,
+    assignment_id INTEGER,
+    interval_minutes INTEGER NOT NULL DEFAULT 60,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    conflict_policy TEXT NOT NULL DEFAULT 'local_wins' CHECK (conflict_policy IN ('sheet_wins', 'local_wins')),
+    status_column TEXT, -- header of the column that receives the project status, NULL disables write-back
+    score_column TEXT, -- header of the column that receives the score, NULL disables write-back
+    last_synced_at DATETIME,
+    last_error TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (course_id) REFERENCES courses(id),
+    FOREIGN KEY (assignment_id) REFERENCES assignments(id)
+);
+
+-- row_key identifies a sheet row across syncs (email, else GitHub username, else name);
+-- row_data is the parsed row as last synced, the base for three-way conflict detection
+CREATE TABLE IF NOT EXISTS roster_source_rows (
+    source_id INTEGER NOT NULL,
+    row_key TEXT NOT NULL,
+    row_number INTEGER NOT NULL,
+    row_data TEXT NOT NULL, -- StudentData as JSON
+    student_id INTEGER,
+    project_id INTEGER,
+    removed_at DATETIME, -- set when the row disappears from the sheet; local records are kept
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (source_id, row_key),
+    FOREIGN KEY (source_id) REFERENCES roster_sources(id),
+    FOREIGN KEY (student_id) REFERENCES students(id),
+    FOREIGN KEY (project_id) REFERENCES projects(id)
+);
+
+CREATE TABLE IF NOT EXISTS roster_sync_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_id INTEGER NOT NULL,
+    added INTEGER NOT NULL DEFAULT 0,
+    changed INTEGER NOT NULL DEFAULT 0,
+    removed INTEGER NOT NULL DEFAULT 0,
+    unchanged INTEGER NOT NULL DEFAULT 0,
+    written_back INTEGER NOT NULL DEFAULT 0,
+    conflicts TEXT, -- JSON array as string
+    errors TEXT, -- JSON array as string
+    started_at DATETIME NOT NULL,
+    finished_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (source_id) REFERENCES roster_sources(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_roster_source_rows_student_id ON roster_source_rows(student_id);
+CREATE INDEX IF NOT EXISTS idx_roster_sync_runs_source_id ON roster_sync_runs(source_id);
@@ -0,0 +1,4 @@
+-- When the submission was last committed to, taken from the analyzed repository's HEAD.
+-- Assignment lateness is judged on this rather than on when the project was imported.
+
+ALTER TABLE projects ADD COLUMN submitted_at DATETIME;
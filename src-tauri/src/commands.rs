@@ -1,61 +1,176 @@
-use crate::database::{Database, schema};
+use crate::database::{Database, ProjectStatus, schema};
 use crate::services::*;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::Mutex;
 
 // Application state structure
 pub struct AppState {
     pub db: Arc<Database>,
     pub auth_service: Arc<AuthService>,
+    pub api_client: Arc<ApiClient>,
     pub github_service: Arc<Mutex<GitHubService>>,
     pub sheets_service: Arc<SheetsService>,
-    pub docker_service: Arc<Mutex<DockerService>>,
+    pub container_runtime: Arc<dyn ContainerRuntime>,
+    pub container_runtime_status: ContainerRuntimeStatus,
     pub analysis_service: Arc<AnalysisService>,
+    pub smoke_test_service: Arc<SmokeTestService>,
+    pub identity_service: Arc<IdentityService>,
+    pub roster_file_service: Arc<RosterFileService>,
+    pub gradebook_export_service: Arc<GradebookExportService>,
+    pub roster_sync_lock: Arc<Mutex<()>>,
 }
 
 // Authentication Commands
 #[tauri::command]
 pub async fn get_auth_status(state: State<'_, AppState>) -> Result<AuthStatus, String> {
     state.auth_service
-        .get_auth_status()
+        .get_auth_status(&state.api_client)
         .await
         .map_err(|e| e.to_string())
 }
 
+const GOOGLE_LOGIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+// Returns the URL to open in the browser; the redirect is received and exchanged in the background and the
+// outcome arrives as a "google-auth-completed" or "google-auth-failed" event
 #[tauri::command]
-pub async fn generate_google_auth_url(state: State<'_, AppState>) -> Result<GoogleAuthUrl, String> {
-    state.auth_service
-        .generate_google_auth_url()
-        .map_err(|e| e.to_string())
+pub async fn start_google_login(
+    app_handle: AppHandle,
+    state: State<'_, AppState>
+) -> Result<GoogleAuthUrl, String> {
+    let (auth_url, listener) = state.auth_service
+        .begin_google_login()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let auth_service = state.auth_service.clone();
+    let redirect_uri = auth_url.redirect_uri.clone();
+    tauri::async_runtime::spawn(async move {
+        let outcome = match tokio::time::timeout(GOOGLE_LOGIN_TIMEOUT, auth_service.complete_google_login(listener)).await {
+            Ok(outcome) => outcome,
+            // A sign-in finished through exchange_google_code or replaced by a newer one has nothing to report
+            Err(_) if !auth_service.abandon_google_login(&redirect_uri) => return,
+            Err(_) => Err(anyhow::anyhow!("Timed out waiting for the Google sign-in")),
+        };
+
+        let _ = match outcome {
+            Ok(()) => app_handle.emit("google-auth-completed", ()),
+            Err(e) => app_handle.emit("google-auth-failed", e.to_string()),
+        };
+    });
+
+    Ok(auth_url)
 }
 
+// Manual fallback for when the browser cannot reach the loopback listener
 #[tauri::command]
 pub async fn exchange_google_code(
     code: String,
-    csrf_token: String,
-    pkce_verifier: String,
+    oauth_state: String,
     state: State<'_, AppState>
 ) -> Result<(), String> {
     state.auth_service
-        .exchange_google_code(code, csrf_token, pkce_verifier)
+        .exchange_google_code(code, oauth_state)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_google_oauth_client(state: State<'_, AppState>) -> Result<GoogleOAuthClientStatus, String> {
+    Ok(state.auth_service.google_oauth_client_status())
+}
+
+#[tauri::command]
+pub async fn set_google_oauth_client(
+    client: GoogleOAuthClient,
+    state: State<'_, AppState>
+) -> Result<GoogleOAuthClientStatus, String> {
+    state.auth_service
+        .set_google_oauth_client(&client)
+        .map_err(|e| e.to_string())?;
+
+    Ok(state.auth_service.google_oauth_client_status())
+}
+
 #[tauri::command]
 pub async fn validate_github_token(
     token: String,
     state: State<'_, AppState>
 ) -> Result<String, String> {
     state.auth_service
-        .validate_github_token(&token)
+        .validate_github_token(&state.api_client, &token)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Returns the code for the user to enter on GitHub; polling runs in the background until the code is approved
+// or expires, and the outcome arrives as a "github-auth-completed" (with the username) or "github-auth-failed" event
+#[tauri::command]
+pub async fn start_github_device_login(
+    app_handle: AppHandle,
+    state: State<'_, AppState>
+) -> Result<GitHubDeviceLogin, String> {
+    let (login, device) = state.auth_service
+        .begin_github_device_login()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let auth_service = state.auth_service.clone();
+    tauri::async_runtime::spawn(async move {
+        // A login replaced by a newer one ends quietly; the newer one reports the outcome
+        let _ = match auth_service.complete_github_device_login(device).await {
+            Ok(Some(username)) => app_handle.emit("github-auth-completed", username),
+            Ok(None) => return,
+            Err(e) => app_handle.emit("github-auth-failed", e.to_string()),
+        };
+    });
+
+    Ok(login)
+}
+
+#[tauri::command]
+pub async fn get_github_oauth_client(state: State<'_, AppState>) -> Result<GitHubOAuthClientStatus, String> {
+    Ok(state.auth_service.github_oauth_client_status())
+}
+
+#[tauri::command]
+pub async fn set_github_oauth_client(
+    client: GitHubOAuthClient,
+    state: State<'_, AppState>
+) -> Result<GitHubOAuthClientStatus, String> {
+    state.auth_service
+        .set_github_oauth_client(&client)
+        .map_err(|e| e.to_string())?;
+
+    Ok(state.auth_service.github_oauth_client_status())
+}
+
+#[tauri::command]
+pub async fn get_github_app_status(state: State<'_, AppState>) -> Result<GitHubAppStatus, String> {
+    Ok(state.auth_service.github_app_status())
+}
+
+#[tauri::command]
+pub async fn set_github_app(
+    config: GitHubAppConfig,
+    state: State<'_, AppState>
+) -> Result<GitHubAppStatus, String> {
+    state.auth_service
+        .set_github_app(&config)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn remove_github_app(state: State<'_, AppState>) -> Result<(), String> {
+    state.auth_service
+        .remove_github_app()
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn logout(state: State<'_, AppState>) -> Result<(), String> {
     state.auth_service
@@ -76,6 +191,26 @@ pub async fn get_sheet_data(
         .map_err(|e| e.to_string())
 }
 
+// Local CSV/XLSX/ODS rosters feed the same parse/validate/import commands as Google Sheets
+#[tauri::command]
+pub async fn read_roster_file(
+    path: String,
+    options: Option<RosterFileOptions>,
+    state: State<'_, AppState>
+) -> Result<RosterFilePreview, String> {
+    state.roster_file_service
+        .read_roster_file(std::path::Path::new(&path), &options.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn suggest_sheet_mapping(
+    sheet_data: SheetData,
+    state: State<'_, AppState>
+) -> Result<MappingSuggestion, String> {
+    Ok(state.sheets_service.suggest_mapping(&sheet_data))
+}
+
 #[tauri::command]
 pub async fn parse_and_validate_sheet_data(
     sheet_data: SheetData,
@@ -93,207 +228,2276 @@ pub async fn parse_and_validate_sheet_data(
     Ok((students, errors))
 }
 
+// A dry run performs the whole import inside a transaction and rolls it back, so the preview is
+// exactly what a real import would do. A real import is all-or-nothing: any failed row rolls back every row.
 #[tauri::command]
 pub async fn import_students_from_sheet(
     students_data: Vec<StudentData>,
+    course_id: Option<i64>,
+    assignment_id: Option<i64>,
+    dry_run: Option<bool>,
     state: State<'_, AppState>
 ) -> Result<ImportResult, String> {
-    let mut students_imported = 0;
-    let mut projects_imported = 0;
-    let mut errors = Vec::new();
-    let mut warnings = Vec::new();
+    let dry_run = dry_run.unwrap_or(false);
+
+    let mut tx = state.db.pool.begin().await.map_err(|e| e.to_string())?;
+    let mut result = run_student_import(&state.sheets_service, &state.identity_service, &mut *tx, &students_data, course_id, assignment_id).await?;
+    result.dry_run = dry_run;
+
+    if dry_run || !result.issues.is_empty() {
+        tx.rollback().await.map_err(|e| e.to_string())?;
+    } else {
+        tx.commit().await.map_err(|e| e.to_string())?;
+        result.committed = true;
+    }
+
+    Ok(result)
+}
+
+async fn run_student_import(
+    sheets_service: &SheetsService,
+    identity_service: &IdentityService,
+    conn: &mut sqlx::SqliteConnection,
+    students_data: &[StudentData],
+    course_id: Option<i64>,
+    assignment_id: Option<i64>,
+) -> Result<ImportResult, String> {
+    let mut result = ImportResult::default();
+
+    // Invalid rows are reported and never written. import_students_from_sheet then rolls the whole
+    // import back; the roster sync commits the remaining rows and retries these on the next run.
+    result.issues = sheets_service.validate_student_rows(students_data);
+
+    // Rows naming an assignment (one row per submission) must name one of the course's
+    let assignments = schema::get_assignments(&mut *conn, course_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    for (row, student) in students_data.iter().enumerate() {
+        if let Some(label) = &student.assignment {
+            if resolve_assignment(label, &assignments).is_none() {
+                result.issues.push(ImportIssue {
+                    row: Some(student.row_number(row)),
+                    column: Some("assignment".to_string()),
+                    reason: format!("Unknown assignment '{}'", label),
+                });
+            }
+        }
+    }
 
     // Convert to CreateStudent structs
-    let create_students = state.sheets_service.convert_to_create_students(&students_data);
+    let create_students = sheets_service.convert_to_create_students(students_data);
+
+    // Rows are matched against existing students (and earlier rows) so re-imports update instead of duplicating
+    let mut known_students = schema::get_all_students(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
     
-    // Import students
-    let mut student_ids = std::collections::HashMap::new();
-    for create_student in create_students {
-        match schema::create_student(&state.db.pool, create_student.clone()).await {
-            Ok(id) => {
-                student_ids.insert(create_student.name.clone(), id);
-                students_imported += 1;
+    // Import students; student_ids and result.rows are parallel to students_data
+    let mut student_ids: Vec<Option<i64>> = Vec::with_capacity(create_students.len());
+    for (row, mut create_student) in create_students.into_iter().enumerate() {
+        let row_num = students_data[row].row_number(row);
+        let mut outcome = ImportRowOutcome {
+            row: row_num,
+            student_name: create_student.name.clone(),
+            student: RecordChange::failed(),
+            projects: Vec::new(),
+            team: students_data[row].team.clone(),
+        };
+
+        if result.issues.iter().any(|issue| issue.row == Some(row_num)) {
+            student_ids.push(None);
+            result.rows.push(outcome);
+            continue;
+        }
+
+        // Link the free-text cohort to the course's cohort, creating it on first sight
+        if let (Some(course_id), Some(cohort)) = (course_id, create_student.cohort.as_deref()) {
+            if !cohort.trim().is_empty() {
+                match schema::find_or_create_cohort(&mut *conn, course_id, cohort).await {
+                    Ok(cohort_id) => create_student.cohort_id = Some(cohort_id),
+                    Err(e) => result.warnings.push(format!("Could not resolve cohort '{}' for {}: {}", cohort, create_student.name, e)),
+                }
+            }
+        }
+
+        let matched = identity_service.match_student(&create_student, &known_students);
+        let corroborated = match matched.as_ref().filter(|m| m.reason == MatchReason::ExactName && !m.is_ambiguous()) {
+            Some(found) => name_match_is_corroborated(&mut *conn, found.student_id, &students_data[row], course_id).await?,
+            None => false,
+        };
+        let matched = matched.map(|m| (m.is_confident() || corroborated, m));
+        if let Some((_, found)) = matched.as_ref().filter(|(accepted, _)| !accepted) {
+            let existing_names: Vec<String> = std::iter::once(found.student_id)
+                .chain(found.also_matched.iter().copied())
+                .filter_map(|id| known_students.iter().find(|s| s.id == id))
+                .map(|s| s.name.clone())
+                .collect();
+            result.warnings.push(format!(
+                "Row {}: {} looks like existing student {} ({:.0}% similar); imported separately, merge if they are the same person",
+                row_num, create_student.name, existing_names.join(" or "), found.similarity * 100.0
+            ));
+        }
+
+        let change = match matched.filter(|(accepted, _)| *accepted) {
+            Some((_, found)) => {
+                let changes = known_students
+                    .iter()
+                    .find(|s| s.id == found.student_id)
+                    .map(|existing| student_import_changes(existing, &create_student))
+                    .unwrap_or_default();
+
+                if changes.is_empty() {
+                    Ok(RecordChange { action: ImportAction::Skip, record_id: Some(found.student_id), changes })
+                } else {
+                    schema::update_student_from_import(&mut *conn, found.student_id, &create_student)
+                        .await
+                        .map(|_| RecordChange { action: ImportAction::Update, record_id: Some(found.student_id), changes })
+                }
+            }
+            None => schema::create_student(&mut *conn, create_student.clone())
+                .await
+                .map(|id| RecordChange {
+                    action: ImportAction::Create,
+                    record_id: Some(id),
+                    changes: new_record_fields(&[
+                        ("name", Some(create_student.name.clone())),
+                        ("email", create_student.email.clone()),
+                        ("github_username", create_student.github_username.clone()),
+                        ("cohort", create_student.cohort.clone()),
+                    ]),
+                }),
+        };
+
+        match change {
+            Ok(change) => {
+                match change.action {
+                    ImportAction::Create => result.students_imported += 1,
+                    ImportAction::Update => result.students_updated += 1,
+                    _ => {}
+                }
+
+                student_ids.push(change.record_id);
+                if let Some(id) = change.record_id {
+                    if let Ok(Some(student)) = schema::get_student_by_id(&mut *conn, id).await {
+                        known_students.retain(|s| s.id != id);
+                        known_students.push(student);
+                    }
+                }
+                outcome.student = change;
             }
             Err(e) => {
-                errors.push(format!("Failed to import student {}: {}", create_student.name, e));
+                student_ids.push(None);
+                result.issues.push(ImportIssue {
+                    row: Some(row_num),
+                    column: None,
+                    reason: format!("Failed to import student {}: {}", create_student.name, e),
+                });
             }
         }
+
+        result.rows.push(outcome);
     }
 
     // Import projects
-    let create_projects = state.sheets_service.convert_to_create_projects(&students_data, &student_ids);
-    for create_project in create_projects {
-        match schema::create_project(&state.db.pool, create_project.clone()).await {
-            Ok(_) => {
-                projects_imported += 1;
-            }
+    let create_projects = sheets_service.convert_to_create_projects(students_data, &student_ids, assignment_id, &assignments);
+    for (row, create_project) in create_projects {
+        let project_name = create_project.name.clone();
+        let change = match import_project(&mut *conn, create_project).await {
+            Ok(change) => change,
             Err(e) => {
-                errors.push(format!("Failed to import project {}: {}", create_project.name, e));
+                result.issues.push(ImportIssue {
+                    row: Some(students_data[row].row_number(row)),
+                    column: None,
+                    reason: format!("Failed to import project {}: {}", project_name, e),
+                });
+                RecordChange::failed()
             }
+        };
+
+        if change.action == ImportAction::Create {
+            result.projects_imported += 1;
         }
+        result.rows[row].projects.push(change);
     }
 
-    Ok(ImportResult {
-        students_imported,
-        projects_imported,
-        errors,
-        warnings,
-    })
+    // Import teams, each owning one group project
+    for team_data in sheets_service.group_teams(students_data) {
+        let member_ids: Vec<i64> = team_data.member_rows
+            .iter()
+            .filter_map(|row| student_ids.get(*row).copied().flatten())
+            .collect();
+        let first_row = team_data.member_rows.first().map(|row| students_data[*row].row_number(*row));
+        let mut outcome = TeamOutcome {
+            name: team_data.name.clone(),
+            team: RecordChange::failed(),
+            member_rows: team_data.member_rows.iter().map(|row| students_data[*row].row_number(*row)).collect(),
+            project: None,
+        };
+
+        let Some(&submitter_id) = member_ids.first() else {
+            result.warnings.push(format!("Team {} has no imported members, skipping", team_data.name));
+            outcome.team.action = ImportAction::Skip;
+            result.teams.push(outcome);
+            continue;
+        };
+
+        let member_name = |id: &i64| known_students.iter().find(|s| s.id == *id).map(|s| s.name.clone());
+
+        let existing = schema::find_team_by_name(&mut *conn, &team_data.name, assignment_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        let team_id = match existing {
+            Some(team) => {
+                let current = schema::get_team_member_ids(&mut *conn, team.id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                let mut changes = Vec::new();
+                for student_id in member_ids.iter().filter(|id| !current.contains(id)) {
+                    match schema::add_team_member(&mut *conn, team.id, *student_id, None).await {
+                        Ok(()) => changes.push(FieldChange {
+                            field: "member".to_string(),
+                            old_value: None,
+                            new_value: member_name(student_id),
+                        }),
+                        Err(e) => result.issues.push(ImportIssue {
+                            row: first_row,
+                            column: Some("team".to_string()),
+                            reason: format!("Failed to add member to team {}: {}", team_data.name, e),
+                        }),
+                    }
+                }
+
+                let action = if changes.is_empty() { ImportAction::Skip } else { ImportAction::Update };
+                outcome.team = RecordChange { action, record_id: Some(team.id), changes };
+                team.id
+            }
+            None => {
+                let create_team = crate::database::models::CreateTeam {
+                    name: team_data.name.clone(),
+                    course_id,
+                    assignment_id,
+                    member_ids: member_ids.clone(),
+                };
+                match schema::create_team(&mut *conn, create_team).await {
+                    Ok(id) => {
+                        result.teams_imported += 1;
+                        outcome.team = RecordChange {
+                            action: ImportAction::Create,
+                            record_id: Some(id),
+                            changes: member_ids
+                                .iter()
+                                .map(|id| FieldChange { field: "member".to_string(), old_value: None, new_value: member_name(id) })
+                                .collect(),
+                        };
+                        id
+                    }
+                    Err(e) => {
+                        result.issues.push(ImportIssue {
+                            row: first_row,
+                            column: Some("team".to_string()),
+                            reason: format!("Failed to import team {}: {}", team_data.name, e),
+                        });
+                        result.teams.push(outcome);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        match (team_data.project_name, team_data.github_url) {
+            (Some(project_name), Some(github_url)) => {
+                let create_project = crate::database::models::CreateProject {
+                    student_id: submitter_id,
+                    name: project_name.clone(),
+                    description: team_data.project_description,
+                    github_url,
+                    technology_stack: None,
+                    assignment_id,
+                    team_id: Some(team_id),
+                };
+                let change = match import_project(&mut *conn, create_project).await {
+                    Ok(change) => change,
+                    Err(e) => {
+                        result.issues.push(ImportIssue {
+                            row: first_row,
+                            column: None,
+                            reason: format!("Failed to import project {}: {}", project_name, e),
+                        });
+                        RecordChange::failed()
+                    }
+                };
+                if change.action == ImportAction::Create {
+                    result.projects_imported += 1;
+                }
+                outcome.project = Some(change);
+            }
+            _ => result.warnings.push(format!("Team {} has no project name or repository URL", team_data.name)),
+        }
+
+        result.teams.push(outcome);
+    }
+
+    result.errors = result.issues.iter().map(|issue| issue.to_string()).collect();
+
+    Ok(result)
 }
 
-#[tauri::command]
-pub async fn extract_spreadsheet_id(url: String, state: State<'_, AppState>) -> Result<Option<String>, String> {
-    Ok(state.sheets_service.extract_spreadsheet_id(&url))
+// A lone exact-name match is the same student when nothing contradicts it and something ties it to the row:
+// one of the row's repositories is already theirs, or they already belong to the import's course. A repository
+// owned by someone else contradicts it. Team repositories are owned by one member, so they decide nothing.
+async fn name_match_is_corroborated(
+    conn: &mut sqlx::SqliteConnection,
+    student_id: i64,
+    row: &StudentData,
+    course_id: Option<i64>,
+) -> Result<bool, String> {
+    let mut owns_repository = false;
+    if row.team.is_none() {
+        for github_url in row.github_url.iter().chain(row.extra_projects.iter().map(|project| &project.github_url)) {
+            let owners = schema::get_project_owner_ids_by_url(&mut *conn, github_url)
+                .await
+                .map_err(|e| e.to_string())?;
+            if owners.iter().any(|owner| *owner != student_id) {
+                return Ok(false);
+            }
+            owns_repository |= !owners.is_empty();
+        }
+    }
+    if owns_repository {
+        return Ok(true);
+    }
+
+    match course_id {
+        Some(course_id) => schema::is_student_in_course(&mut *conn, student_id, course_id)
+            .await
+            .map_err(|e| e.to_string()),
+        None => Ok(false),
+    }
 }
 
-// Project Management Commands
-#[tauri::command]
-pub async fn get_all_projects(state: State<'_, AppState>) -> Result<Vec<crate::database::models::ProjectWithStudent>, String> {
-    schema::get_projects_with_students(&state.db.pool)
+// Mirrors update_student_from_import: identity fields are only filled in, the cohort is replaced
+fn student_import_changes(
+    existing: &crate::database::models::Student,
+    incoming: &crate::database::models::CreateStudent,
+) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut fill = |field: &str, old: &Option<String>, new: &Option<String>, replace: bool| {
+        let wanted = new.as_ref().filter(|v| !v.trim().is_empty());
+        if wanted.is_some() && (old.is_none() || replace) && old.as_ref() != wanted {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                old_value: old.clone(),
+                new_value: wanted.cloned(),
+            });
+        }
+    };
+
+    fill("email", &existing.email, &incoming.email, false);
+    fill("github_username", &existing.github_username, &incoming.github_username, false);
+    fill("cohort", &existing.cohort, &incoming.cohort, true);
+
+    // Same cohort name but not yet linked (or linked to another course's cohort)
+    if incoming.cohort_id.is_some() && incoming.cohort_id != existing.cohort_id && !changes.iter().any(|c| c.field == "cohort") {
+        changes.push(FieldChange {
+            field: "cohort_id".to_string(),
+            old_value: existing.cohort_id.map(|id| id.to_string()),
+            new_value: incoming.cohort_id.map(|id| id.to_string()),
+        });
+    }
+
+    changes
+}
+
+fn new_record_fields(fields: &[(&str, Option<String>)]) -> Vec<FieldChange> {
+    fields
+        .iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(field, value)| FieldChange {
+            field: field.to_string(),
+            old_value: None,
+            new_value: value.clone(),
+        })
+        .collect()
+}
+
+// Skips a repository already on record for the assignment
+async fn import_project(
+    conn: &mut sqlx::SqliteConnection,
+    project: crate::database::models::CreateProject,
+) -> Result<RecordChange, String> {
+    let existing = schema::find_project_by_url(&mut *conn, &project.github_url, project.assignment_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(existing) = existing {
+        // A team can take over an individually imported project, never the other way round
+        if existing.team_id.is_none() && project.team_id.is_some() {
+            schema::set_project_team(&mut *conn, existing.id, project.team_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            return Ok(RecordChange {
+                action: ImportAction::Update,
+                record_id: Some(existing.id),
+                changes: vec![FieldChange {
+                    field: "team_id".to_string(),
+                    old_value: None,
+                    new_value: project.team_id.map(|id| id.to_string()),
+                }],
+            });
+        }
+        return Ok(RecordChange { action: ImportAction::Skip, record_id: Some(existing.id), changes: Vec::new() });
+    }
+
+    let changes = new_record_fields(&[
+        ("name", Some(project.name.clone())),
+        ("github_url", Some(project.github_url.clone())),
+        ("description", project.description.clone()),
+    ]);
+
+    schema::create_project(&mut *conn, project)
         .await
+        .map(|id| RecordChange { action: ImportAction::Create, record_id: Some(id), changes })
         .map_err(|e| e.to_string())
 }
 
+// Student Identity Commands
 #[tauri::command]
-pub async fn get_project_by_id(id: i64, state: State<'_, AppState>) -> Result<Option<crate::database::models::Project>, String> {
-    schema::get_project_by_id(&state.db.pool, id)
+pub async fn find_duplicate_students(
+    state: State<'_, AppState>
+) -> Result<Vec<DuplicateCandidate>, String> {
+    let students = schema::get_all_students(&state.db.pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    Ok(state.identity_service.find_duplicates(&students))
 }
 
 #[tauri::command]
-pub async fn update_project_status(
-    id: i64,
-    status: String,
+pub async fn merge_students(
+    surviving_id: i64,
+    merged_id: i64,
     state: State<'_, AppState>
-) -> Result<(), String> {
-    schema::update_project_status(&state.db.pool, id, &status)
+) -> Result<crate::database::models::Student, String> {
+    if surviving_id == merged_id {
+        return Err("Cannot merge a student into itself".to_string());
+    }
+
+    schema::get_student_by_id(&state.db.pool, surviving_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Surviving student not found".to_string())?;
+
+    schema::merge_students(&state.db.pool, surviving_id, merged_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    schema::get_student_by_id(&state.db.pool, surviving_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Surviving student not found".to_string())
+}
+
+// Roster Sync Commands
+const MIN_ROSTER_SYNC_INTERVAL_MINUTES: i64 = 5;
+const DEFAULT_ROSTER_SYNC_RUN_LIMIT: i64 = 20;
+
+fn validate_roster_source(source: &crate::database::models::CreateRosterSource) -> Result<(), String> {
+    serde_json::from_value::<SheetMapping>(source.mapping.clone())
+        .map_err(|e| format!("Invalid column mapping: {}", e))?;
+
+    if source.interval_minutes.is_some_and(|minutes| minutes < MIN_ROSTER_SYNC_INTERVAL_MINUTES) {
+        return Err(format!("Sync interval must be at least {} minutes", MIN_ROSTER_SYNC_INTERVAL_MINUTES));
+    }
+
+    Ok(())
 }
 
-// GitHub Integration Commands
 #[tauri::command]
-pub async fn get_repository_info(
-    repo_url: String,
+pub async fn create_roster_source(
+    source: crate::database::models::CreateRosterSource,
     state: State<'_, AppState>
-) -> Result<RepositoryInfo, String> {
-    let github_service = state.github_service.lock().await;
-    github_service
-        .get_repository_info(&repo_url)
+) -> Result<i64, String> {
+    validate_roster_source(&source)?;
+    schema::create_roster_source(&state.db.pool, source)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn clone_repository(
-    repo_url: String,
-    target_dir: String,
+pub async fn list_roster_sources(
     state: State<'_, AppState>
-) -> Result<String, String> {
-    let github_service = state.github_service.lock().await;
-    let target_path = std::path::Path::new(&target_dir);
-    let cloned_path = github_service
-        .clone_repository(&repo_url, target_path)
+) -> Result<Vec<crate::database::models::RosterSource>, String> {
+    schema::get_roster_sources(&state.db.pool)
         .await
-        .map_err(|e| e.to_string())?;
-    
-    Ok(cloned_path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn analyze_project_structure(
-    project_path: String,
+pub async fn update_roster_source(
+    id: i64,
+    source: crate::database::models::CreateRosterSource,
     state: State<'_, AppState>
-) -> Result<ProjectStructure, String> {
-    let github_service = state.github_service.lock().await;
-    let path = std::path::Path::new(&project_path);
-    github_service
-        .analyze_project_structure(path)
+) -> Result<(), String> {
+    validate_roster_source(&source)?;
+    schema::update_roster_source(&state.db.pool, id, source)
         .await
         .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn validate_github_url(url: String, state: State<'_, AppState>) -> Result<bool, String> {
-    let github_service = state.github_service.lock().await;
-    Ok(github_service.validate_github_url(&url))
+pub async fn delete_roster_source(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_roster_source(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
 }
 
-// Analysis Commands
 #[tauri::command]
-pub async fn analyze_project(
-    project_id: i64,
+pub async fn sync_roster_source(
+    id: i64,
     state: State<'_, AppState>
-) -> Result<crate::services::analysis_service::AnalysisResult, String> {
-    // Get project details
-    let project = schema::get_project_by_id(&state.db.pool, project_id)
+) -> Result<crate::database::models::RosterSyncRun, String> {
+    let source = schema::get_roster_source_by_id(&state.db.pool, id)
         .await
         .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Project not found".to_string())?;
+        .ok_or_else(|| "Roster source not found".to_string())?;
 
-    // Update project status to analyzing
-    schema::update_project_status(&state.db.pool, project_id, "analyzing")
+    run_roster_sync(&state, &source).await
+}
+
+#[tauri::command]
+pub async fn list_roster_sync_runs(
+    source_id: i64,
+    limit: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::RosterSyncRun>, String> {
+    schema::get_roster_sync_runs(&state.db.pool, source_id, limit.unwrap_or(DEFAULT_ROSTER_SYNC_RUN_LIMIT))
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    // Clone repository for analysis
-    let temp_dir = std::env::temp_dir().join(format!("r3viewer_analysis_{}", project_id));
-    std::fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+// Shared by the command and the background scheduler. Every attempt is recorded as a run, failed ones included.
+pub async fn run_roster_sync(
+    state: &AppState,
+    source: &crate::database::models::RosterSource,
+) -> Result<crate::database::models::RosterSyncRun, String> {
+    // A manual sync and a scheduled one must not reconcile the same rows at once
+    let _guard = state.roster_sync_lock.lock().await;
 
-    let github_service = state.github_service.lock().await;
-    let project_path = github_service
-        .clone_repository(&project.github_url, &temp_dir)
+    let mut run = crate::database::models::CreateRosterSyncRun {
+        source_id: source.id,
+        added: 0,
+        changed: 0,
+        removed: 0,
+        unchanged: 0,
+        written_back: 0,
+        conflicts: Vec::new(),
+        errors: Vec::new(),
+        started_at: chrono::Utc::now(),
+    };
+
+    let failure = reconcile_roster(state, source, &mut run).await.err();
+    if let Some(error) = &failure {
+        // The transaction was rolled back, so none of the counted rows were actually synced
+        run.added = 0;
+        run.changed = 0;
+        run.removed = 0;
+        run.unchanged = 0;
+        run.written_back = 0;
+        run.conflicts.clear();
+        run.errors = vec![error.clone()];
+    }
+
+    schema::set_roster_source_sync_result(&state.db.pool, source.id, run.errors.first().map(String::as_str))
+        .await
+        .map_err(|e| e.to_string())?;
+    let run_id = schema::create_roster_sync_run(&state.db.pool, run)
         .await
         .map_err(|e| e.to_string())?;
 
-    // Detect technology stack
-    let repo_info = github_service
-        .get_repository_info(&project.github_url)
+    if let Some(error) = failure {
+        return Err(error);
+    }
+
+    schema::get_roster_sync_run_by_id(&state.db.pool, run_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Roster sync run not found".to_string())
+}
+
+// Compares the sheet with the rows stored by the previous sync. New rows go through the regular import, changed
+// rows are merged field by field, and rows that disappeared are only flagged: local students and projects are never
+// deleted by a sync. Team membership changes are left to a manual import.
+async fn reconcile_roster(
+    state: &AppState,
+    source: &crate::database::models::RosterSource,
+    run: &mut crate::database::models::CreateRosterSyncRun,
+) -> Result<(), String> {
+    let mapping: SheetMapping = serde_json::from_str(&source.mapping)
+        .map_err(|e| format!("Invalid column mapping: {}", e))?;
+    let policy: crate::database::models::ConflictPolicy = source.conflict_policy.parse()?;
+
+    let sheet = state.sheets_service
+        .get_sheet_data(&source.spreadsheet_id, &source.range)
+        .await
+        .map_err(|e| e.to_string())?;
+    let rows = roster_row_keys(
+        state.sheets_service
+            .parse_student_data(&sheet, &mapping)
+            .map_err(|e| e.to_string())?,
+    );
+    let current_keys: std::collections::HashSet<String> = rows.iter().map(|(key, _)| key.clone()).collect();
+
+    let mut tx = state.db.pool.begin().await.map_err(|e| e.to_string())?;
+    let previous: std::collections::HashMap<String, crate::database::models::RosterSourceRow> =
+        schema::get_roster_source_rows(&mut *tx, source.id)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|row| (row.row_key.clone(), row))
+            .collect();
+    let assignments = schema::get_assignments(&mut *tx, source.course_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Editing a row's email or username in the sheet changes its key. The students of rows that vanished from the
+    // sheet are candidates for the new keys, so such an edit is merged as a change instead of a removal plus an add.
+    let mut vanished: Vec<&crate::database::models::RosterSourceRow> = previous
+        .values()
+        .filter(|prev| prev.student_id.is_some() && !current_keys.contains(&prev.row_key))
+        .collect();
+    vanished.sort_by_key(|prev| prev.row_number);
+    let mut vanished_students = Vec::new();
+    for student_id in vanished.iter().filter_map(|prev| prev.student_id) {
+        if vanished_students.iter().any(|s: &crate::database::models::Student| s.id == student_id) {
+            continue;
+        }
+        if let Some(student) = schema::get_student_by_id(&mut *tx, student_id).await.map_err(|e| e.to_string())? {
+            vanished_students.push(student);
+        }
+    }
+    let mut rekeyed: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    // Rows to store as the new baseline: key, row, student id, project id
+    let mut synced: Vec<(String, StudentData, Option<i64>, Option<i64>)> = Vec::new();
+    let mut new_rows: Vec<(String, StudentData)> = Vec::new();
+
+    for (key, row) in rows {
+        let known = match previous.get(&key).and_then(|prev| prev.student_id.map(|id| (prev, id))) {
+            Some(known) => Some(known),
+            None => {
+                let candidate = state.sheets_service.convert_to_create_students(std::slice::from_ref(&row)).pop();
+                let matched = candidate
+                    .and_then(|candidate| state.identity_service.match_student(&candidate, &vanished_students))
+                    .filter(|found| found.is_confident());
+                let prev = matched.and_then(|found| {
+                    vanished
+                        .iter()
+                        .find(|prev| prev.student_id == Some(found.student_id) && !rekeyed.contains(&prev.row_key))
+                        .copied()
+                });
+                match prev {
+                    Some(prev) => {
+                        schema::rename_roster_source_row_key(&mut *tx, source.id, &prev.row_key, &key)
+                            .await
+                            .map_err(|e| e.to_string())?;
+                        rekeyed.insert(prev.row_key.clone());
+                        prev.student_id.map(|id| (prev, id))
+                    }
+                    None => None,
+                }
+            }
+        };
+        let Some((prev, student_id)) = known else {
+            new_rows.push((key, row));
+            continue;
+        };
+
+        let base: StudentData = serde_json::from_str(&prev.row_data).map_err(|e| e.to_string())?;
+        let unchanged = roster_row_fields(&base) == roster_row_fields(&row) && base.extra_projects == row.extra_projects;
+        let project_id = if unchanged {
+            if prev.removed_at.is_some() {
+                run.added += 1;
+            } else {
+                run.unchanged += 1;
+            }
+            prev.project_id
+        } else {
+            let assignment_id = match row.assignment.as_deref() {
+                Some(label) => match resolve_assignment(label, &assignments) {
+                    Some(id) => Some(id),
+                    None => {
+                        // Keep the old baseline so the row is merged once the assignment exists
+                        let row_number = row.source_row.unwrap_or_default();
+                        run.errors.push(format!("Row {}: unknown assignment '{}'", row_number, label));
+                        synced.push((key, StudentData { source_row: row.source_row, ..base }, Some(student_id), prev.project_id));
+                        continue;
+                    }
+                },
+                None => source.assignment_id,
+            };
+            run.changed += 1;
+            merge_roster_row(&mut *tx, source, policy, &key, &base, &row, student_id, prev.project_id, assignment_id, run).await?
+        };
+
+        synced.push((key, row, Some(student_id), project_id));
+    }
+
+    if !new_rows.is_empty() {
+        let students_data: Vec<StudentData> = new_rows.iter().map(|(_, row)| row.clone()).collect();
+        let result = run_student_import(&state.sheets_service, &state.identity_service, &mut *tx, &students_data, source.course_id, source.assignment_id).await?;
+        run.errors.extend(result.issues.iter().map(|issue| issue.to_string()));
+
+        // Rows that failed to import are not stored, so the next sync retries them
+        for ((key, row), outcome) in new_rows.into_iter().zip(&result.rows) {
+            if let Some(student_id) = outcome.student.record_id {
+                run.added += 1;
+                // Only the row's own repository is tracked; a row with extra repositories alone has none
+                let project_id = has_primary_project(&row)
+                    .then(|| outcome.projects.first().and_then(|project| project.record_id))
+                    .flatten();
+                synced.push((key, row, Some(student_id), project_id));
+            }
+        }
+    }
+
+    for (key, prev) in &previous {
+        if prev.removed_at.is_none() && !current_keys.contains(key) && !rekeyed.contains(key) {
+            schema::mark_roster_source_row_removed(&mut *tx, source.id, key)
+                .await
+                .map_err(|e| e.to_string())?;
+            run.removed += 1;
+        }
+    }
+
+    for (key, row, student_id, project_id) in &synced {
+        let row_data = serde_json::to_value(row).map_err(|e| e.to_string())?;
+        let row_number = row.source_row.unwrap_or_default() as i64;
+        schema::upsert_roster_source_row(&mut *tx, source.id, key, row_number, &row_data, *student_id, *project_id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    // The roster itself is in sync at this point; a failed write-back is reported without failing the run
+    if source.status_column.is_some() || source.score_column.is_some() {
+        match write_back_roster(state, source, &sheet, &synced).await {
+            Ok(written) => run.written_back = written as i64,
+            Err(e) => run.errors.push(format!("Write-back failed: {}", e)),
+        }
+    }
+
+    Ok(())
+}
+
+// Identifies a sheet row across syncs even when rows are inserted or re-sorted: email, else GitHub username,
+// else name. Repeated keys (one student on several rows) are told apart by occurrence.
+fn roster_row_keys(rows: Vec<StudentData>) -> Vec<(String, StudentData)> {
+    let mut occurrences: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+
+    rows.into_iter()
+        .map(|row| {
+            let base = match (&row.email, &row.github_username) {
+                (Some(email), _) => format!("email:{}", email.trim().to_lowercase()),
+                (None, Some(username)) => format!("github:{}", username.trim().trim_start_matches('@').to_lowercase()),
+                (None, None) => format!("name:{}", crate::services::identity_service::normalize_name(&row.name)),
+            };
+            let count = occurrences.entry(base.clone()).or_insert(0);
+            *count += 1;
+            let key = if *count == 1 { base } else { format!("{}#{}", base, count) };
+            (key, row)
+        })
+        .collect()
+}
+
+// Whether the row's own repository columns yield a project, which convert_to_create_projects then lists first
+fn has_primary_project(row: &StudentData) -> bool {
+    row.team.is_none() && (row.github_url.is_some() || (row.github_username.is_some() && row.project_name.is_some()))
+}
+
+// The synced fields of a row; the row number is deliberately left out so re-sorting the sheet is not a change
+fn roster_row_fields(row: &StudentData) -> [Option<&str>; 9] {
+    [
+        Some(row.name.as_str()),
+        row.email.as_deref(),
+        row.github_username.as_deref(),
+        row.github_url.as_deref(),
+        row.project_name.as_deref(),
+        row.project_description.as_deref(),
+        row.cohort.as_deref(),
+        row.team.as_deref(),
+        row.assignment.as_deref(),
+    ]
+}
+
+// Three-way merge of one changed row. `base` is the row as last synced and the local records are the third side:
+// a field edited only in the sheet is applied, one edited on both sides is settled by the policy and reported.
+// Returns the row's project id, which is new when the sheet has just gained a repository. New projects go to
+// `assignment_id`, the row's own assignment or else the source's.
+#[allow(clippy::too_many_arguments)]
+async fn merge_roster_row(
+    conn: &mut sqlx::SqliteConnection,
+    source: &crate::database::models::RosterSource,
+    policy: crate::database::models::ConflictPolicy,
+    key: &str,
+    base: &StudentData,
+    incoming: &StudentData,
+    student_id: i64,
+    project_id: Option<i64>,
+    assignment_id: Option<i64>,
+    run: &mut crate::database::models::CreateRosterSyncRun,
+) -> Result<Option<i64>, String> {
+    let row_number = incoming.source_row.unwrap_or_default();
+    let Some(student) = schema::get_student_by_id(&mut *conn, student_id).await.map_err(|e| e.to_string())? else {
+        run.errors.push(format!("Row {}: the student imported from this row no longer exists", row_number));
+        return Ok(project_id);
+    };
+
+    let mut merge = |field: &str, base: Option<&str>, sheet: Option<&str>, local: Option<&str>| -> Option<Option<String>> {
+        if sheet == base || sheet == local {
+            return None;
+        }
+        let take_sheet = local == base || policy == crate::database::models::ConflictPolicy::SheetWins;
+        if local != base {
+            run.conflicts.push(serde_json::json!({
+                "row": row_number,
+                "row_key": key,
+                "field": field,
+                "base": base,
+                "sheet": sheet,
+                "local": local,
+                "resolution": policy.as_str(),
+            }));
+        }
+        take_sheet.then(|| sheet.map(str::to_string))
+    };
+
+    let name = merge("name", Some(base.name.as_str()), Some(incoming.name.as_str()), Some(student.name.as_str()));
+    let email = merge("email", base.email.as_deref(), incoming.email.as_deref(), student.email.as_deref());
+    let github_username = merge(
+        "github_username",
+        base.github_username.as_deref(),
+        incoming.github_username.as_deref(),
+        student.github_username.as_deref(),
+    );
+    let cohort = merge("cohort", base.cohort.as_deref(), incoming.cohort.as_deref(), student.cohort.as_deref());
+
+    let project = match project_id {
+        Some(id) => schema::get_project_by_id(&mut *conn, id).await.map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let project_changes = project.as_ref().map(|project| {
+        (
+            merge("project_name", base.project_name.as_deref(), incoming.project_name.as_deref(), Some(project.name.as_str())),
+            merge(
+                "project_description",
+                base.project_description.as_deref(),
+                incoming.project_description.as_deref(),
+                project.description.as_deref(),
+            ),
+            merge("github_url", base.github_url.as_deref(), incoming.github_url.as_deref(), Some(project.github_url.as_str())),
+        )
+    });
+
+    if name.is_some() || email.is_some() || github_username.is_some() || cohort.is_some() {
+        let mut updated = crate::database::models::CreateStudent {
+            name: name.flatten().unwrap_or(student.name.clone()),
+            email: email.unwrap_or(student.email.clone()),
+            github_username: github_username.unwrap_or(student.github_username.clone()),
+            cohort: cohort.clone().unwrap_or(student.cohort.clone()),
+            cohort_id: None,
+        };
+        if let (Some(course_id), Some(Some(cohort))) = (source.course_id, &cohort) {
+            match schema::find_or_create_cohort(&mut *conn, course_id, cohort).await {
+                Ok(cohort_id) => updated.cohort_id = Some(cohort_id),
+                Err(e) => run.errors.push(format!("Row {}: could not resolve cohort '{}': {}", row_number, cohort, e)),
+            }
+        }
+        if let Err(e) = schema::update_student_details(&mut *conn, student_id, &updated).await {
+            run.errors.push(format!("Row {}: failed to update student {}: {}", row_number, student.name, e));
+        }
+    }
+
+    let project_id = match (project, project_changes) {
+        (Some(project), Some((name, description, github_url))) => {
+            if name.is_some() || description.is_some() || github_url.is_some() {
+                // Name and repository are required locally, so clearing them in the sheet keeps the local value
+                let name = name.flatten().unwrap_or(project.name.clone());
+                let description = description.unwrap_or(project.description.clone());
+                let github_url = github_url.flatten().unwrap_or(project.github_url.clone());
+                if let Err(e) = schema::update_project_details(&mut *conn, project.id, &name, description.as_deref(), &github_url).await {
+                    run.errors.push(format!("Row {}: failed to update project {}: {}", row_number, project.name, e));
+                }
+            }
+            Some(project.id)
+        }
+        _ => match (&incoming.team, &incoming.github_url) {
+            (None, Some(github_url)) => {
+                let project = ProjectData {
+                    name: incoming.project_name.clone(),
+                    description: incoming.project_description.clone(),
+                    github_url: github_url.clone(),
+                    assignment_id,
+                };
+                import_roster_project(&mut *conn, student_id, &project, row_number, run).await.or(project_id)
+            }
+            _ => project_id,
+        },
+    };
+
+    // Repositories added to the extra columns since the last sync; edits to ones already imported are left alone
+    if incoming.team.is_none() {
+        for project in incoming.extra_projects.iter().filter(|p| !base.extra_projects.iter().any(|b| b.github_url == p.github_url)) {
+            let project = ProjectData { assignment_id: project.assignment_id.or(assignment_id), ..project.clone() };
+            import_roster_project(&mut *conn, student_id, &project, row_number, run).await;
+        }
+    }
+
+    Ok(project_id)
+}
+
+async fn import_roster_project(
+    conn: &mut sqlx::SqliteConnection,
+    student_id: i64,
+    project: &ProjectData,
+    row_number: usize,
+    run: &mut crate::database::models::CreateRosterSyncRun,
+) -> Option<i64> {
+    let name = project.name.clone().or_else(|| repository_name(&project.github_url))?;
+    let create_project = crate::database::models::CreateProject {
+        student_id,
+        name: name.clone(),
+        description: project.description.clone(),
+        github_url: project.github_url.clone(),
+        technology_stack: None,
+        assignment_id: project.assignment_id,
+        team_id: None,
+    };
+
+    match import_project(&mut *conn, create_project).await {
+        Ok(change) => change.record_id,
+        Err(e) => {
+            run.errors.push(format!("Row {}: failed to import project {}: {}", row_number, name, e));
+            None
+        }
+    }
+}
+
+// Writes project status and score into the configured columns, touching only cells whose value differs
+async fn write_back_roster(
+    state: &AppState,
+    source: &crate::database::models::RosterSource,
+    sheet: &SheetData,
+    synced: &[(String, StudentData, Option<i64>, Option<i64>)],
+) -> Result<usize, String> {
+    let column_index = |column: &Option<String>| -> Result<Option<(String, usize)>, String> {
+        match column {
+            Some(column) => sheet.headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(column.trim()))
+                .map(|index| Some((column.clone(), index)))
+                .ok_or_else(|| format!("Column '{}' not found in the roster", column)),
+            None => Ok(None),
+        }
+    };
+    let status_column = column_index(&source.status_column)?;
+    let score_column = column_index(&source.score_column)?;
+
+    let mut writes = Vec::new();
+    for (_, row, _, project_id) in synced {
+        let (Some(project_id), Some(row_number)) = (project_id, row.source_row) else {
+            continue;
+        };
+        let Some(project) = schema::get_project_by_id(&state.db.pool, *project_id).await.map_err(|e| e.to_string())? else {
+            continue;
+        };
+
+        let current = |index: usize| {
+            row_number
+                .checked_sub(2)
+                .and_then(|offset| sheet.rows.get(offset))
+                .and_then(|cells| cells.get(index))
+                .map(|cell| cell.trim().to_string())
+                .unwrap_or_default()
+        };
+
+        if let Some((column, index)) = &status_column {
+            if current(*index) != project.status {
+                writes.push(RosterCellWrite { row: row_number, column: column.clone(), value: project.status.clone() });
+            }
+        }
+
+        if let Some((column, index)) = &score_column {
+            let final_grade = schema::get_final_grade(&state.db.pool, project.id)
+                .await
+                .map_err(|e| e.to_string())?;
+            // Same rule as the exports: a final grade leaves the app only once released, and until then
+            // the cell keeps whatever it holds
+            let score = match final_grade {
+                Some(grade) => grade.released_at.map(|_| grade.final_score),
+                None => schema::get_analysis_by_project_id(&state.db.pool, project.id)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .and_then(|analysis| analysis.total_score),
+            };
+            if let Some(score) = score.map(|score| score.to_string()) {
+                if current(*index) != score {
+                    writes.push(RosterCellWrite { row: row_number, column: column.clone(), value: score });
+                }
+            }
+        }
+    }
+
+    state.sheets_service
+        .write_roster_values(&source.spreadsheet_id, &source.range, &sheet.headers, &writes)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn extract_spreadsheet_id(url: String, state: State<'_, AppState>) -> Result<Option<String>, String> {
+    Ok(state.sheets_service.extract_spreadsheet_id(&url))
+}
+
+// Project Management Commands
+#[tauri::command]
+pub async fn get_all_projects(state: State<'_, AppState>) -> Result<Vec<crate::database::models::ProjectWithStudent>, String> {
+    schema::get_projects_with_students(&state.db.pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_projects(
+    query: crate::database::models::ProjectSearchQuery,
+    state: State<'_, AppState>
+) -> Result<crate::database::models::ProjectSearchPage, String> {
+    if let (Some(min), Some(max)) = (query.min_score, query.max_score) {
+        if min > max {
+            return Err("Minimum score cannot be above the maximum score".to_string());
+        }
+    }
+
+    if let Some(statuses) = &query.statuses {
+        for status in statuses {
+            status.parse::<ProjectStatus>()?;
+        }
+    }
+
+    schema::search_projects(&state.db.pool, &query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_project_by_id(id: i64, state: State<'_, AppState>) -> Result<Option<crate::database::models::Project>, String> {
+    schema::get_project_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Every status change goes through here so the review workflow can't be skipped
+async fn transition_project(
+    state: &AppState,
+    project_id: i64,
+    next: ProjectStatus,
+    note: Option<&str>,
+) -> Result<(), String> {
+    let current = check_transition(state, project_id, next).await?;
+
+    let updated = schema::transition_project_status(&state.db.pool, project_id, current.as_str(), next.as_str(), note)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    ensure_transitioned(updated)
+}
+
+// Returns the current status the move to `next` starts from
+async fn check_transition(state: &AppState, project_id: i64, next: ProjectStatus) -> Result<ProjectStatus, String> {
+    let project = schema::get_project_by_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    let current: ProjectStatus = project.status.parse()?;
+    if !current.can_transition_to(next) {
+        return Err(format!(
+            "Cannot move project from '{}' to '{}'",
+            current.as_str(),
+            next.as_str()
+        ));
+    }
+
+    Ok(current)
+}
+
+fn ensure_transitioned(updated: bool) -> Result<(), String> {
+    if !updated {
+        return Err("Project status changed concurrently, reload and try again".to_string());
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_project_status(
+    id: i64,
+    status: String,
+    note: Option<String>,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let next: ProjectStatus = status.parse()?;
+
+    // Grading has its own commands so the final grade is always recorded alongside the state
+    if matches!(next, ProjectStatus::Graded | ProjectStatus::Released) {
+        return Err(format!("Use the grading commands to move a project to '{}'", next.as_str()));
+    }
+
+    transition_project(&state, id, next, note.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_allowed_status_transitions(
+    id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<ProjectStatus>, String> {
+    let project = schema::get_project_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    let current: ProjectStatus = project.status.parse()?;
+    Ok(current.allowed_transitions().to_vec())
+}
+
+#[tauri::command]
+pub async fn get_project_status_history(
+    id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::ProjectStatusChange>, String> {
+    schema::get_project_status_history(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn assign_project_to_assignment(
+    project_id: i64,
+    assignment_id: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    if let Some(assignment_id) = assignment_id {
+        schema::get_assignment_by_id(&state.db.pool, assignment_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Assignment not found".to_string())?;
+    }
+
+    schema::set_project_assignment(&state.db.pool, project_id, assignment_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Team Commands
+#[tauri::command]
+pub async fn create_team(
+    team: crate::database::models::CreateTeam,
+    state: State<'_, AppState>
+) -> Result<i64, String> {
+    if team.name.trim().is_empty() {
+        return Err("Team name is required".to_string());
+    }
+
+    let mut conn = state.db.pool.acquire().await.map_err(|e| e.to_string())?;
+    schema::create_team(&mut conn, team)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_teams(
+    assignment_id: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::Team>, String> {
+    schema::get_teams(&state.db.pool, assignment_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_team(id: i64, name: String, state: State<'_, AppState>) -> Result<(), String> {
+    schema::rename_team(&state.db.pool, id, &name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_team(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_team(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_team_members(
+    team_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::TeamMemberWithStudent>, String> {
+    schema::get_team_members(&state.db.pool, team_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_team_member(
+    team_id: i64,
+    student_id: i64,
+    role: Option<String>,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    schema::add_team_member(&state.db.pool, team_id, student_id, role.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_team_member(
+    team_id: i64,
+    student_id: i64,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    schema::remove_team_member(&state.db.pool, team_id, student_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn assign_project_to_team(
+    project_id: i64,
+    team_id: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    if let Some(team_id) = team_id {
+        schema::get_team_by_id(&state.db.pool, team_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Team not found".to_string())?;
+    }
+
+    schema::set_project_team(&state.db.pool, project_id, team_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_member_grade_weight(
+    team_id: i64,
+    student_id: i64,
+    grade_weight: f64,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    if !(0.0..=2.0).contains(&grade_weight) {
+        return Err("Grade weight must be between 0 and 2".to_string());
+    }
+
+    schema::set_team_member_grade_weight(&state.db.pool, team_id, student_id, grade_weight)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn compute_project_contributions(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::ProjectContribution>, String> {
+    let project = schema::get_project_by_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    // Team members, or the single owner for individual projects
+    let students = match project.team_id {
+        Some(team_id) => schema::get_team_students(&state.db.pool, team_id)
+            .await
+            .map_err(|e| e.to_string())?,
+        None => schema::get_student_by_id(&state.db.pool, project.student_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect(),
+    };
+
+    let temp_dir = std::env::temp_dir().join(format!("r3viewer_contributions_{}", project_id));
+    std::fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+
+    let github_service = state.github_service.lock().await;
+    let project_path = github_service
+        .clone_repository(&project.github_url, &temp_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let contributions = github_service
+        .collect_contributions(&project_path)
+        .map(|mut contributions| {
+            github_service.attribute_contributions(&mut contributions, &students);
+            contributions
+        });
+
+    drop(github_service); // Release the lock
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    let create_contributions = contributions
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| crate::database::models::CreateProjectContribution {
+            project_id,
+            student_id: c.student_id,
+            author_name: c.author_name,
+            author_email: c.author_email,
+            commits: c.commits,
+            lines_added: c.lines_added,
+            lines_deleted: c.lines_deleted,
+            files_touched: c.files_touched,
+            share: c.share,
+            first_commit_at: c.first_commit_at,
+            last_commit_at: c.last_commit_at,
+        })
+        .collect();
+
+    schema::replace_project_contributions(&state.db.pool, project_id, create_contributions)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    schema::get_project_contributions(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_project_contributions(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::ProjectContribution>, String> {
+    schema::get_project_contributions(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Splits a group project's score across members using their grade weights.
+// The suggested weight scales each member's share against an even split, capped at 1.
+#[tauri::command]
+pub async fn get_member_grades(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::MemberGrade>, String> {
+    let project = schema::get_project_by_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    let team_id = project.team_id
+        .ok_or_else(|| "Project is not a group project".to_string())?;
+
+    let members = schema::get_team_members(&state.db.pool, team_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let contributions = schema::get_project_contributions(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let total_score = schema::get_analysis_by_project_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|analysis| analysis.total_score);
+
+    let member_count = members.len().max(1) as f64;
+
+    Ok(members
+        .into_iter()
+        .map(|member| {
+            let contribution_share = if contributions.is_empty() {
+                None
+            } else {
+                Some(contributions
+                    .iter()
+                    .filter(|c| c.student_id == Some(member.student_id))
+                    .map(|c| c.share)
+                    .sum::<f64>())
+            };
+
+            crate::database::models::MemberGrade {
+                student_id: member.student_id,
+                student_name: member.student_name,
+                grade_weight: member.grade_weight,
+                contribution_share,
+                suggested_weight: contribution_share.map(|share| (share * member_count).min(1.0)),
+                adjusted_score: total_score.map(|score| ((score as f64 * member.grade_weight).round() as i32).clamp(0, 100)),
+            }
+        })
+        .collect())
+}
+
+// Course & Assignment Commands
+#[tauri::command]
+pub async fn create_course(
+    course: crate::database::models::CreateCourse,
+    state: State<'_, AppState>
+) -> Result<i64, String> {
+    schema::create_course(&state.db.pool, course)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_courses(
+    include_archived: Option<bool>,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::Course>, String> {
+    schema::get_courses(&state.db.pool, include_archived.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_course(
+    id: i64,
+    course: crate::database::models::CreateCourse,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    schema::update_course(&state.db.pool, id, course)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_course(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_course(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_cohort(
+    cohort: crate::database::models::CreateCohort,
+    state: State<'_, AppState>
+) -> Result<i64, String> {
+    schema::get_course_by_id(&state.db.pool, cohort.course_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Course not found".to_string())?;
+
+    schema::create_cohort(&state.db.pool, cohort)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_cohorts(
+    course_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::Cohort>, String> {
+    schema::get_cohorts_by_course_id(&state.db.pool, course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_cohort(
+    id: i64,
+    cohort: crate::database::models::CreateCohort,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    schema::update_cohort(&state.db.pool, id, cohort)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_cohort(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_cohort(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_assignment(
+    assignment: crate::database::models::CreateAssignment,
+    state: State<'_, AppState>
+) -> Result<i64, String> {
+    schema::get_course_by_id(&state.db.pool, assignment.course_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Course not found".to_string())?;
+
+    schema::create_assignment(&state.db.pool, assignment)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_assignment_by_id(
+    id: i64,
+    state: State<'_, AppState>
+) -> Result<Option<crate::database::models::Assignment>, String> {
+    schema::get_assignment_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_assignments(
+    course_id: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::Assignment>, String> {
+    schema::get_assignments(&state.db.pool, course_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_assignment(
+    id: i64,
+    assignment: crate::database::models::CreateAssignment,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    schema::update_assignment(&state.db.pool, id, assignment)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_assignment(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_assignment(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_assignment_submissions(
+    assignment_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::AssignmentSubmission>, String> {
+    schema::get_assignment_submissions(&state.db.pool, assignment_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// GitHub Integration Commands
+#[tauri::command]
+pub async fn get_repository_info(
+    repo_url: String,
+    state: State<'_, AppState>
+) -> Result<RepositoryInfo, String> {
+    let github_service = state.github_service.lock().await;
+    github_service
+        .get_repository_info(&repo_url)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn clone_repository(
+    repo_url: String,
+    target_dir: String,
+    state: State<'_, AppState>
+) -> Result<String, String> {
+    let github_service = state.github_service.lock().await;
+    let target_path = std::path::Path::new(&target_dir);
+    let cloned_path = github_service
+        .clone_repository(&repo_url, target_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(cloned_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn analyze_project_structure(
+    project_path: String,
+    state: State<'_, AppState>
+) -> Result<ProjectStructure, String> {
+    let github_service = state.github_service.lock().await;
+    let path = std::path::Path::new(&project_path);
+    github_service
+        .analyze_project_structure(path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn validate_github_url(url: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let github_service = state.github_service.lock().await;
+    Ok(github_service.validate_github_url(&url))
+}
+
+// Analysis Commands
+#[tauri::command]
+pub async fn analyze_project(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<crate::services::analysis_service::AnalysisResult, String> {
+    // Get project details
+    let project = schema::get_project_by_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    // Update project status to analyzing
+    transition_project(&state, project_id, ProjectStatus::Analyzing, None).await?;
+
+    // Clone repository for analysis
+    let temp_dir = std::env::temp_dir().join(format!("r3viewer_analysis_{}", project_id));
+
+    let outcome = run_analysis(&state, project_id, &project, &temp_dir).await;
+
+    // Cleanup
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    match outcome {
+        Ok(analysis_result) => {
+            transition_project(&state, project_id, ProjectStatus::Analyzed, None).await?;
+            Ok(analysis_result)
+        }
+        Err(e) => {
+            let _ = transition_project(&state, project_id, ProjectStatus::Failed, Some(&e)).await;
+            Err(e)
+        }
+    }
+}
+
+async fn run_analysis(
+    state: &AppState,
+    project_id: i64,
+    project: &crate::database::models::Project,
+    temp_dir: &std::path::Path,
+) -> Result<crate::services::analysis_service::AnalysisResult, String> {
+    std::fs::create_dir_all(temp_dir).map_err(|e| e.to_string())?;
+
+    let github_service = state.github_service.lock().await;
+    let project_path = github_service
+        .clone_repository(&project.github_url, temp_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Detect technology stack
+    let repo_info = github_service
+        .get_repository_info(&project.github_url)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    drop(github_service); // Release the lock
+
+    // Feature completeness uses the latest run of each scenario, if any
+    let smoke_reports = schema::get_latest_smoke_test_runs(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|run| run.results)
+        .filter_map(|json| serde_json::from_str::<SmokeTestReport>(&json).ok())
+        .collect();
+    let smoke_results = SmokeTestReport::combine(smoke_reports);
+
+    // Perform analysis
+    let analysis_result = state.analysis_service
+        .analyze_project(&project_path, &repo_info.technology_stack, smoke_results.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // The analysis is stored either way; without a commit it just can't anchor review comments
+    let head_sha = match state.github_service.lock().await.head_commit_sha(&project_path) {
+        Ok(sha) => Some(sha),
+        Err(e) => {
+            eprintln!("⚠️  Failed to read the HEAD commit for project {}: {}", project_id, e);
+            None
+        }
+    };
+
+    // Kept on the project so searches can filter by stack; the analysis is stored even if this fails
+    if let Err(e) = schema::set_project_technology_stack(&state.db.pool, project_id, &repo_info.technology_stack).await {
+        eprintln!("⚠️  Failed to record the technology stack for project {}: {}", project_id, e);
+    }
+
+    // Lateness is judged on the analyzed commit, so a failure here only leaves the submission time unknown
+    match state.github_service.lock().await.head_commit_time(&project_path) {
+        Ok(submitted_at) => {
+            if let Err(e) = schema::set_project_submitted_at(&state.db.pool, project_id, submitted_at).await {
+                eprintln!("⚠️  Failed to record submission time for project {}: {}", project_id, e);
+            }
+        }
+        Err(e) => eprintln!("⚠️  Failed to read the commit time for project {}: {}", project_id, e),
+    }
+
+    let rubric_version = match project.assignment_id {
+        Some(assignment_id) => schema::get_assignment_by_id(&state.db.pool, assignment_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .and_then(|assignment| assignment.rubric)
+            .map(|rubric| state.analysis_service.rubric_version(&rubric)),
+        None => None,
+    };
+
+    // Save analysis results
+    let mut create_analysis = state.analysis_service
+        .convert_to_create_analysis_result(project_id, &analysis_result);
+    create_analysis.commit_sha = head_sha.clone();
+    create_analysis.rubric_version = rubric_version;
+
+    schema::create_analysis_result(&state.db.pool, create_analysis)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Carry review comments over to the commit that was just analyzed; the analysis itself stands either way
+    if let Some(head_sha) = &head_sha {
+        if let Err(e) = reanchor_review_comments(state, project_id, &project_path, head_sha).await {
+            eprintln!("⚠️  Failed to re-anchor review comments for project {}: {}", project_id, e);
+        }
+    }
+
+    Ok(analysis_result)
+}
+
+async fn reanchor_review_comments(
+    state: &AppState,
+    project_id: i64,
+    repo_path: &std::path::Path,
+    head_sha: &str,
+) -> Result<(), String> {
+    let comments = schema::get_review_comments_by_project_id(&state.db.pool, project_id, false)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let github_service = state.github_service.lock().await;
+    for comment in comments.into_iter().filter(|c| c.commit_sha != head_sha) {
+        // A failed lookup says nothing about the line, so the comment keeps its anchor until the next analysis
+        let anchor = match github_service
+            .reanchor_line_range(repo_path, &comment.file_path, &comment.commit_sha, head_sha, comment.start_line, comment.end_line)
+        {
+            Ok(anchor) => anchor,
+            Err(e) => {
+                eprintln!("⚠️  Failed to re-anchor review comment {}: {}", comment.id, e);
+                continue;
+            }
+        };
+
+        match anchor {
+            Some(anchor) => schema::reanchor_review_comment(
+                &state.db.pool,
+                comment.id,
+                &anchor.file_path,
+                anchor.start_line,
+                anchor.end_line,
+                head_sha,
+            )
+            .await,
+            None => schema::mark_review_comment_outdated(&state.db.pool, comment.id).await,
+        }
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// Review Comment Commands
+fn validate_review_comment(body: &str, points_deducted: Option<i32>) -> Result<(), String> {
+    if body.trim().is_empty() {
+        return Err("Comment body is required".to_string());
+    }
+    if points_deducted.is_some_and(|points| points < 0) {
+        return Err("Point deductions cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+// Deductions count towards the grade, so they go through the same review gate as score adjustments
+fn validate_deduction_status(project: &crate::database::models::Project, points_deducted: Option<i32>) -> Result<(), String> {
+    if points_deducted.is_some() && project.status.parse::<ProjectStatus>()? != ProjectStatus::InReview {
+        return Err("Points can only be deducted while the project is in review".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_review_comment(
+    comment: crate::database::models::CreateReviewComment,
+    state: State<'_, AppState>
+) -> Result<crate::database::models::ReviewComment, String> {
+    validate_review_comment(&comment.body, comment.points_deducted)?;
+
+    if comment.file_path.trim().is_empty() {
+        return Err("File path is required".to_string());
+    }
+    if comment.start_line < 1 || comment.end_line < comment.start_line {
+        return Err("Invalid line range".to_string());
+    }
+    if comment.commit_sha.trim().is_empty() {
+        return Err("Commit SHA is required".to_string());
+    }
+
+    let project = schema::get_project_by_id(&state.db.pool, comment.project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+    validate_deduction_status(&project, comment.points_deducted)?;
+
+    let comment = crate::database::models::CreateReviewComment {
+        file_path: comment.file_path.trim().trim_start_matches("./").replace('\\', "/"),
+        ..comment
+    };
+
+    let id = schema::create_review_comment(&state.db.pool, comment)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    schema::get_review_comment_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Comment not found".to_string())
+}
+
+#[tauri::command]
+pub async fn list_review_comments(
+    project_id: i64,
+    include_outdated: Option<bool>,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::ReviewComment>, String> {
+    schema::get_review_comments_by_project_id(&state.db.pool, project_id, include_outdated.unwrap_or(true))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_review_comment(
+    id: i64,
+    comment: crate::database::models::UpdateReviewComment,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    validate_review_comment(&comment.body, comment.points_deducted)?;
+
+    if comment.points_deducted.is_some() {
+        let existing = schema::get_review_comment_by_id(&state.db.pool, id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Comment not found".to_string())?;
+        let project = schema::get_project_by_id(&state.db.pool, existing.project_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Project not found".to_string())?;
+        validate_deduction_status(&project, comment.points_deducted)?;
+    }
+
+    schema::update_review_comment(&state.db.pool, id, comment)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_review_comment(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_review_comment(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn format_review_comments(comments: &[crate::database::models::ReviewComment]) -> Option<String> {
+    if comments.is_empty() {
+        return None;
+    }
+
+    let lines: Vec<String> = comments
+        .iter()
+        .map(|comment| {
+            let location = if comment.start_line == comment.end_line {
+                format!("{}:{}", comment.file_path, comment.start_line)
+            } else {
+                format!("{}:{}-{}", comment.file_path, comment.start_line, comment.end_line)
+            };
+            let deduction = comment.points_deducted
+                .filter(|points| *points > 0)
+                .map(|points| format!(" (-{})", points))
+                .unwrap_or_default();
+            format!("- [{}] {}: {}{}", comment.category, location, comment.body.trim(), deduction)
+        })
+        .collect();
+
+    Some(format!("Review comments:\n{}", lines.join("\n")))
+}
+
+#[tauri::command]
+pub async fn get_analysis_by_project_id(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<Option<crate::database::models::AnalysisResult>, String> {
+    schema::get_analysis_by_project_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_analysis_history(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::AnalysisResult>, String> {
+    schema::get_analysis_history(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Defaults to comparing the previous run against the latest one
+#[tauri::command]
+pub async fn diff_analysis_runs(
+    project_id: i64,
+    from_run_id: Option<i64>,
+    to_run_id: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<crate::services::analysis_service::AnalysisDiff, String> {
+    let history = schema::get_analysis_history(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let find_run = |id: Option<i64>, fallback: usize| -> Result<&crate::database::models::AnalysisResult, String> {
+        match id {
+            Some(id) => history
+                .iter()
+                .find(|run| run.id == id)
+                .ok_or_else(|| format!("Analysis run {} not found for this project", id)),
+            None => history
+                .get(fallback)
+                .ok_or_else(|| "At least two analysis runs are needed to compare".to_string()),
+        }
+    };
+
+    let to = find_run(to_run_id, 0)?;
+    let from = find_run(from_run_id, 1)?;
+
+    if from.id == to.id {
+        return Err("Pick two different analysis runs to compare".to_string());
+    }
+
+    Ok(state.analysis_service.diff_runs(from, to))
+}
+
+// Grade Review Commands
+#[tauri::command]
+pub async fn upsert_grade_adjustment(
+    adjustment: crate::database::models::CreateGradeAdjustment,
+    state: State<'_, AppState>
+) -> Result<i64, String> {
+    let project = schema::get_project_by_id(&state.db.pool, adjustment.project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    if project.status.parse::<ProjectStatus>()? != ProjectStatus::InReview {
+        return Err("Scores can only be adjusted while the project is in review".to_string());
+    }
+    if adjustment.criterion.trim().is_empty() {
+        return Err("Criterion is required".to_string());
+    }
+    if adjustment.justification.trim().is_empty() {
+        return Err("A justification is required for every adjusted score".to_string());
+    }
+
+    let max_score = adjustment.max_score.unwrap_or(100);
+    if max_score <= 0 || !(0..=max_score).contains(&adjustment.adjusted_score) {
+        return Err(format!("Adjusted score must be between 0 and {}", max_score));
+    }
+
+    // Record what the analysis said so the override stays explainable
+    let automated_score = match schema::get_analysis_by_project_id(&state.db.pool, project.id)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Some(analysis) => state.analysis_service.automated_criterion_score(&analysis, adjustment.criterion.trim()),
+        None => None,
+    };
+
+    let adjustment = crate::database::models::CreateGradeAdjustment {
+        criterion: adjustment.criterion.trim().to_string(),
+        automated_score: automated_score.or(adjustment.automated_score),
+        ..adjustment
+    };
+
+    schema::upsert_grade_adjustment(&state.db.pool, adjustment)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_grade_adjustment(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let adjustment = schema::get_grade_adjustment_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Adjustment not found".to_string())?;
+
+    let project = schema::get_project_by_id(&state.db.pool, adjustment.project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    if project.status.parse::<ProjectStatus>()? != ProjectStatus::InReview {
+        return Err("Scores can only be adjusted while the project is in review".to_string());
+    }
+
+    schema::delete_grade_adjustment(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn build_grade_summary(state: &AppState, project_id: i64) -> Result<crate::database::models::GradeSummary, String> {
+    let project = schema::get_project_by_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Project not found".to_string())?;
+
+    let analysis = schema::get_analysis_by_project_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let adjustments = schema::get_grade_adjustments(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let final_grade = schema::get_final_grade(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Outdated comments point at code the student has since removed, so they no longer cost points
+    let comments = schema::get_review_comments_by_project_id(&state.db.pool, project_id, false)
+        .await
+        .map_err(|e| e.to_string())?;
+    let comment_deductions: i32 = comments.iter().filter_map(|c| c.points_deducted).sum();
+
+    let computed_score = analysis
+        .as_ref()
+        .and_then(|analysis| state.analysis_service.apply_grade_adjustments(analysis, &adjustments))
+        .map(|score| (score - comment_deductions).clamp(0, 100));
+
+    Ok(crate::database::models::GradeSummary {
+        project_id,
+        status: project.status,
+        automated_score: analysis.and_then(|analysis| analysis.total_score),
+        computed_score,
+        final_grade,
+        adjustments,
+        comment_deductions,
+    })
+}
+
+#[tauri::command]
+pub async fn get_grade_summary(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<crate::database::models::GradeSummary, String> {
+    build_grade_summary(&state, project_id).await
+}
+
+// Without an explicit final score the adjusted automated score is used
+#[tauri::command]
+pub async fn finalize_grade(
+    project_id: i64,
+    final_score: Option<i32>,
+    notes: Option<String>,
+    graded_by: Option<String>,
+    state: State<'_, AppState>
+) -> Result<crate::database::models::GradeSummary, String> {
+    let summary = build_grade_summary(&state, project_id).await?;
+
+    if summary.status.parse::<ProjectStatus>()? != ProjectStatus::InReview {
+        return Err("Only projects in review can be graded".to_string());
+    }
+
+    let final_score = final_score
+        .or(summary.computed_score)
+        .ok_or_else(|| "No automated score available, provide a final score".to_string())?;
+    if !(0..=100).contains(&final_score) {
+        return Err("Final score must be between 0 and 100".to_string());
+    }
+
+    // A manual score that differs from the computed one needs the same paper trail as an adjustment
+    if Some(final_score) != summary.computed_score && notes.as_deref().is_none_or(|n| n.trim().is_empty()) {
+        return Err("Notes are required when the final score differs from the computed score".to_string());
+    }
+
+    let current = check_transition(&state, project_id, ProjectStatus::Graded).await?;
+    let updated = schema::record_final_grade(
+        &state.db.pool,
+        project_id,
+        current.as_str(),
+        summary.automated_score,
+        final_score,
+        notes.as_deref(),
+        graded_by.as_deref(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    ensure_transitioned(updated)?;
+
+    build_grade_summary(&state, project_id).await
+}
+
+#[tauri::command]
+pub async fn release_grades(
+    project_ids: Vec<i64>,
+    state: State<'_, AppState>
+) -> Result<Vec<String>, String> {
+    let mut errors = Vec::new();
+
+    for project_id in project_ids {
+        let released = async {
+            let current = check_transition(&state, project_id, ProjectStatus::Released).await?;
+            let updated = schema::release_final_grade(&state.db.pool, project_id, current.as_str())
+                .await
+                .map_err(|e| e.to_string())?;
+            ensure_transitioned(updated)
+        }
+        .await;
+
+        if let Err(e) = released {
+            errors.push(format!("Project {}: {}", project_id, e));
+        }
+    }
+
+    Ok(errors)
+}
+
+#[tauri::command]
+pub async fn request_regrade(
+    project_id: i64,
+    reason: String,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    if reason.trim().is_empty() {
+        return Err("A reason is required to request a regrade".to_string());
+    }
+
+    transition_project(&state, project_id, ProjectStatus::Regrade, Some(reason.trim())).await
+}
+
+// Smoke Test Commands
+fn parse_smoke_steps(steps: &serde_json::Value) -> Result<Vec<SmokeStep>, String> {
+    serde_json::from_value(steps.clone()).map_err(|e| format!("Invalid smoke steps: {}", e))
+}
+
+#[tauri::command]
+pub async fn create_smoke_scenario(
+    scenario: crate::database::models::CreateSmokeScenario,
+    state: State<'_, AppState>
+) -> Result<i64, String> {
+    parse_smoke_steps(&scenario.steps)?;
+    schema::create_smoke_scenario(&state.db.pool, scenario)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_smoke_scenarios(
+    assignment_id: Option<i64>,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::SmokeScenario>, String> {
+    schema::get_smoke_scenarios(&state.db.pool, assignment_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_smoke_scenario(
+    id: i64,
+    scenario: crate::database::models::CreateSmokeScenario,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    parse_smoke_steps(&scenario.steps)?;
+    schema::update_smoke_scenario(&state.db.pool, id, scenario)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_smoke_scenario(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    schema::delete_smoke_scenario(&state.db.pool, id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn run_smoke_tests(
+    project_id: i64,
+    scenario_id: i64,
+    state: State<'_, AppState>
+) -> Result<SmokeTestReport, String> {
+    let scenario = schema::get_smoke_scenario_by_id(&state.db.pool, scenario_id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Smoke scenario not found".to_string())?;
 
-    drop(github_service); // Release the lock
+    let steps: Vec<SmokeStep> = serde_json::from_str(&scenario.steps)
+        .map_err(|e| format!("Invalid smoke steps: {}", e))?;
 
-    // Perform analysis
-    let analysis_result = state.analysis_service
-        .analyze_project(&project_path, &repo_info.technology_stack)
+    // Smoke tests run against the project's current playground
+    let session = schema::get_playground_session_by_project_id(&state.db.pool, project_id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+        .filter(|session| session.status == "running")
+        .ok_or_else(|| "No running playground for this project".to_string())?;
 
-    // Save analysis results
-    let create_analysis = state.analysis_service
-        .convert_to_create_analysis_result(project_id, &analysis_result);
+    let port = session.port
+        .ok_or_else(|| "Playground session has no port".to_string())?;
 
-    schema::create_analysis_result(&state.db.pool, create_analysis)
+    let report = state.smoke_test_service
+        .run_scenario(&format!("http://localhost:{}", port), &steps)
         .await
         .map_err(|e| e.to_string())?;
 
-    // Update project status to completed
-    schema::update_project_status(&state.db.pool, project_id, "completed")
+    let create_run = crate::database::models::CreateSmokeTestRun {
+        project_id,
+        playground_session_id: Some(session.id),
+        scenario_id,
+        passed: report.passed(),
+        total_steps: report.total_steps as i32,
+        passed_steps: report.passed_steps as i32,
+        results: serde_json::to_value(&report).ok(),
+    };
+
+    schema::create_smoke_test_run(&state.db.pool, create_run)
         .await
         .map_err(|e| e.to_string())?;
 
-    // Cleanup
-    let _ = std::fs::remove_dir_all(&temp_dir);
-
-    Ok(analysis_result)
+    Ok(report)
 }
 
 #[tauri::command]
-pub async fn get_analysis_by_project_id(
+pub async fn get_smoke_test_runs(
     project_id: i64,
     state: State<'_, AppState>
-) -> Result<Option<crate::database::models::AnalysisResult>, String> {
-    schema::get_analysis_by_project_id(&state.db.pool, project_id)
+) -> Result<Vec<crate::database::models::SmokeTestRun>, String> {
+    schema::get_smoke_test_runs_by_project_id(&state.db.pool, project_id)
         .await
         .map_err(|e| e.to_string())
 }
 
 // Playground Commands
+async fn load_image_catalog(state: &AppState) -> Result<ImageCatalog, String> {
+    let stored = schema::get_setting(&state.db.pool, IMAGE_CATALOG_SETTING_KEY)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match stored {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("Invalid image catalog setting: {}", e)),
+        None => Ok(ImageCatalog::default()),
+    }
+}
+
+fn emit_pull_progress(app_handle: AppHandle) -> PullProgressCallback {
+    Arc::new(move |progress: ImagePullProgress| {
+        let _ = app_handle.emit("image-pull-progress", &progress);
+    })
+}
+
 #[tauri::command]
 pub async fn start_playground(
     project_id: i64,
+    assignment_id: Option<i64>,
+    app_handle: AppHandle,
     state: State<'_, AppState>
 ) -> Result<PlaygroundInfo, String> {
     // Get project details
@@ -320,10 +2524,16 @@ pub async fn start_playground(
 
     drop(github_service); // Release the lock
 
-    // Start playground container
-    let docker_service = state.docker_service.lock().await;
-    let playground_info = docker_service
-        .start_playground(&project_path, &repo_info.technology_stack)
+    // Start playground container; an explicit assignment wins over the project's own
+    let options = PlaygroundOptions {
+        image_catalog: load_image_catalog(&state).await?,
+        assignment_id: assignment_id.or(project.assignment_id),
+        on_pull_progress: Some(emit_pull_progress(app_handle)),
+        restore_from: None,
+    };
+
+    let playground_info = state.container_runtime
+        .start_playground(&project_path, &repo_info.technology_stack, &options)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -333,6 +2543,7 @@ pub async fn start_playground(
         container_id: Some(playground_info.container_id.clone()),
         port: Some(playground_info.port as i32),
         status: "running".to_string(),
+        snapshot_id: None,
     };
 
     schema::create_playground_session(&state.db.pool, create_session)
@@ -354,8 +2565,7 @@ pub async fn stop_playground(
         .ok_or_else(|| "No playground session found".to_string())?;
 
     if let Some(container_id) = &session.container_id {
-        let docker_service = state.docker_service.lock().await;
-        docker_service
+        state.container_runtime
             .stop_playground(container_id)
             .await
             .map_err(|e| e.to_string())?;
@@ -380,8 +2590,7 @@ pub async fn get_playground_status(
 
     if let Some(session) = session {
         if let Some(container_id) = &session.container_id {
-            let docker_service = state.docker_service.lock().await;
-            let status = docker_service
+            let status = state.container_runtime
                 .get_playground_status(container_id)
                 .await
                 .map_err(|e| e.to_string())?;
@@ -405,8 +2614,7 @@ pub async fn get_playground_resource_usage(
 
     if let Some(session) = session {
         if let Some(container_id) = &session.container_id {
-            let docker_service = state.docker_service.lock().await;
-            let usage = docker_service
+            let usage = state.container_runtime
                 .get_resource_usage(container_id)
                 .await
                 .map_err(|e| e.to_string())?;
@@ -421,8 +2629,7 @@ pub async fn get_playground_resource_usage(
 
 #[tauri::command]
 pub async fn list_active_playgrounds(state: State<'_, AppState>) -> Result<Vec<bollard::models::ContainerSummary>, String> {
-    let docker_service = state.docker_service.lock().await;
-    docker_service
+    state.container_runtime
         .list_active_playgrounds()
         .await
         .map_err(|e| e.to_string())
@@ -433,13 +2640,261 @@ pub async fn cleanup_old_containers(
     max_age_hours: u64,
     state: State<'_, AppState>
 ) -> Result<usize, String> {
-    let docker_service = state.docker_service.lock().await;
-    docker_service
+    state.container_runtime
         .cleanup_old_containers(max_age_hours)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_dependency_cache_usage(state: State<'_, AppState>) -> Result<Vec<CacheVolumeUsage>, String> {
+    state.container_runtime
+        .get_cache_usage()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn prune_dependency_caches(
+    caches: Option<Vec<DependencyCache>>,
+    state: State<'_, AppState>
+) -> Result<CachePruneReport, String> {
+    state.container_runtime
+        .prune_caches(&caches.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Playground Snapshot Commands
+const SNAPSHOT_RETENTION_SETTING_KEY: &str = "snapshot_retention_per_project";
+const DEFAULT_SNAPSHOT_RETENTION: usize = 5;
+
+async fn snapshot_retention_limit(state: &AppState) -> Result<usize, String> {
+    let stored = schema::get_setting(&state.db.pool, SNAPSHOT_RETENTION_SETTING_KEY)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(stored
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_SNAPSHOT_RETENTION))
+}
+
+// The record goes first so a failed delete never leaves it pointing at a removed image. The image may
+// already be gone (manual prune, different runtime), so cleaning up after that is best effort.
+async fn remove_snapshot(state: &AppState, snapshot: &crate::database::models::PlaygroundSnapshot) -> Result<(), String> {
+    schema::delete_playground_snapshot(&state.db.pool, snapshot.id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = state.container_runtime.remove_snapshot_image(&snapshot.image).await;
+    let _ = std::fs::remove_dir_all(&snapshot.workspace_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_playground_snapshot(
+    project_id: i64,
+    label: String,
+    app_handle: AppHandle,
+    state: State<'_, AppState>
+) -> Result<crate::database::models::PlaygroundSnapshot, String> {
+    let session = schema::get_playground_session_by_project_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "No playground session found".to_string())?;
+
+    let container_id = session.container_id
+        .clone()
+        .ok_or_else(|| "Playground session has no container".to_string())?;
+
+    let tag = snapshot_tag(project_id);
+    let workspace_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("snapshots")
+        .join(&tag);
+
+    let committed = state.container_runtime
+        .snapshot_playground(&container_id, &tag, &label, &workspace_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let committed_image = committed.image.clone();
+    let create_snapshot = crate::database::models::CreatePlaygroundSnapshot {
+        project_id,
+        session_id: session.id,
+        label,
+        image: committed_image.clone(),
+        workspace_path: workspace_dir.to_string_lossy().to_string(),
+        environment: serde_json::to_value(&committed.environment).map_err(|e| e.to_string())?,
+        size_bytes: committed.size_bytes.map(|size| size as i64),
+    };
+
+    let snapshot_id = match schema::create_playground_snapshot(&state.db.pool, create_snapshot).await {
+        Ok(id) => id,
+        Err(e) => {
+            // Nothing refers to the image or the copied workspace yet
+            let _ = state.container_runtime.remove_snapshot_image(&committed_image).await;
+            let _ = std::fs::remove_dir_all(&workspace_dir);
+            return Err(e.to_string());
+        }
+    };
+
+    // Enforce retention, dropping the oldest snapshots first. The new snapshot exists at this point,
+    // so a failed prune is logged and retried by the next snapshot rather than failing this one.
+    match snapshot_retention_limit(&state).await {
+        Ok(retention) => match schema::get_playground_snapshots_by_project_id(&state.db.pool, project_id).await {
+            Ok(snapshots) => {
+                for expired in snapshots.iter().skip(retention.max(1)) {
+                    if let Err(e) = remove_snapshot(&state, expired).await {
+                        eprintln!("⚠️  Failed to prune snapshot {}: {}", expired.id, e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("⚠️  Failed to list snapshots for retention: {}", e),
+        },
+        Err(e) => eprintln!("⚠️  Failed to read snapshot retention: {}", e),
+    }
+
+    schema::get_playground_snapshot_by_id(&state.db.pool, snapshot_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Snapshot not found".to_string())
+}
+
+#[tauri::command]
+pub async fn list_playground_snapshots(
+    project_id: i64,
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::models::PlaygroundSnapshot>, String> {
+    schema::get_playground_snapshots_by_project_id(&state.db.pool, project_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_playground_snapshot(
+    snapshot_id: i64,
+    state: State<'_, AppState>
+) -> Result<PlaygroundInfo, String> {
+    let snapshot = schema::get_playground_snapshot_by_id(&state.db.pool, snapshot_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Snapshot not found".to_string())?;
+
+    let environment: EnvironmentConfig = serde_json::from_str(&snapshot.environment)
+        .map_err(|e| format!("Invalid snapshot environment: {}", e))?;
+
+    // Work on a copy so the archived workspace stays reusable
+    let snapshot_path = std::path::Path::new(&snapshot.workspace_path);
+    let workspace_name = snapshot_path
+        .file_name()
+        .ok_or_else(|| "Invalid snapshot workspace path".to_string())?;
+    let project_path = std::env::temp_dir()
+        .join(format!("r3viewer_playground_{}_restore", snapshot.project_id))
+        .join(workspace_name);
+    if project_path.exists() {
+        std::fs::remove_dir_all(&project_path).map_err(|e| e.to_string())?;
+    }
+    copy_workspace(snapshot_path, &project_path).map_err(|e| e.to_string())?;
+
+    let options = PlaygroundOptions {
+        restore_from: Some(SnapshotRestore {
+            image: snapshot.image.clone(),
+            environment,
+        }),
+        ..Default::default()
+    };
+
+    let playground_info = state.container_runtime
+        .start_playground(&project_path, &[], &options)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let create_session = crate::database::models::CreatePlaygroundSession {
+        project_id: snapshot.project_id,
+        container_id: Some(playground_info.container_id.clone()),
+        port: Some(playground_info.port as i32),
+        status: "running".to_string(),
+        snapshot_id: Some(snapshot.id),
+    };
+
+    schema::create_playground_session(&state.db.pool, create_session)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(playground_info)
+}
+
+#[tauri::command]
+pub async fn delete_playground_snapshot(
+    snapshot_id: i64,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let snapshot = schema::get_playground_snapshot_by_id(&state.db.pool, snapshot_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Snapshot not found".to_string())?;
+
+    remove_snapshot(&state, &snapshot).await
+}
+
+#[tauri::command]
+pub async fn update_snapshot_retention(
+    max_per_project: usize,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    if max_per_project == 0 {
+        return Err("Retention must keep at least one snapshot per project".to_string());
+    }
+
+    schema::set_setting(&state.db.pool, SNAPSHOT_RETENTION_SETTING_KEY, &max_per_project.to_string())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_image_catalog(state: State<'_, AppState>) -> Result<ImageCatalog, String> {
+    load_image_catalog(&state).await
+}
+
+#[tauri::command]
+pub async fn update_image_catalog(
+    catalog: ImageCatalog,
+    state: State<'_, AppState>
+) -> Result<(), String> {
+    let json = serde_json::to_string(&catalog).map_err(|e| e.to_string())?;
+    schema::set_setting(&state.db.pool, IMAGE_CATALOG_SETTING_KEY, &json)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn prepull_images(
+    stacks: Option<Vec<ImageStack>>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>
+) -> Result<Vec<ImagePullResult>, String> {
+    let catalog = load_image_catalog(&state).await?;
+    let on_progress = emit_pull_progress(app_handle);
+    let mut results = Vec::new();
+
+    for image in catalog.references(&stacks.unwrap_or_default()) {
+        let outcome = state.container_runtime
+            .pull_image(&image, Some(&on_progress))
+            .await;
+
+        results.push(ImagePullResult {
+            image,
+            success: outcome.is_ok(),
+            error: outcome.err().map(|e| e.to_string()),
+        });
+    }
+
+    Ok(results)
+}
+
 // Utility Commands
 #[tauri::command]
 pub async fn get_app_data_dir(app_handle: AppHandle) -> Result<String, String> {
@@ -452,25 +2907,106 @@ pub async fn get_app_data_dir(app_handle: AppHandle) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn check_docker_status(state: State<'_, AppState>) -> Result<bool, String> {
-    let docker_service = state.docker_service.lock().await;
-    // Try to list containers to check if Docker is running
-    match docker_service.list_active_playgrounds().await {
-        Ok(_) => Ok(true),
-        Err(_) => Ok(false),
+pub async fn get_database_encryption_status(
+    state: State<'_, AppState>
+) -> Result<crate::database::encryption::EncryptionStatus, String> {
+    state.db.encryption_status().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn enable_database_encryption(
+    state: State<'_, AppState>
+) -> Result<crate::database::encryption::EncryptionStatus, String> {
+    state.db.enable_encryption().map_err(|e| e.to_string())
+}
+
+// Backup & Archive Commands
+#[tauri::command]
+pub async fn create_database_backup(
+    state: State<'_, AppState>
+) -> Result<crate::database::BackupInfo, String> {
+    state.db.create_backup().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_database_backups(
+    state: State<'_, AppState>
+) -> Result<Vec<crate::database::BackupInfo>, String> {
+    state.db.list_backups().map_err(|e| e.to_string())
+}
+
+// The backup replaces the database when r3viewer restarts; the status tells the user to restart
+#[tauri::command]
+pub async fn restore_database_backup(
+    backup_path: String,
+    state: State<'_, AppState>
+) -> Result<crate::database::RestoreStatus, String> {
+    state.db
+        .stage_restore(std::path::Path::new(&backup_path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_course_archive(
+    course_id: i64,
+    destination: String,
+    state: State<'_, AppState>
+) -> Result<crate::database::archive::ArchiveManifest, String> {
+    crate::database::archive::export_course_archive(&state.db, course_id, std::path::Path::new(&destination))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn import_course_archive(
+    source: String,
+    state: State<'_, AppState>
+) -> Result<crate::database::archive::ArchiveImportSummary, String> {
+    let (manifest, archive) = crate::database::archive::read_course_archive(std::path::Path::new(&source))
+        .map_err(|e| e.to_string())?;
+
+    let local_version = state.db.schema_version().await.map_err(|e| e.to_string())?;
+    if manifest.schema_version > local_version {
+        println!(
+            "⚠️  Importing archive from schema version {} into version {}; fields added since are dropped",
+            manifest.schema_version, local_version
+        );
+    }
+
+    crate::database::archive::import_course_archive(&state.db.pool, &state.identity_service, &archive)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn check_docker_status(state: State<'_, AppState>) -> Result<ContainerRuntimeStatus, String> {
+    let mut status = state.container_runtime_status.clone();
+
+    // Re-check the active runtime in case the daemon went away since startup
+    if status.available {
+        if let Err(e) = state.container_runtime.ping().await {
+            status.available = false;
+            status.failures.push(RuntimeProbeFailure {
+                runtime: status.active_runtime,
+                reason: e.to_string(),
+            });
+        }
     }
+
+    Ok(status)
 }
 
 // Export/Import Commands
 #[tauri::command]
 pub async fn export_results_to_sheet(
     spreadsheet_id: String,
-    range: String,
+    tab_name: Option<String>,
     results: Vec<ExportRow>,
     state: State<'_, AppState>
-) -> Result<(), String> {
+) -> Result<SheetExportSummary, String> {
     state.sheets_service
-        .export_results_to_sheet(&spreadsheet_id, &range, &results)
+        .export_results_to_sheet(&spreadsheet_id, tab_name.as_deref(), &results)
         .await
         .map_err(|e| e.to_string())
 }
@@ -480,9 +3016,27 @@ pub async fn export_project_results(
     project_ids: Vec<i64>,
     state: State<'_, AppState>
 ) -> Result<Vec<ExportRow>, String> {
+    build_export_rows(&state, &project_ids).await
+}
+
+#[tauri::command]
+pub async fn export_gradebook(
+    project_ids: Vec<i64>,
+    destination: String,
+    options: GradebookExportOptions,
+    state: State<'_, AppState>
+) -> Result<GradebookExportResult, String> {
+    let rows = build_export_rows(&state, &project_ids).await?;
+
+    state.gradebook_export_service
+        .export(&rows, std::path::Path::new(&destination), &options)
+        .map_err(|e| e.to_string())
+}
+
+async fn build_export_rows(state: &AppState, project_ids: &[i64]) -> Result<Vec<ExportRow>, String> {
     let mut results = Vec::new();
 
-    for project_id in project_ids {
+    for &project_id in project_ids {
         let project = schema::get_project_by_id(&state.db.pool, project_id)
             .await
             .map_err(|e| e.to_string())?;
@@ -495,23 +3049,189 @@ pub async fn export_project_results(
             None
         };
 
+        let (Some(project), Some(student)) = (project, student) else {
+            continue;
+        };
+
         let analysis = schema::get_analysis_by_project_id(&state.db.pool, project_id)
             .await
             .map_err(|e| e.to_string())?;
 
-        if let (Some(project), Some(student)) = (project, student) {
-            results.push(ExportRow {
-                student_name: student.name,
-                project_name: project.name,
-                total_score: analysis.as_ref().and_then(|a| a.total_score),
-                code_quality_score: analysis.as_ref().and_then(|a| a.code_quality_score),
-                structure_score: analysis.as_ref().and_then(|a| a.structure_score),
-                documentation_score: analysis.as_ref().and_then(|a| a.documentation_score),
-                functionality_score: analysis.as_ref().and_then(|a| a.functionality_score),
-                feedback: analysis.as_ref().and_then(|a| a.feedback.clone()),
-            });
-        }
+        let comments = schema::get_review_comments_by_project_id(&state.db.pool, project_id, false)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let summary = build_grade_summary(state, project_id).await?;
+
+        // Analyzer criteria with reviewer adjustments applied, then rubric-only criteria
+        let mut criteria: Vec<CriterionScore> = SCORE_CRITERIA
+            .iter()
+            .map(|(criterion, _)| {
+                let adjustment = summary.adjustments.iter().find(|a| a.criterion == *criterion);
+                CriterionScore {
+                    criterion: criterion.to_string(),
+                    score: adjustment.map(|a| a.adjusted_score).or_else(|| {
+                        analysis.as_ref().and_then(|a| state.analysis_service.automated_criterion_score(a, criterion))
+                    }),
+                    max_score: adjustment.and_then(|a| a.max_score).or(Some(100)),
+                }
+            })
+            .collect();
+        criteria.extend(
+            summary.adjustments
+                .iter()
+                .filter(|a| !SCORE_CRITERIA.iter().any(|(criterion, _)| *criterion == a.criterion))
+                .map(|a| CriterionScore {
+                    criterion: a.criterion.clone(),
+                    score: Some(a.adjusted_score),
+                    max_score: a.max_score,
+                }),
+        );
+
+        results.push(ExportRow {
+            student_name: student.name,
+            project_name: project.name,
+            total_score: analysis.as_ref().and_then(|a| a.total_score),
+            code_quality_score: analysis.as_ref().and_then(|a| a.code_quality_score),
+            structure_score: analysis.as_ref().and_then(|a| a.structure_score),
+            documentation_score: analysis.as_ref().and_then(|a| a.documentation_score),
+            functionality_score: analysis.as_ref().and_then(|a| a.functionality_score),
+            feedback: analysis.as_ref().and_then(|a| a.feedback.clone()),
+            student_id: Some(student.id),
+            student_email: student.email,
+            github_username: student.github_username,
+            final_score: summary.final_grade.as_ref().map(|g| g.final_score),
+            criteria,
+            comments: format_review_comments(&comments),
+            released_at: summary.final_grade.and_then(|g| g.released_at),
+        });
     }
 
     Ok(results)
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateStudent, Student};
+    use chrono::Utc;
+
+    fn existing(email: Option<&str>, cohort: Option<&str>, cohort_id: Option<i64>) -> Student {
+        Student {
+            id: 1,
+            name: "Ada Lovelace".to_string(),
+            email: email.map(str::to_string),
+            github_username: None,
+            cohort: cohort.map(str::to_string),
+            cohort_id,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn incoming(email: Option<&str>, cohort: Option<&str>, cohort_id: Option<i64>) -> CreateStudent {
+        CreateStudent {
+            name: "Ada Lovelace".to_string(),
+            email: email.map(str::to_string),
+            github_username: None,
+            cohort: cohort.map(str::to_string),
+            cohort_id,
+        }
+    }
+
+    fn fields(changes: &[FieldChange]) -> Vec<&str> {
+        changes.iter().map(|c| c.field.as_str()).collect()
+    }
+
+    #[test]
+    fn student_import_changes_fills_blanks_and_replaces_cohort() {
+        let changes = student_import_changes(
+            &existing(None, Some("Fall"), Some(3)),
+            &incoming(Some("ada@example.edu"), Some("Spring"), Some(4)),
+        );
+        assert_eq!(fields(&changes), ["email", "cohort"]);
+
+        // An email already on record is kept
+        assert!(student_import_changes(&existing(Some("a@x.edu"), None, None), &incoming(Some("b@x.edu"), None, None)).is_empty());
+    }
+
+    #[test]
+    fn student_import_changes_links_an_unlinked_cohort() {
+        let changes = student_import_changes(&existing(None, Some("Fall"), None), &incoming(None, Some("Fall"), Some(3)));
+        assert_eq!(fields(&changes), ["cohort_id"]);
+        assert_eq!(changes[0].new_value.as_deref(), Some("3"));
+
+        assert!(student_import_changes(&existing(None, Some("Fall"), Some(3)), &incoming(None, Some("Fall"), Some(3))).is_empty());
+        assert!(student_import_changes(&existing(None, Some("Fall"), Some(3)), &incoming(None, Some("Fall"), None)).is_empty());
+    }
+
+    fn roster_row(name: &str, email: Option<&str>, github_username: Option<&str>, source_row: usize) -> StudentData {
+        StudentData {
+            name: name.to_string(),
+            email: email.map(str::to_string),
+            github_username: github_username.map(str::to_string),
+            github_url: None,
+            project_name: None,
+            project_description: None,
+            cohort: None,
+            team: None,
+            source_row: Some(source_row),
+            assignment: None,
+            extra_projects: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn reimporting_a_row_without_identifiers_updates_the_same_student() {
+        let pool = crate::database::test_support::pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+        let sheets = SheetsService::new(ApiClient::new(AuthService::new()));
+        let identity = IdentityService::new();
+        let row = |github_url: &str| StudentData { github_url: Some(github_url.to_string()), ..roster_row("Ada Lovelace", None, None, 2) };
+        let rows = vec![row("https://github.com/ada/engine")];
+
+        let first = run_student_import(&sheets, &identity, &mut conn, &rows, None, None).await.unwrap();
+        assert_eq!((first.students_imported, first.projects_imported), (1, 1));
+
+        // The repository is already theirs, so the name match is the same student
+        let second = run_student_import(&sheets, &identity, &mut conn, &rows, None, None).await.unwrap();
+        assert_eq!((second.students_imported, second.projects_imported), (0, 0));
+        assert_eq!(second.rows[0].student.record_id, first.rows[0].student.record_id);
+        assert!(second.warnings.is_empty());
+
+        // Outside a course nothing ties a new repository to them, so the name alone only raises a warning
+        let unrelated = run_student_import(&sheets, &identity, &mut conn, &[row("https://github.com/someone/else")], None, None)
+            .await
+            .unwrap();
+        assert_eq!(unrelated.students_imported, 1);
+        assert_eq!(unrelated.warnings.len(), 1);
+        assert_eq!(schema::get_all_students(&mut *conn).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn roster_row_keys_prefer_email_then_username_and_number_repeats() {
+        let keys: Vec<String> = roster_row_keys(vec![
+            roster_row("Ada Lovelace", Some(" Ada@Example.edu "), Some("ada"), 2),
+            roster_row("Grace Hopper", None, Some("@GHopper"), 3),
+            roster_row("Alan Turing", None, None, 4),
+            roster_row("Ada Lovelace", Some("ada@example.edu"), None, 5),
+        ])
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+
+        assert_eq!(keys[0], "email:ada@example.edu");
+        assert_eq!(keys[1], "github:ghopper");
+        assert!(keys[2].starts_with("name:"));
+        assert_eq!(keys[3], "email:ada@example.edu#2");
+    }
+
+    #[test]
+    fn roster_row_fields_ignore_the_row_number() {
+        let row = roster_row("Ada Lovelace", Some("ada@example.edu"), None, 2);
+        let moved = roster_row("Ada Lovelace", Some("ada@example.edu"), None, 9);
+        assert_eq!(roster_row_fields(&row), roster_row_fields(&moved));
+
+        let renamed = roster_row("Ada King", Some("ada@example.edu"), None, 2);
+        assert_ne!(roster_row_fields(&row), roster_row_fields(&renamed));
+    }
+}
@@ -0,0 +1,839 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::{SqliteConnectOptions, SqliteJournalMode}, Connection, Sqlite, SqliteConnection, SqlitePool, Transaction};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use super::models::*;
+use super::Database;
+use crate::services::IdentityService;
+
+// Bump when the JSON layout changes incompatibly; importers refuse newer formats
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATA_ENTRY: &str = "course.json";
+const SNAPSHOT_ENTRY: &str = "course.db";
+
+// Projects belong to a course through an assignment or a course team
+const PROJECT_SCOPE: &str = "(assignment_id IN (SELECT id FROM assignments WHERE course_id = ?1) \
+     OR team_id IN (SELECT id FROM teams WHERE course_id = ?1))";
+
+// The tables an archive carries, parents first so the snapshot can be filled in this order
+const ARCHIVE_TABLES: &[&str] = &[
+    "courses",
+    "cohorts",
+    "assignments",
+    "students",
+    "teams",
+    "team_members",
+    "projects",
+    "analysis_results",
+    "review_comments",
+    "grade_adjustments",
+    "final_grades",
+    "project_status_history",
+];
+
+// The condition selecting the course's rows of `table`, with the course id bound as ?1
+fn course_scope(table: &str) -> String {
+    let in_scope = format!("project_id IN (SELECT id FROM projects WHERE {})", PROJECT_SCOPE);
+    match table {
+        "courses" => "id = ?1".to_string(),
+        "cohorts" | "assignments" => "course_id = ?1".to_string(),
+        "projects" => PROJECT_SCOPE.to_string(),
+        "teams" => format!("course_id = ?1 OR id IN (SELECT team_id FROM projects WHERE {})", PROJECT_SCOPE),
+        "team_members" => format!(
+            "team_id IN (SELECT id FROM teams WHERE course_id = ?1 OR id IN (SELECT team_id FROM projects WHERE {}))",
+            PROJECT_SCOPE
+        ),
+        "students" => format!(
+            "id IN (SELECT student_id FROM projects WHERE {}) \
+             OR cohort_id IN (SELECT id FROM cohorts WHERE course_id = ?1) \
+             OR id IN (SELECT tm.student_id FROM team_members tm JOIN teams t ON t.id = tm.team_id WHERE t.course_id = ?1)",
+            PROJECT_SCOPE
+        ),
+        _ => in_scope,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub schema_version: i64,
+    pub exported_at: DateTime<Utc>,
+    pub course_code: String,
+    pub course_name: String,
+    pub student_count: usize,
+    pub project_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CourseArchive {
+    pub course: Course,
+    pub cohorts: Vec<Cohort>,
+    pub assignments: Vec<Assignment>, // rubrics travel with their assignment
+    pub students: Vec<Student>,
+    pub teams: Vec<Team>,
+    pub team_members: Vec<TeamMember>,
+    pub projects: Vec<Project>,
+    pub analysis_results: Vec<AnalysisResult>,
+    pub review_comments: Vec<ReviewComment>,
+    pub grade_adjustments: Vec<GradeAdjustment>,
+    pub final_grades: Vec<FinalGrade>,
+    pub status_history: Vec<ProjectStatusChange>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ArchiveImportSummary {
+    pub course_id: i64,
+    pub course_created: bool,
+    pub students_created: usize,
+    pub students_matched: usize,
+    pub projects_created: usize,
+    pub projects_matched: usize,
+    pub analysis_results_imported: usize,
+    pub review_comments_imported: usize,
+    pub grade_adjustments_imported: usize,
+    pub final_grades_imported: usize,
+}
+
+pub async fn load_course_archive(pool: &SqlitePool, course_id: i64) -> Result<CourseArchive> {
+    let course: Course = sqlx::query_as(&format!("SELECT * FROM courses WHERE {}", course_scope("courses")))
+        .bind(course_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("Course not found"))?;
+
+    let cohorts: Vec<Cohort> = sqlx::query_as(&format!("SELECT * FROM cohorts WHERE {} ORDER BY id", course_scope("cohorts")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let assignments: Vec<Assignment> = sqlx::query_as(&format!("SELECT * FROM assignments WHERE {} ORDER BY id", course_scope("assignments")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let projects: Vec<Project> = sqlx::query_as(&format!("SELECT * FROM projects WHERE {} ORDER BY id", course_scope("projects")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let teams: Vec<Team> = sqlx::query_as(&format!("SELECT * FROM teams WHERE {} ORDER BY id", course_scope("teams")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let team_members: Vec<TeamMember> = sqlx::query_as(&format!("SELECT * FROM team_members WHERE {}", course_scope("team_members")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let students: Vec<Student> = sqlx::query_as(&format!("SELECT * FROM students WHERE {} ORDER BY id", course_scope("students")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let analysis_results: Vec<AnalysisResult> = sqlx::query_as(&format!(
+        "SELECT * FROM analysis_results WHERE {} ORDER BY id",
+        course_scope("analysis_results")
+    ))
+    .bind(course_id)
+    .fetch_all(pool)
+    .await?;
+
+    let review_comments: Vec<ReviewComment> = sqlx::query_as(&format!(
+        "SELECT * FROM review_comments WHERE {} ORDER BY id",
+        course_scope("review_comments")
+    ))
+    .bind(course_id)
+    .fetch_all(pool)
+    .await?;
+
+    let grade_adjustments: Vec<GradeAdjustment> = sqlx::query_as(&format!(
+        "SELECT * FROM grade_adjustments WHERE {} ORDER BY id",
+        course_scope("grade_adjustments")
+    ))
+    .bind(course_id)
+    .fetch_all(pool)
+    .await?;
+
+    let final_grades: Vec<FinalGrade> = sqlx::query_as(&format!("SELECT * FROM final_grades WHERE {}", course_scope("final_grades")))
+        .bind(course_id)
+        .fetch_all(pool)
+        .await?;
+
+    let status_history: Vec<ProjectStatusChange> = sqlx::query_as(&format!(
+        "SELECT * FROM project_status_history WHERE {} ORDER BY id",
+        course_scope("project_status_history")
+    ))
+    .bind(course_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(CourseArchive {
+        course,
+        cohorts,
+        assignments,
+        students,
+        teams,
+        team_members,
+        projects,
+        analysis_results,
+        review_comments,
+        grade_adjustments,
+        final_grades,
+        status_history,
+    })
+}
+
+// A zip holding the manifest, the course's own rows as JSON and a SQLite snapshot of those same rows;
+// nothing from other courses leaves the database
+pub async fn export_course_archive(db: &Database, course_id: i64, destination: &Path) -> Result<ArchiveManifest> {
+    let archive = load_course_archive(&db.pool, course_id).await?;
+
+    let mut snapshot_path = destination.as_os_str().to_owned();
+    snapshot_path.push(".snapshot");
+    let snapshot_path = std::path::PathBuf::from(snapshot_path);
+    let written = write_course_snapshot(db, course_id, &snapshot_path).await;
+    let snapshot = written.and_then(|()| std::fs::read(&snapshot_path).map_err(anyhow::Error::from));
+    let _ = std::fs::remove_file(&snapshot_path);
+    let snapshot = snapshot?;
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        schema_version: db.schema_version().await?,
+        exported_at: Utc::now(),
+        course_code: archive.course.code.clone(),
+        course_name: archive.course.name.clone(),
+        student_count: archive.students.len(),
+        project_count: archive.projects.len(),
+    };
+
+    let file = std::fs::File::create(destination)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file(MANIFEST_ENTRY, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+
+    zip.start_file(DATA_ENTRY, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&archive)?)?;
+
+    zip.start_file(SNAPSHOT_ENTRY, options)?;
+    zip.write_all(&snapshot)?;
+
+    zip.finish()?;
+
+    Ok(manifest)
+}
+
+// A fresh, migrated database holding only the course's rows. It opens in any SQLite tool, and as an
+// r3viewer database it can be restored like a backup. Rows keep their ids and their references to
+// records outside the course, so foreign keys are switched off while copying.
+async fn write_course_snapshot(db: &Database, course_id: i64, path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    // Rollback journal rather than WAL, so the file is complete once the last connection lets go
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Delete);
+    let mut snapshot = SqliteConnection::connect_with(&options).await?;
+    super::MIGRATOR.run(&mut snapshot).await?;
+    snapshot.close().await?;
+
+    let mut conn = db.pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+    let copied = copy_course_rows(&mut conn, course_id, path).await;
+    let restored = sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await;
+
+    // A connection left without foreign keys or with the snapshot attached must not go back to the pool
+    if copied.is_err() || restored.is_err() {
+        let _ = conn.detach().close().await;
+    }
+    copied?;
+    restored?;
+
+    Ok(())
+}
+
+async fn copy_course_rows(conn: &mut SqliteConnection, course_id: i64, path: &Path) -> Result<()> {
+    // An empty key keeps the snapshot unencrypted when the database itself is encrypted
+    sqlx::query("ATTACH DATABASE ? AS snapshot KEY ''")
+        .bind(path.to_string_lossy().to_string())
+        .execute(&mut *conn)
+        .await?;
+
+    let mut copied = Ok(());
+    for table in ARCHIVE_TABLES {
+        let statement = format!("INSERT INTO snapshot.{} SELECT * FROM main.{} WHERE {}", table, table, course_scope(table));
+        if let Err(e) = sqlx::query(&statement).bind(course_id).execute(&mut *conn).await {
+            copied = Err(anyhow!("Failed to copy {} into the course snapshot: {}", table, e));
+            break;
+        }
+    }
+
+    sqlx::query("DETACH DATABASE snapshot").execute(&mut *conn).await?;
+
+    copied
+}
+
+pub fn read_course_archive(source: &Path) -> Result<(ArchiveManifest, CourseArchive)> {
+    let file = std::fs::File::open(source)?;
+    let mut zip = zip::ZipArchive::new(file)?;
+
+    let mut read_entry = |name: &str| -> Result<String> {
+        let mut entry = zip
+            .by_name(name)
+            .map_err(|_| anyhow!("{} is not an r3viewer course archive (missing {})", source.display(), name))?;
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents)?;
+        Ok(contents)
+    };
+
+    let manifest: ArchiveManifest = serde_json::from_str(&read_entry(MANIFEST_ENTRY)?)?;
+    if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(anyhow!(
+            "This archive uses format version {}, but this version of r3viewer only reads up to {}. Please update r3viewer.",
+            manifest.format_version,
+            ARCHIVE_FORMAT_VERSION
+        ));
+    }
+
+    let archive: CourseArchive = serde_json::from_str(&read_entry(DATA_ENTRY)?)?;
+
+    Ok((manifest, archive))
+}
+
+// Merges an archive into this database in one transaction. Records that already exist (same course
+// code, assignment title, student identity, repository) are reused, so a grading load can travel
+// back and forth between instructors; the newer grade wins when both sides changed it.
+pub async fn import_course_archive(
+    pool: &SqlitePool,
+    identity_service: &IdentityService,
+    archive: &CourseArchive,
+) -> Result<ArchiveImportSummary> {
+    let mut tx = pool.begin().await?;
+    let mut summary = ArchiveImportSummary::default();
+
+    let (course_id, course_created) = import_course(&mut tx, &archive.course).await?;
+    summary.course_id = course_id;
+    summary.course_created = course_created;
+
+    let mut cohort_ids = HashMap::new();
+    for cohort in &archive.cohorts {
+        sqlx::query(
+            "INSERT INTO cohorts (course_id, name, starts_on, ends_on) VALUES (?, ?, ?, ?)
+             ON CONFLICT(course_id, name) DO NOTHING"
+        )
+        .bind(course_id)
+        .bind(&cohort.name)
+        .bind(cohort.starts_on)
+        .bind(cohort.ends_on)
+        .execute(&mut *tx)
+        .await?;
+
+        let id: i64 = sqlx::query_scalar("SELECT id FROM cohorts WHERE course_id = ? AND name = ?")
+            .bind(course_id)
+            .bind(&cohort.name)
+            .fetch_one(&mut *tx)
+            .await?;
+        cohort_ids.insert(cohort.id, id);
+    }
+
+    let mut assignment_ids = HashMap::new();
+    for assignment in &archive.assignments {
+        let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM assignments WHERE course_id = ? AND title = ?")
+            .bind(course_id)
+            .bind(&assignment.title)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let id = match existing {
+            Some(id) => id,
+            None => sqlx::query(
+                "INSERT INTO assignments (course_id, cohort_id, title, description, due_at, rubric, starter_repo_url, allowed_stacks, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(course_id)
+            .bind(assignment.cohort_id.and_then(|id| cohort_ids.get(&id).copied()))
+            .bind(&assignment.title)
+            .bind(&assignment.description)
+            .bind(assignment.due_at)
+            .bind(&assignment.rubric)
+            .bind(&assignment.starter_repo_url)
+            .bind(&assignment.allowed_stacks)
+            .bind(assignment.created_at)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid(),
+        };
+        assignment_ids.insert(assignment.id, id);
+    }
+
+    // Students are matched the same way roster imports match them; anything short of a confident
+    // match becomes a new record that shows up in the duplicate review
+    let mut known_students = super::schema::get_all_students(&mut *tx).await?;
+    let mut student_ids = HashMap::new();
+    for student in &archive.students {
+        let candidate = CreateStudent {
+            name: student.name.clone(),
+            email: student.email.clone(),
+            github_username: student.github_username.clone(),
+            cohort: student.cohort.clone(),
+            cohort_id: None,
+        };
+        let matched = identity_service
+            .match_student(&candidate, &known_students)
+            .filter(|found| found.is_confident());
+
+        let id = match matched.map(|found| found.student_id) {
+            Some(id) => {
+                summary.students_matched += 1;
+                id
+            }
+            None => {
+                summary.students_created += 1;
+                sqlx::query(
+                    "INSERT INTO students (name, email, github_username, cohort, cohort_id, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+                )
+                .bind(&student.name)
+                .bind(&student.email)
+                .bind(&student.github_username)
+                .bind(&student.cohort)
+                .bind(student.cohort_id.and_then(|id| cohort_ids.get(&id).copied()))
+                .bind(student.created_at)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid()
+            }
+        };
+        if let Some(stored) = super::schema::get_student_by_id(&mut *tx, id).await? {
+            known_students.retain(|s| s.id != id);
+            known_students.push(stored);
+        }
+        student_ids.insert(student.id, id);
+    }
+
+    let mut team_ids = HashMap::new();
+    for team in &archive.teams {
+        let team_course = team.course_id.filter(|id| *id == archive.course.id).map(|_| course_id);
+        let team_assignment = team.assignment_id.and_then(|id| assignment_ids.get(&id).copied());
+
+        let existing: Option<i64> = sqlx::query_scalar(
+            "SELECT id FROM teams WHERE name = ? AND course_id IS ? AND assignment_id IS ?"
+        )
+        .bind(&team.name)
+        .bind(team_course)
+        .bind(team_assignment)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let id = match existing {
+            Some(id) => id,
+            None => sqlx::query("INSERT INTO teams (name, course_id, assignment_id, created_at) VALUES (?, ?, ?, ?)")
+                .bind(&team.name)
+                .bind(team_course)
+                .bind(team_assignment)
+                .bind(team.created_at)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid(),
+        };
+        team_ids.insert(team.id, id);
+    }
+
+    for member in &archive.team_members {
+        let (Some(team_id), Some(student_id)) = (team_ids.get(&member.team_id), student_ids.get(&member.student_id)) else {
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO team_members (team_id, student_id, role, grade_weight, created_at) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(team_id)
+        .bind(student_id)
+        .bind(&member.role)
+        .bind(member.grade_weight)
+        .bind(member.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut project_ids = HashMap::new();
+    let mut created_projects = Vec::new();
+    for project in &archive.projects {
+        let student_id = *student_ids
+            .get(&project.student_id)
+            .ok_or_else(|| anyhow!("Archive is missing the student for project {}", project.name))?;
+        let assignment_id = project.assignment_id.and_then(|id| assignment_ids.get(&id).copied());
+
+        // Only projects of this course are candidates; a repository submitted to another course stays separate
+        let existing: Option<i64> = match assignment_id {
+            Some(assignment_id) => sqlx::query_scalar("SELECT id FROM projects WHERE github_url = ? AND assignment_id = ?")
+                .bind(&project.github_url)
+                .bind(assignment_id)
+                .fetch_optional(&mut *tx)
+                .await?,
+            None => sqlx::query_scalar(
+                "SELECT p.id FROM projects p JOIN teams t ON t.id = p.team_id
+                 WHERE p.github_url = ? AND p.assignment_id IS NULL AND t.course_id = ?"
+            )
+            .bind(&project.github_url)
+            .bind(course_id)
+            .fetch_optional(&mut *tx)
+            .await?,
+        };
+
+        let id = match existing {
+            Some(id) => {
+                summary.projects_matched += 1;
+                id
+            }
+            None => {
+                summary.projects_created += 1;
+                let id = sqlx::query(
+                    "INSERT INTO projects (student_id, name, description, github_url, technology_stack, status, assignment_id, team_id,
+                                           created_at, submitted_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                )
+                .bind(student_id)
+                .bind(&project.name)
+                .bind(&project.description)
+                .bind(&project.github_url)
+                .bind(&project.technology_stack)
+                .bind(&project.status)
+                .bind(assignment_id)
+                .bind(project.team_id.and_then(|id| team_ids.get(&id).copied()))
+                .bind(project.created_at)
+                .bind(project.submitted_at)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+                created_projects.push(id);
+                id
+            }
+        };
+        project_ids.insert(project.id, id);
+    }
+
+    for result in &archive.analysis_results {
+        let Some(project_id) = project_ids.get(&result.project_id) else { continue };
+
+        let inserted = sqlx::query(
+            "INSERT INTO analysis_results (project_id, code_quality_score, structure_score, documentation_score, functionality_score,
+                                           total_score, feedback, analysis_data, commit_sha, analyzer_version, rubric_version, created_at)
+             SELECT ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+             WHERE NOT EXISTS (SELECT 1 FROM analysis_results WHERE project_id = ? AND julianday(created_at) = julianday(?))"
+        )
+        .bind(project_id)
+        .bind(result.code_quality_score)
+        .bind(result.structure_score)
+        .bind(result.documentation_score)
+        .bind(result.functionality_score)
+        .bind(result.total_score)
+        .bind(&result.feedback)
+        .bind(&result.analysis_data)
+        .bind(&result.commit_sha)
+        .bind(&result.analyzer_version)
+        .bind(&result.rubric_version)
+        .bind(result.created_at)
+        .bind(project_id)
+        .bind(result.created_at)
+        .execute(&mut *tx)
+        .await?;
+        summary.analysis_results_imported += inserted.rows_affected() as usize;
+    }
+
+    for comment in &archive.review_comments {
+        let Some(project_id) = project_ids.get(&comment.project_id) else { continue };
+
+        let inserted = sqlx::query(
+            "INSERT INTO review_comments (project_id, file_path, start_line, end_line, commit_sha, category, body, points_deducted,
+                                          author, outdated, original_commit_sha, original_file_path, original_start_line,
+                                          original_end_line, created_at, updated_at)
+             SELECT ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+             WHERE NOT EXISTS (SELECT 1 FROM review_comments WHERE project_id = ? AND original_commit_sha = ?
+                               AND original_file_path = ? AND original_start_line = ? AND body = ?)"
+        )
+        .bind(project_id)
+        .bind(&comment.file_path)
+        .bind(comment.start_line)
+        .bind(comment.end_line)
+        .bind(&comment.commit_sha)
+        .bind(&comment.category)
+        .bind(&comment.body)
+        .bind(comment.points_deducted)
+        .bind(&comment.author)
+        .bind(comment.outdated)
+        .bind(&comment.original_commit_sha)
+        .bind(&comment.original_file_path)
+        .bind(comment.original_start_line)
+        .bind(comment.original_end_line)
+        .bind(comment.created_at)
+        .bind(comment.updated_at)
+        .bind(project_id)
+        .bind(&comment.original_commit_sha)
+        .bind(&comment.original_file_path)
+        .bind(comment.original_start_line)
+        .bind(&comment.body)
+        .execute(&mut *tx)
+        .await?;
+        summary.review_comments_imported += inserted.rows_affected() as usize;
+    }
+
+    for adjustment in &archive.grade_adjustments {
+        let Some(project_id) = project_ids.get(&adjustment.project_id) else { continue };
+
+        let imported = sqlx::query(
+            "INSERT INTO grade_adjustments (project_id, criterion, automated_score, adjusted_score, max_score, justification, reviewer, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(project_id, criterion) DO UPDATE SET
+                automated_score = excluded.automated_score,
+                adjusted_score = excluded.adjusted_score,
+                max_score = excluded.max_score,
+                justification = excluded.justification,
+                reviewer = excluded.reviewer,
+                updated_at = excluded.updated_at
+             WHERE julianday(excluded.updated_at) > julianday(grade_adjustments.updated_at)"
+        )
+        .bind(project_id)
+        .bind(&adjustment.criterion)
+        .bind(adjustment.automated_score)
+        .bind(adjustment.adjusted_score)
+        .bind(adjustment.max_score)
+        .bind(&adjustment.justification)
+        .bind(&adjustment.reviewer)
+        .bind(adjustment.created_at)
+        .bind(adjustment.updated_at)
+        .execute(&mut *tx)
+        .await?;
+        summary.grade_adjustments_imported += imported.rows_affected() as usize;
+    }
+
+    for grade in &archive.final_grades {
+        let Some(project_id) = project_ids.get(&grade.project_id) else { continue };
+
+        let imported = sqlx::query(
+            "INSERT INTO final_grades (project_id, automated_score, final_score, notes, graded_by, graded_at, released_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(project_id) DO UPDATE SET
+                automated_score = excluded.automated_score,
+                final_score = excluded.final_score,
+                notes = excluded.notes,
+                graded_by = excluded.graded_by,
+                graded_at = excluded.graded_at,
+                released_at = COALESCE(final_grades.released_at, excluded.released_at)
+             WHERE julianday(excluded.graded_at) > julianday(final_grades.graded_at)"
+        )
+        .bind(project_id)
+        .bind(grade.automated_score)
+        .bind(grade.final_score)
+        .bind(&grade.notes)
+        .bind(&grade.graded_by)
+        .bind(grade.graded_at)
+        .bind(grade.released_at)
+        .execute(&mut *tx)
+        .await?;
+        summary.final_grades_imported += imported.rows_affected() as usize;
+    }
+
+    // History only makes sense for projects this import created; matched projects keep their own
+    for change in &archive.status_history {
+        let Some(project_id) = project_ids.get(&change.project_id).filter(|id| created_projects.contains(id)) else { continue };
+
+        sqlx::query(
+            "INSERT INTO project_status_history (project_id, from_status, to_status, note, created_at) VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(project_id)
+        .bind(&change.from_status)
+        .bind(&change.to_status)
+        .bind(&change.note)
+        .bind(change.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(summary)
+}
+
+async fn import_course(tx: &mut Transaction<'_, Sqlite>, course: &Course) -> Result<(i64, bool)> {
+    let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM courses WHERE code = ?")
+        .bind(&course.code)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    if let Some(id) = existing {
+        return Ok((id, false));
+    }
+
+    let id = sqlx::query("INSERT INTO courses (code, name, description, term, archived, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&course.code)
+        .bind(&course.name)
+        .bind(&course.description)
+        .bind(&course.term)
+        .bind(course.archived)
+        .bind(course.created_at)
+        .execute(&mut **tx)
+        .await?
+        .last_insert_rowid();
+
+    Ok((id, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{schema, test_support};
+
+    async fn course_with_submission(pool: &SqlitePool, code: &str, student: (&str, Option<&str>)) -> i64 {
+        let course_id = schema::create_course(pool, CreateCourse {
+            code: code.to_string(),
+            name: format!("{} course", code),
+            description: None,
+            term: None,
+            archived: None,
+        })
+        .await
+        .unwrap();
+        let assignment_id = schema::create_assignment(pool, CreateAssignment {
+            course_id,
+            cohort_id: None,
+            title: "Project 1".to_string(),
+            description: None,
+            due_at: None,
+            rubric: None,
+            starter_repo_url: None,
+            allowed_stacks: None,
+        })
+        .await
+        .unwrap();
+        let student_id = test_support::student(pool, student.0, student.1).await;
+        test_support::project(pool, student_id, Some(assignment_id)).await;
+        course_id
+    }
+
+    #[tokio::test]
+    async fn archives_hold_only_the_course_rows() {
+        let pool = test_support::pool().await;
+        let course_id = course_with_submission(&pool, "CS101", ("Ada", Some("ada@example.com"))).await;
+        course_with_submission(&pool, "CS202", ("Grace", Some("grace@example.com"))).await;
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database { pool, path: dir.path().join("r3viewer.db"), encrypted: false };
+        let destination = dir.path().join("cs101.zip");
+
+        let manifest = export_course_archive(&db, course_id, &destination).await.unwrap();
+        assert_eq!((manifest.course_code.as_str(), manifest.student_count, manifest.project_count), ("CS101", 1, 1));
+
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&destination).unwrap()).unwrap();
+        let mut entries: Vec<&str> = zip.file_names().collect();
+        entries.sort_unstable();
+        assert_eq!(entries, vec![SNAPSHOT_ENTRY, DATA_ENTRY, MANIFEST_ENTRY]);
+
+        let (_, archive) = read_course_archive(&destination).unwrap();
+        assert_eq!(archive.students.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Ada"]);
+        assert_eq!(archive.assignments.len(), 1);
+
+        // The snapshot is a migrated database holding the same rows
+        let snapshot_path = dir.path().join("snapshot.db");
+        let mut snapshot = Vec::new();
+        zip.by_name(SNAPSHOT_ENTRY).unwrap().read_to_end(&mut snapshot).unwrap();
+        std::fs::write(&snapshot_path, snapshot).unwrap();
+        let snapshot = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&snapshot_path)).await.unwrap();
+        let courses: Vec<String> = sqlx::query_scalar("SELECT code FROM courses").fetch_all(&snapshot).await.unwrap();
+        let students: Vec<String> = sqlx::query_scalar("SELECT name FROM students").fetch_all(&snapshot).await.unwrap();
+        let projects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects").fetch_one(&snapshot).await.unwrap();
+        assert_eq!((courses, students, projects), (vec!["CS101".to_string()], vec!["Ada".to_string()], 1));
+        assert!(!crate::database::applied_migrations(&snapshot).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unassigned_projects_only_match_within_the_course() {
+        let source = test_support::pool().await;
+        let course_id = schema::create_course(&source, CreateCourse {
+            code: "CS101".to_string(),
+            name: "Intro".to_string(),
+            description: None,
+            term: None,
+            archived: None,
+        })
+        .await
+        .unwrap();
+        let ada = test_support::student(&source, "Ada", Some("ada@example.com")).await;
+        let team_id = schema::create_team(&mut source.acquire().await.unwrap(), CreateTeam {
+            name: "Engines".to_string(),
+            course_id: Some(course_id),
+            assignment_id: None,
+            member_ids: vec![ada],
+        })
+        .await
+        .unwrap();
+        let shared_url = "https://github.com/org/shared".to_string();
+        schema::create_project(&source, CreateProject {
+            student_id: ada,
+            name: "Engine".to_string(),
+            description: None,
+            github_url: shared_url.clone(),
+            technology_stack: None,
+            assignment_id: None,
+            team_id: Some(team_id),
+        })
+        .await
+        .unwrap();
+        let archive = load_course_archive(&source, course_id).await.unwrap();
+
+        // Same repository, unassigned, in no course at all
+        let target = test_support::pool().await;
+        let grace = test_support::student(&target, "Grace", Some("grace@example.com")).await;
+        schema::create_project(&target, CreateProject {
+            student_id: grace,
+            name: "Fork".to_string(),
+            description: None,
+            github_url: shared_url.clone(),
+            technology_stack: None,
+            assignment_id: None,
+            team_id: None,
+        })
+        .await
+        .unwrap();
+
+        let summary = import_course_archive(&target, &IdentityService::new(), &archive).await.unwrap();
+        assert_eq!((summary.projects_created, summary.projects_matched), (1, 0));
+
+        // Importing again finds the course's own copy
+        let again = import_course_archive(&target, &IdentityService::new(), &archive).await.unwrap();
+        assert_eq!((again.projects_created, again.projects_matched), (0, 1));
+        let shared = schema::get_all_projects(&target).await.unwrap().into_iter().filter(|p| p.github_url == shared_url).count();
+        assert_eq!(shared, 2);
+    }
+
+    #[tokio::test]
+    async fn imports_reuse_students_only_on_a_confident_match() {
+        let source = test_support::pool().await;
+        let course_id = course_with_submission(&source, "CS101", ("Ada Lovelace", Some("ada@example.com"))).await;
+        let name_only = test_support::student(&source, "Grace Hopper", None).await;
+        let assignment_id = schema::get_assignments(&source, Some(course_id)).await.unwrap()[0].id;
+        test_support::project(&source, name_only, Some(assignment_id)).await;
+        let archive = load_course_archive(&source, course_id).await.unwrap();
+
+        let target = test_support::pool().await;
+        let ada = test_support::student(&target, "A. Lovelace", Some("ADA@example.com")).await;
+        test_support::student(&target, "Grace Hopper", None).await;
+
+        let summary = import_course_archive(&target, &IdentityService::new(), &archive).await.unwrap();
+        assert_eq!((summary.students_matched, summary.students_created), (1, 1));
+        assert_eq!((summary.projects_created, summary.course_created), (2, true));
+
+        let projects = schema::get_all_projects(&target).await.unwrap();
+        assert!(projects.iter().any(|p| p.student_id == ada));
+        // A shared name is left for the duplicate review instead of being merged silently
+        assert_eq!(schema::get_all_students(&target).await.unwrap().len(), 3);
+    }
+}
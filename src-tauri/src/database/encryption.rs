@@ -0,0 +1,262 @@
+use anyhow::Result;
+use keyring::Entry;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, SqliteConnection, sqlite::SqliteConnectOptions};
+use std::path::{Path, PathBuf};
+
+// Same keyring service the auth tokens live under
+const KEYRING_SERVICE: &str = "r3viewer";
+const KEYRING_KEY_NAME: &str = "database_key";
+const ENCRYPTION_ENV_VAR: &str = "R3VIEWER_DB_ENCRYPTION";
+const PENDING_SUFFIX: &str = "encrypted";
+const REQUEST_SUFFIX: &str = "encrypt";
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub encrypted: bool,
+    pub key_available: bool,
+    // The plaintext file is encrypted on next start
+    pub pending_restart: bool,
+}
+
+// Plaintext SQLite files start with a fixed header; SQLCipher pages are indistinguishable from noise
+pub fn is_plaintext_database(path: &Path) -> Result<bool> {
+    use std::io::Read;
+
+    let mut header = [0u8; 16];
+    let mut file = std::fs::File::open(path)?;
+    let read = file.read(&mut header)?;
+
+    // Empty files are created by sqlx before the first page is written
+    Ok(read == 0 || (read == header.len() && &header == SQLITE_HEADER))
+}
+
+// Opt in for fresh databases with R3VIEWER_DB_ENCRYPTION=1
+pub fn encryption_requested_by_env() -> bool {
+    matches!(
+        std::env::var(ENCRYPTION_ENV_VAR).map(|v| v.trim().to_lowercase()).as_deref(),
+        Ok("1") | Ok("true") | Ok("on") | Ok("yes")
+    )
+}
+
+pub fn load_key() -> Result<Option<String>> {
+    match Entry::new(KEYRING_SERVICE, KEYRING_KEY_NAME)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn load_or_create_key() -> Result<String> {
+    if let Some(key) = load_key()? {
+        return Ok(key);
+    }
+
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let key: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    Entry::new(KEYRING_SERVICE, KEYRING_KEY_NAME)?.set_password(&key)?;
+
+    Ok(key)
+}
+
+// SQLCipher raw-key syntax, skips the passphrase KDF since the key is already random
+pub fn key_pragma_value(key: &str) -> String {
+    format!("\"x'{}'\"", key)
+}
+
+fn sidecar_path(database_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = database_path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Where the encrypted copy is written before it replaces the plaintext file
+fn pending_path(database_path: &Path) -> PathBuf {
+    sidecar_path(database_path, &format!(".{}", PENDING_SUFFIX))
+}
+
+// Marks the database for encryption on the next start
+pub fn request_path(database_path: &Path) -> PathBuf {
+    sidecar_path(database_path, &format!(".{}", REQUEST_SUFFIX))
+}
+
+// The export runs at startup, before the pool opens, so nothing written after the request is left behind
+pub fn request_encryption(database_path: &Path) -> Result<()> {
+    load_or_create_key()?;
+    std::fs::write(request_path(database_path), b"")?;
+
+    Ok(())
+}
+
+// Encrypts a plaintext database when requested; must run before anything opens the file
+pub async fn apply_requested_encryption(database_path: &Path, backup_dir: &Path) -> Result<bool> {
+    let request = request_path(database_path);
+    if !request.exists() {
+        return Ok(false);
+    }
+
+    if !database_path.exists() || !is_plaintext_database(database_path)? {
+        std::fs::remove_file(&request)?;
+        return Ok(false);
+    }
+
+    // Without the key the copy would be unreadable; keep the plaintext database working
+    let Some(key) = load_key()? else {
+        eprintln!("⚠️  Encryption was requested for {} but no database key is in the keyring", database_path.display());
+        return Ok(false);
+    };
+
+    encrypt_in_place(database_path, backup_dir, &key).await?;
+    std::fs::remove_file(&request)?;
+
+    Ok(true)
+}
+
+// Exports to an encrypted copy, swaps it in and removes every plaintext trace of the old file
+async fn encrypt_in_place(database_path: &Path, backup_dir: &Path, key: &str) -> Result<()> {
+    replace_with_encrypted_copy(database_path, key).await?;
+
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(sidecar_path(database_path, suffix));
+    }
+
+    // Plaintext backups would defeat the point, but manual ones may be the only copy a reviewer has,
+    // so every backup is encrypted with the same key rather than deleted
+    if backup_dir.exists() {
+        for entry in std::fs::read_dir(backup_dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("db") || !is_plaintext_database(&path).unwrap_or(false) {
+                continue;
+            }
+            // Backups are listed and pruned by modification time, which the swap would reset
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            match replace_with_encrypted_copy(&path, key).await {
+                Ok(()) => {
+                    if let Some(modified) = modified {
+                        let _ = std::fs::File::options().write(true).open(&path).and_then(|file| file.set_modified(modified));
+                    }
+                    println!("🔒 Encrypted backup {}", path.display());
+                }
+                Err(e) => eprintln!("⚠️  Backup {} is still unencrypted: {}", path.display(), e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn replace_with_encrypted_copy(path: &Path, key: &str) -> Result<()> {
+    let target = pending_path(path);
+    if target.exists() {
+        std::fs::remove_file(&target)?;
+    }
+
+    // ATTACH and sqlcipher_export must run on the same connection. ATTACH opens files with the
+    // connection's flags, so it needs the create flag to write the copy.
+    let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+    let mut conn = SqliteConnection::connect_with(&options).await?;
+
+    sqlx::query("ATTACH DATABASE ? AS encrypted KEY ?")
+        .bind(target.to_string_lossy().to_string())
+        .bind(format!("x'{}'", key))
+        .execute(&mut conn)
+        .await?;
+
+    let exported = sqlx::query("SELECT sqlcipher_export('encrypted')")
+        .execute(&mut conn)
+        .await;
+
+    sqlx::query("DETACH DATABASE encrypted")
+        .execute(&mut conn)
+        .await?;
+    conn.close().await?;
+
+    if let Err(e) = exported {
+        let _ = std::fs::remove_file(&target);
+        return Err(anyhow::anyhow!("SQLCipher export failed ({}). Is this build linked against SQLCipher?", e));
+    }
+
+    std::fs::rename(&target, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff";
+
+    async fn plaintext_database(path: &Path) {
+        let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query("CREATE TABLE notes (body TEXT)").execute(&mut conn).await.unwrap();
+        sqlx::query("INSERT INTO notes VALUES ('written right before restart')").execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+    }
+
+    async fn read_note(path: &Path) -> String {
+        let options = SqliteConnectOptions::new().filename(path).pragma("key", key_pragma_value(KEY));
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        let (body,): (String,) = sqlx::query_as("SELECT body FROM notes").fetch_one(&mut conn).await.unwrap();
+        body
+    }
+
+    #[tokio::test]
+    async fn encrypting_keeps_the_data_and_encrypts_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("r3viewer.db");
+        let backup_dir = dir.path().join("backups");
+        plaintext_database(&database_path).await;
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        let backups = [
+            backup_dir.join("r3viewer-v3-20260101000000.db"),
+            backup_dir.join("r3viewer-backup-20260102000000.db"),
+            backup_dir.join("r3viewer-pre-restore-20260103000000.db"),
+        ];
+        for backup in &backups {
+            std::fs::copy(&database_path, backup).unwrap();
+        }
+
+        encrypt_in_place(&database_path, &backup_dir, KEY).await.unwrap();
+
+        assert!(!is_plaintext_database(&database_path).unwrap());
+        assert!(!pending_path(&database_path).exists());
+        assert_eq!(read_note(&database_path).await, "written right before restart");
+
+        // Manual and pre-restore backups may be the only copies a reviewer has; none is deleted
+        assert_eq!(std::fs::read_dir(&backup_dir).unwrap().count(), backups.len());
+        for backup in &backups {
+            assert!(!is_plaintext_database(backup).unwrap());
+            assert_eq!(read_note(backup).await, "written right before restart");
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_for_encrypted_databases_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join("r3viewer.db");
+        plaintext_database(&database_path).await;
+        encrypt_in_place(&database_path, &dir.path().join("backups"), KEY).await.unwrap();
+        std::fs::write(request_path(&database_path), b"").unwrap();
+
+        assert!(!apply_requested_encryption(&database_path, &dir.path().join("backups")).await.unwrap());
+        assert!(!request_path(&database_path).exists());
+    }
+
+    #[test]
+    fn plaintext_is_detected_from_the_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.db");
+        let noise = dir.path().join("noise.db");
+        std::fs::write(&empty, b"").unwrap();
+        std::fs::write(&noise, [7u8; 64]).unwrap();
+
+        assert!(is_plaintext_database(&empty).unwrap());
+        assert!(!is_plaintext_database(&noise).unwrap());
+    }
+}
@@ -1,16 +1,75 @@
-use sqlx::{SqlitePool, sqlite::SqlitePoolOptions, migrate::MigrateDatabase};
-use std::path::PathBuf;
-use tauri::AppHandle;
+use sqlx::{SqlitePool, sqlite::{SqliteConnectOptions, SqlitePoolOptions}, migrate::{MigrateDatabase, Migrator}};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tauri::{AppHandle, Manager};
 use anyhow::Result;
 
+pub mod archive;
+pub mod encryption;
 pub mod models;
 pub mod schema;
 
 pub use models::*;
 
+// Versioned migrations live in src-tauri/migrations as NNNN_description.sql.
+// Applied migrations are checksummed, so never edit one that has shipped; add a new file instead.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+const DATABASE_FILE_NAME: &str = "r3viewer.db";
+const BACKUP_DIR_NAME: &str = "backups";
+const MAX_PRE_MIGRATION_BACKUPS: usize = 5;
+const MAX_MANUAL_BACKUPS: usize = 10;
+const PRE_MIGRATION_PREFIX: &str = "r3viewer-v";
+const MANUAL_PREFIX: &str = "r3viewer-backup-";
+const PRE_RESTORE_PREFIX: &str = "r3viewer-pre-restore-";
+const PENDING_RESTORE_SUFFIX: &str = "restore";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub automatic: bool, // taken before a schema migration or a restore
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RestoreStatus {
+    pub pending_restart: bool,
+    pub pre_restore_backup: String, // the current database, in case the wrong backup was picked
+    pub warning: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DatabaseError {
+    #[error("The database at {path} uses schema version {found}, but this version of r3viewer only supports up to version {supported}. Please update r3viewer to open it.")]
+    SchemaTooNew {
+        path: String,
+        found: i64,
+        supported: i64,
+    },
+    #[error("The database at {path} was migrated with a different copy of migration {version}. Restore a backup from {backup_dir} or reinstall the matching r3viewer version.")]
+    MigrationMismatch {
+        path: String,
+        version: i64,
+        backup_dir: String,
+    },
+    #[error("{path} is not an r3viewer database backup: {reason}")]
+    InvalidBackup {
+        path: String,
+        reason: String,
+    },
+    #[error("The database at {path} is encrypted, but its key is missing from the system keyring. Restore the keyring entry or move the file aside to start fresh.")]
+    EncryptionKeyMissing {
+        path: String,
+    },
+}
+
 #[derive(Clone)]
 pub struct Database {
     pub pool: SqlitePool,
+    pub path: PathBuf,
+    pub encrypted: bool,
 }
 
 impl Database {
@@ -19,29 +78,550 @@ impl Database {
             .path()
             .app_data_dir()
             .expect("failed to resolve app data directory");
-        
+
         std::fs::create_dir_all(&app_dir)?;
-        
-        let database_path = app_dir.join("r3viewer.db");
+
+        let database_path = app_dir.join(DATABASE_FILE_NAME);
         let database_url = format!("sqlite://{}", database_path.display());
-        
+
+        // Both run before the pool opens; restoring first means a restored file still gets encrypted
+        if apply_pending_restore(&database_path)? {
+            println!("♻️  Restored database from backup");
+        }
+
+        if encryption::apply_requested_encryption(&database_path, &backup_dir(&database_path)).await? {
+            println!("🔒 Encrypted the database");
+        }
+
         // Create database if it doesn't exist
-        if !sqlx::Sqlite::database_exists(&database_url).await? {
+        let fresh = !sqlx::Sqlite::database_exists(&database_url).await?;
+        if fresh {
             sqlx::Sqlite::create_database(&database_url).await?;
         }
-        
+
+        let encrypted = if fresh {
+            encryption::encryption_requested_by_env()
+        } else {
+            !encryption::is_plaintext_database(&database_path)?
+        };
+
+        let options = connect_options(&database_url, encrypted, fresh, &database_path)?;
+
         let pool = SqlitePoolOptions::new()
             .max_connections(10)
-            .connect(&database_url)
+            .connect_with(options)
             .await?;
-        
-        // Run migrations
-        sqlx::migrate!("./migrations").run(&pool).await?;
-        
-        Ok(Database { pool })
+
+        Self::migrate(&pool, &database_path).await?;
+
+        Ok(Database { pool, path: database_path, encrypted })
+    }
+
+    pub fn encryption_status(&self) -> Result<encryption::EncryptionStatus> {
+        Ok(encryption::EncryptionStatus {
+            encrypted: self.encrypted,
+            key_available: encryption::load_key()?.is_some(),
+            pending_restart: self.encryption_pending(),
+        })
+    }
+
+    fn encryption_pending(&self) -> bool {
+        !self.encrypted && encryption::request_path(&self.path).exists()
+    }
+
+    // Takes effect on the next start, see encryption::apply_requested_encryption
+    pub fn enable_encryption(&self) -> Result<encryption::EncryptionStatus> {
+        // The staged backup would replace the file right before it is encrypted; make the order explicit
+        if self.restore_pending() {
+            return Err(anyhow::anyhow!("A restore is waiting for a restart; restart r3viewer before enabling encryption"));
+        }
+
+        if !self.encrypted {
+            encryption::request_encryption(&self.path)?;
+        }
+
+        self.encryption_status()
+    }
+
+    pub async fn create_backup(&self) -> Result<BackupInfo> {
+        let dir = backup_dir(&self.path);
+        let path = snapshot_to(&self.pool, &dir, MANUAL_PREFIX).await?;
+
+        prune_backups(&dir, MANUAL_PREFIX, MAX_MANUAL_BACKUPS)?;
+
+        backup_info(&path)
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>> {
+        let dir = backup_dir(&self.path);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups: Vec<BackupInfo> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("db"))
+            .filter_map(|path| backup_info(&path).ok())
+            .collect();
+
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(backups)
+    }
+
+    // The live file stays open until restart, so the backup is staged and swapped in by Database::new.
+    // The current database is kept as a pre-restore backup in case the wrong file was picked.
+    pub async fn stage_restore(&self, backup_path: &Path) -> Result<RestoreStatus> {
+        if self.encryption_pending() {
+            return Err(anyhow::anyhow!("Encryption is waiting for a restart; restart r3viewer before restoring a backup"));
+        }
+
+        self.validate_backup(backup_path).await?;
+
+        let dir = backup_dir(&self.path);
+        let pre_restore_backup = snapshot_to(&self.pool, &dir, PRE_RESTORE_PREFIX).await?;
+        prune_backups(&dir, PRE_RESTORE_PREFIX, MAX_PRE_MIGRATION_BACKUPS)?;
+
+        std::fs::copy(backup_path, pending_restore_path(&self.path))?;
+
+        Ok(RestoreStatus {
+            pending_restart: true,
+            pre_restore_backup: pre_restore_backup.display().to_string(),
+            warning: "The backup replaces the database when r3viewer restarts. Changes made before then are \
+                      discarded and are not in the pre-restore backup either, so restart now."
+                .to_string(),
+        })
+    }
+
+    pub fn restore_pending(&self) -> bool {
+        pending_restore_path(&self.path).exists()
+    }
+
+    async fn validate_backup(&self, backup_path: &Path) -> Result<()> {
+        let invalid = |reason: String| DatabaseError::InvalidBackup {
+            path: backup_path.display().to_string(),
+            reason,
+        };
+
+        if !backup_path.is_file() {
+            return Err(invalid("file not found".to_string()).into());
+        }
+
+        let encrypted = !encryption::is_plaintext_database(backup_path)?;
+
+        // Restoring a plaintext copy would silently undo encryption at rest
+        if self.encrypted && !encrypted {
+            return Err(invalid("it is unencrypted and this database is encrypted".to_string()).into());
+        }
+
+        let url = format!("sqlite://{}", backup_path.display());
+        let options = connect_options(&url, encrypted, false, backup_path)?.read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(|e| invalid(e.to_string()))?;
+
+        let applied = applied_migrations(&pool).await.map_err(|e| invalid(e.to_string()));
+        pool.close().await;
+        let applied = applied?;
+
+        let version = applied.iter().map(|(version, _)| *version).max().unwrap_or(0);
+        if version == 0 {
+            return Err(invalid("it has no r3viewer schema".to_string()).into());
+        }
+        if version > Self::supported_schema_version() {
+            return Err(DatabaseError::SchemaTooNew {
+                path: backup_path.display().to_string(),
+                found: version,
+                supported: Self::supported_schema_version(),
+            }
+            .into());
+        }
+
+        Ok(())
     }
-    
-    pub async fn initialize_schema(&self) -> Result<()> {
-        schema::create_tables(&self.pool).await
+
+    // Latest schema version this build knows how to create
+    pub fn supported_schema_version() -> i64 {
+        MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0)
+    }
+
+    pub async fn schema_version(&self) -> Result<i64> {
+        let applied = applied_migrations(&self.pool).await?;
+        Ok(applied.iter().map(|(version, _)| *version).max().unwrap_or(0))
+    }
+
+    async fn migrate(pool: &SqlitePool, database_path: &Path) -> Result<()> {
+        let applied = applied_migrations(pool).await?;
+        let supported = Self::supported_schema_version();
+        let current = applied.iter().map(|(version, _)| *version).max().unwrap_or(0);
+        let backup_dir = backup_dir(database_path);
+
+        // A newer app version has already touched this database; migrating would fail or lose data
+        if current > supported {
+            return Err(DatabaseError::SchemaTooNew {
+                path: database_path.display().to_string(),
+                found: current,
+                supported,
+            }
+            .into());
+        }
+
+        for (version, checksum) in &applied {
+            if let Some(migration) = MIGRATOR.iter().find(|m| m.version == *version) {
+                if migration.checksum.as_ref() != checksum.as_slice() {
+                    return Err(DatabaseError::MigrationMismatch {
+                        path: database_path.display().to_string(),
+                        version: *version,
+                        backup_dir: backup_dir.display().to_string(),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let pending: Vec<i64> = MIGRATOR
+            .iter()
+            .map(|m| m.version)
+            .filter(|version| !applied.iter().any(|(applied_version, _)| applied_version == version))
+            .collect();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        // Fresh databases have nothing worth keeping
+        if current > 0 {
+            let backup_path = snapshot_to(pool, &backup_dir, &format!("{}{}-", PRE_MIGRATION_PREFIX, current)).await?;
+            prune_backups(&backup_dir, PRE_MIGRATION_PREFIX, MAX_PRE_MIGRATION_BACKUPS)?;
+            println!("💾 Backed up database to {} before migrating", backup_path.display());
+        }
+
+        println!("🗄️  Migrating database schema from version {} to {}", current, supported);
+        MIGRATOR.run(pool).await?;
+
+        Ok(())
+    }
+}
+
+async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<(i64, Vec<u8>)>> {
+    let table_exists: Option<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'"
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if table_exists.is_none() {
+        return Ok(Vec::new());
     }
-} 
\ No newline at end of file
+
+    let rows: Vec<(i64, Vec<u8>)> = sqlx::query_as(
+        "SELECT version, checksum FROM _sqlx_migrations WHERE success = 1 ORDER BY version"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+fn backup_dir(database_path: &Path) -> PathBuf {
+    database_path
+        .parent()
+        .map(|dir| dir.join(BACKUP_DIR_NAME))
+        .unwrap_or_else(|| PathBuf::from(BACKUP_DIR_NAME))
+}
+
+fn connect_options(database_url: &str, encrypted: bool, fresh: bool, database_path: &Path) -> Result<SqliteConnectOptions> {
+    let mut options = SqliteConnectOptions::from_str(database_url)?;
+    if encrypted {
+        let key = if fresh {
+            encryption::load_or_create_key()?
+        } else {
+            encryption::load_key()?.ok_or_else(|| DatabaseError::EncryptionKeyMissing {
+                path: database_path.display().to_string(),
+            })?
+        };
+        // sqlx issues the key pragma before any other statement, as SQLCipher requires
+        options = options.pragma("key", encryption::key_pragma_value(&key));
+    }
+
+    Ok(options)
+}
+
+// VACUUM INTO is SQLite's online backup: it writes a consistent copy even while the pool holds
+// connections, and under SQLCipher the copy is encrypted with the same key
+async fn snapshot_to(pool: &SqlitePool, backup_dir: &Path, prefix: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(backup_dir)?;
+
+    let backup_path = backup_dir.join(backup_file_name(backup_dir, prefix));
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup_path.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+
+    Ok(backup_path)
+}
+
+// Microseconds keep back-to-back backups apart; the counter covers clocks too coarse for that
+fn backup_file_name(backup_dir: &Path, prefix: &str) -> String {
+    let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S%6f");
+    let mut file_name = format!("{}{}.db", prefix, timestamp);
+    let mut counter = 1;
+    while backup_dir.join(&file_name).exists() {
+        file_name = format!("{}{}-{}.db", prefix, timestamp, counter);
+        counter += 1;
+    }
+
+    file_name
+}
+
+fn backup_info(path: &Path) -> Result<BackupInfo> {
+    let metadata = std::fs::metadata(path)?;
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    Ok(BackupInfo {
+        automatic: !file_name.starts_with(MANUAL_PREFIX),
+        file_name,
+        path: path.display().to_string(),
+        size_bytes: metadata.len(),
+        created_at: metadata.modified()?.into(),
+    })
+}
+
+fn pending_restore_path(database_path: &Path) -> PathBuf {
+    let mut name = database_path.as_os_str().to_owned();
+    name.push(".");
+    name.push(PENDING_RESTORE_SUFFIX);
+    PathBuf::from(name)
+}
+
+fn apply_pending_restore(database_path: &Path) -> Result<bool> {
+    let pending = pending_restore_path(database_path);
+    if !pending.exists() {
+        return Ok(false);
+    }
+
+    std::fs::rename(&pending, database_path)?;
+
+    // Stale WAL pages from the old file would be replayed onto the restored one
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = database_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(sidecar));
+    }
+
+    Ok(true)
+}
+
+fn prune_backups(backup_dir: &Path, prefix: &str, keep: usize) -> Result<()> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(prefix) && name.ends_with(".db"))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    // Timestamped names sort chronologically within a version; order by modification time to be safe
+    backups.sort_by_key(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok());
+
+    let excess = backups.len().saturating_sub(keep);
+    for path in backups.into_iter().take(excess) {
+        let _ = std::fs::remove_file(path);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+    use crate::database::schema;
+
+    // A migrated in-memory database. A single connection, since every connection to :memory: is its own database.
+    pub async fn pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        pool
+    }
+
+    pub async fn student(pool: &SqlitePool, name: &str, email: Option<&str>) -> i64 {
+        github_student(pool, name, email, None).await
+    }
+
+    pub async fn github_student(pool: &SqlitePool, name: &str, email: Option<&str>, github_username: Option<&str>) -> i64 {
+        schema::create_student(pool, CreateStudent {
+            name: name.to_string(),
+            email: email.map(str::to_string),
+            github_username: github_username.map(str::to_string),
+            cohort: None,
+            cohort_id: None,
+        })
+        .await
+        .unwrap()
+    }
+
+    pub async fn project(pool: &SqlitePool, student_id: i64, assignment_id: Option<i64>) -> i64 {
+        schema::create_project(pool, CreateProject {
+            student_id,
+            name: "project".to_string(),
+            description: None,
+            github_url: format!("https://github.com/student{}/project", student_id),
+            technology_stack: None,
+            assignment_id,
+            team_id: None,
+        })
+        .await
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrates_fresh_database_to_supported_version() {
+        let pool = memory_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join(DATABASE_FILE_NAME);
+
+        assert!(applied_migrations(&pool).await.unwrap().is_empty());
+
+        Database::migrate(&pool, &database_path).await.unwrap();
+
+        let applied = applied_migrations(&pool).await.unwrap();
+        assert_eq!(applied.last().map(|(version, _)| *version), Some(Database::supported_schema_version()));
+        // Nothing to back up on a fresh database
+        assert!(!backup_dir(&database_path).exists());
+    }
+
+    #[tokio::test]
+    async fn refuses_database_from_newer_version() {
+        let pool = memory_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join(DATABASE_FILE_NAME);
+        Database::migrate(&pool, &database_path).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) VALUES (?, 'future', 1, x'00', 0)"
+        )
+        .bind(Database::supported_schema_version() + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let error = Database::migrate(&pool, &database_path).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<DatabaseError>(), Some(DatabaseError::SchemaTooNew { .. })));
+    }
+
+    #[tokio::test]
+    async fn refuses_edited_migration() {
+        let pool = memory_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let database_path = dir.path().join(DATABASE_FILE_NAME);
+        Database::migrate(&pool, &database_path).await.unwrap();
+
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = x'00' WHERE version = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let error = Database::migrate(&pool, &database_path).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<DatabaseError>(),
+            Some(DatabaseError::MigrationMismatch { version: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn prune_backups_keeps_newest_with_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for (index, name) in ["r3viewer-v1-a.db", "r3viewer-v1-b.db", "r3viewer-v1-c.db", "r3viewer-backup-a.db"].iter().enumerate() {
+            let file = std::fs::File::create(dir.path().join(name)).unwrap();
+            file.set_modified(base + std::time::Duration::from_secs(index as u64 * 60)).unwrap();
+        }
+
+        prune_backups(dir.path(), PRE_MIGRATION_PREFIX, 2).unwrap();
+
+        let mut remaining: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["r3viewer-backup-a.db", "r3viewer-v1-b.db", "r3viewer-v1-c.db"]);
+    }
+
+    #[tokio::test]
+    async fn encryption_and_restore_cannot_both_wait_for_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = Database {
+            pool: memory_pool().await,
+            path: dir.path().join(DATABASE_FILE_NAME),
+            encrypted: false,
+        };
+
+        std::fs::write(encryption::request_path(&database.path), b"").unwrap();
+        let error = database.stage_restore(&dir.path().join("backup.db")).await.unwrap_err();
+        assert!(error.to_string().contains("Encryption is waiting"));
+        assert!(!database.restore_pending());
+
+        std::fs::remove_file(encryption::request_path(&database.path)).unwrap();
+        std::fs::write(pending_restore_path(&database.path), b"").unwrap();
+        let error = database.enable_encryption().unwrap_err();
+        assert!(error.to_string().contains("restore is waiting"));
+        assert!(!encryption::request_path(&database.path).exists());
+    }
+
+    #[test]
+    fn backup_names_stay_unique_within_a_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut names = std::collections::HashSet::new();
+        for _ in 0..5 {
+            let name = backup_file_name(dir.path(), MANUAL_PREFIX);
+            std::fs::write(dir.path().join(&name), b"").unwrap();
+            assert!(names.insert(name));
+        }
+    }
+
+    #[tokio::test]
+    async fn staged_restores_warn_that_later_changes_are_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE_NAME);
+        // File-backed like the real database, so backups go through the same VACUUM INTO path
+        let pool = SqlitePoolOptions::new()
+            .connect_with(SqliteConnectOptions::new().filename(&path).create_if_missing(true))
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        let database = Database { pool, path, encrypted: false };
+
+        let backup = database.create_backup().await.unwrap();
+        let status = database.stage_restore(Path::new(&backup.path)).await.unwrap();
+
+        assert!(status.pending_restart && database.restore_pending());
+        assert!(status.warning.contains("discarded"));
+        assert!(Path::new(&status.pre_restore_backup).exists());
+        assert_ne!(status.pre_restore_backup, backup.path);
+    }
+}
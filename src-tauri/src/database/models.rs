@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -10,6 +10,7 @@ pub struct Student {
     pub email: Option<String>,
     pub github_username: Option<String>,
     pub cohort: Option<String>,
+    pub cohort_id: Option<i64>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -21,7 +22,80 @@ pub struct Project {
     pub description: Option<String>,
     pub github_url: String,
     pub technology_stack: Option<String>, // JSON array as string
-    pub status: String, // 'pending', 'analyzing', 'completed', 'failed'
+    pub status: String, // see ProjectStatus
+    pub assignment_id: Option<i64>,
+    pub team_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>, // HEAD commit time of the last analyzed clone
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Team {
+    pub id: i64,
+    pub name: String,
+    pub course_id: Option<i64>,
+    pub assignment_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TeamMember {
+    pub team_id: i64,
+    pub student_id: i64,
+    pub role: Option<String>,
+    pub grade_weight: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProjectContribution {
+    pub id: i64,
+    pub project_id: i64,
+    pub student_id: Option<i64>,
+    pub author_name: String,
+    pub author_email: String,
+    pub commits: i64,
+    pub lines_added: i64,
+    pub lines_deleted: i64,
+    pub files_touched: i64,
+    pub share: f64,
+    pub first_commit_at: Option<DateTime<Utc>>,
+    pub last_commit_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Course {
+    pub id: i64,
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub term: Option<String>,
+    pub archived: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Cohort {
+    pub id: i64,
+    pub course_id: i64,
+    pub name: String,
+    pub starts_on: Option<NaiveDate>,
+    pub ends_on: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Assignment {
+    pub id: i64,
+    pub course_id: i64,
+    pub cohort_id: Option<i64>,
+    pub title: String,
+    pub description: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub rubric: Option<String>, // JSON as string
+    pub starter_repo_url: Option<String>,
+    pub allowed_stacks: Option<String>, // JSON array as string
     pub created_at: DateTime<Utc>,
 }
 
@@ -36,6 +110,9 @@ pub struct AnalysisResult {
     pub total_score: Option<i32>,
     pub feedback: Option<String>,
     pub analysis_data: Option<String>, // JSON as string
+    pub commit_sha: Option<String>,
+    pub analyzer_version: Option<String>,
+    pub rubric_version: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -46,25 +123,277 @@ pub struct PlaygroundSession {
     pub container_id: Option<String>,
     pub port: Option<i32>,
     pub status: String, // 'starting', 'running', 'stopped', 'error'
+    pub snapshot_id: Option<i64>, // Set when the session was restored from a snapshot
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PlaygroundSnapshot {
+    pub id: i64,
+    pub project_id: i64,
+    pub session_id: i64,
+    pub label: String,
+    pub image: String,
+    pub workspace_path: String,
+    pub environment: String, // JSON as string
+    pub size_bytes: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SmokeScenario {
+    pub id: i64,
+    pub assignment_id: Option<i64>,
+    pub name: String,
+    pub steps: String, // JSON array of smoke steps
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SmokeTestRun {
+    pub id: i64,
+    pub project_id: i64,
+    pub playground_session_id: Option<i64>,
+    pub scenario_id: i64,
+    pub passed: bool,
+    pub total_steps: i32,
+    pub passed_steps: i32,
+    pub results: Option<String>, // JSON as string
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GradeAdjustment {
+    pub id: i64,
+    pub project_id: i64,
+    pub criterion: String,
+    pub automated_score: Option<i32>,
+    pub adjusted_score: i32,
+    pub max_score: Option<i32>,
+    pub justification: String,
+    pub reviewer: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FinalGrade {
+    pub project_id: i64,
+    pub automated_score: Option<i32>,
+    pub final_score: i32,
+    pub notes: Option<String>,
+    pub graded_by: Option<String>,
+    pub graded_at: DateTime<Utc>,
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProjectStatusChange {
+    pub id: i64,
+    pub project_id: i64,
+    pub from_status: String,
+    pub to_status: String,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ReviewComment {
+    pub id: i64,
+    pub project_id: i64,
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub commit_sha: String,
+    pub category: String, // see CommentCategory
+    pub body: String,
+    pub points_deducted: Option<i32>,
+    pub author: Option<String>,
+    pub outdated: bool,
+    pub original_commit_sha: String,
+    pub original_file_path: String,
+    pub original_start_line: i64,
+    pub original_end_line: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RosterSource {
+    pub id: i64,
+    pub name: String,
+    pub spreadsheet_id: String,
+    pub range: String,
+    pub mapping: String, // SheetMapping as JSON
+    pub course_id: Option<i64>,
+    pub assignment_id: Option<i64>,
+    pub interval_minutes: i64,
+    pub enabled: bool,
+    pub conflict_policy: String, // see ConflictPolicy
+    pub status_column: Option<String>,
+    pub score_column: Option<String>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RosterSourceRow {
+    pub source_id: i64,
+    pub row_key: String,
+    pub row_number: i64,
+    pub row_data: String, // StudentData as JSON
+    pub student_id: Option<i64>,
+    pub project_id: Option<i64>,
+    pub removed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RosterSyncRun {
+    pub id: i64,
+    pub source_id: i64,
+    pub added: i64,
+    pub changed: i64,
+    pub removed: i64,
+    pub unchanged: i64,
+    pub written_back: i64,
+    pub conflicts: Option<String>, // JSON array as string
+    pub errors: Option<String>, // JSON array as string
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
 // Input DTOs for creating new records
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateStudent {
     pub name: String,
     pub email: Option<String>,
     pub github_username: Option<String>,
     pub cohort: Option<String>,
+    pub cohort_id: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateProject {
     pub student_id: i64,
     pub name: String,
     pub description: Option<String>,
     pub github_url: String,
     pub technology_stack: Option<Vec<String>>,
+    pub assignment_id: Option<i64>,
+    pub team_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGradeAdjustment {
+    pub project_id: i64,
+    pub criterion: String,
+    pub automated_score: Option<i32>,
+    pub adjusted_score: i32,
+    pub max_score: Option<i32>,
+    pub justification: String,
+    pub reviewer: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateReviewComment {
+    pub project_id: i64,
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub commit_sha: String,
+    pub category: CommentCategory,
+    pub body: String,
+    pub points_deducted: Option<i32>,
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateReviewComment {
+    pub category: CommentCategory,
+    pub body: String,
+    pub points_deducted: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRosterSource {
+    pub name: String,
+    pub spreadsheet_id: String,
+    pub range: String,
+    pub mapping: serde_json::Value,
+    pub course_id: Option<i64>,
+    pub assignment_id: Option<i64>,
+    pub interval_minutes: Option<i64>,
+    pub enabled: Option<bool>,
+    pub conflict_policy: Option<ConflictPolicy>,
+    pub status_column: Option<String>,
+    pub score_column: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRosterSyncRun {
+    pub source_id: i64,
+    pub added: i64,
+    pub changed: i64,
+    pub removed: i64,
+    pub unchanged: i64,
+    pub written_back: i64,
+    pub conflicts: Vec<serde_json::Value>,
+    pub errors: Vec<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTeam {
+    pub name: String,
+    pub course_id: Option<i64>,
+    pub assignment_id: Option<i64>,
+    pub member_ids: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateProjectContribution {
+    pub project_id: i64,
+    pub student_id: Option<i64>,
+    pub author_name: String,
+    pub author_email: String,
+    pub commits: i64,
+    pub lines_added: i64,
+    pub lines_deleted: i64,
+    pub files_touched: i64,
+    pub share: f64,
+    pub first_commit_at: Option<DateTime<Utc>>,
+    pub last_commit_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCourse {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub term: Option<String>,
+    pub archived: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCohort {
+    pub course_id: i64,
+    pub name: String,
+    pub starts_on: Option<NaiveDate>,
+    pub ends_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAssignment {
+    pub course_id: i64,
+    pub cohort_id: Option<i64>,
+    pub title: String,
+    pub description: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub rubric: Option<serde_json::Value>,
+    pub starter_repo_url: Option<String>,
+    pub allowed_stacks: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,6 +406,9 @@ pub struct CreateAnalysisResult {
     pub total_score: Option<i32>,
     pub feedback: Option<String>,
     pub analysis_data: Option<serde_json::Value>,
+    pub commit_sha: Option<String>,
+    pub analyzer_version: Option<String>,
+    pub rubric_version: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -85,6 +417,70 @@ pub struct CreatePlaygroundSession {
     pub container_id: Option<String>,
     pub port: Option<i32>,
     pub status: String,
+    pub snapshot_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatePlaygroundSnapshot {
+    pub project_id: i64,
+    pub session_id: i64,
+    pub label: String,
+    pub image: String,
+    pub workspace_path: String,
+    pub environment: serde_json::Value,
+    pub size_bytes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSmokeScenario {
+    pub assignment_id: Option<i64>,
+    pub name: String,
+    pub steps: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSmokeTestRun {
+    pub project_id: i64,
+    pub playground_session_id: Option<i64>,
+    pub scenario_id: i64,
+    pub passed: bool,
+    pub total_steps: i32,
+    pub passed_steps: i32,
+    pub results: Option<serde_json::Value>,
+}
+
+// Search inputs; every filter is optional and filters combine with AND
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSearchQuery {
+    pub text: Option<String>,
+    pub course_id: Option<i64>,
+    pub cohort_id: Option<i64>,
+    pub assignment_id: Option<i64>,
+    pub statuses: Option<Vec<String>>,
+    pub stack: Option<String>,
+    pub min_score: Option<i32>,
+    pub max_score: Option<i32>,
+    pub has_security_issues: Option<bool>,
+    pub sort: Option<ProjectSearchSort>,
+    pub descending: Option<bool>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ProjectSearchSort {
+    #[serde(rename = "relevance")]
+    Relevance,
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "score")]
+    Score,
+    #[serde(rename = "student_name")]
+    StudentName,
+    #[serde(rename = "project_name")]
+    ProjectName,
+    #[serde(rename = "status")]
+    Status,
 }
 
 // Response DTOs with joined data
@@ -97,12 +493,73 @@ pub struct ProjectWithStudent {
     pub github_url: String,
     pub technology_stack: Option<Vec<String>>,
     pub status: String,
+    pub assignment_id: Option<i64>,
+    pub team_id: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub student_name: String,
     pub student_email: Option<String>,
     pub student_github_username: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TeamMemberWithStudent {
+    pub team_id: i64,
+    pub student_id: i64,
+    pub role: Option<String>,
+    pub grade_weight: f64,
+    pub student_name: String,
+    pub student_email: Option<String>,
+    pub student_github_username: Option<String>,
+}
+
+// Per-member view of a group project's score
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberGrade {
+    pub student_id: i64,
+    pub student_name: String,
+    pub grade_weight: f64,
+    pub contribution_share: Option<f64>,
+    pub suggested_weight: Option<f64>,
+    pub adjusted_score: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GradeSummary {
+    pub project_id: i64,
+    pub status: String,
+    pub automated_score: Option<i32>,
+    pub computed_score: Option<i32>, // automated criteria with reviewer adjustments applied
+    pub final_grade: Option<FinalGrade>,
+    pub adjustments: Vec<GradeAdjustment>,
+    pub comment_deductions: i32, // points deducted through review comments, already applied to computed_score
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectSearchHit {
+    pub project: ProjectWithStudent,
+    pub score: Option<i32>, // finalized grade when present, otherwise the latest automated total
+    pub security_issue_count: i64,
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectSearchPage {
+    pub items: Vec<ProjectSearchHit>,
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignmentSubmission {
+    pub project: ProjectWithStudent,
+    pub cohort_id: Option<i64>,
+    pub total_score: Option<i32>,
+    pub analyzed_at: Option<DateTime<Utc>>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub is_late: bool, // false until an analysis has recorded when the work was committed
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProjectWithAnalysis {
     pub project: Project,
@@ -144,17 +601,129 @@ pub enum TechnologyStack {
     Generic,
 }
 
-// Project status enum
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Project status enum, doubling as the review workflow state machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProjectStatus {
     #[serde(rename = "pending")]
     Pending,
     #[serde(rename = "analyzing")]
     Analyzing,
-    #[serde(rename = "completed")]
-    Completed,
+    #[serde(rename = "analyzed")]
+    Analyzed,
+    #[serde(rename = "in_review")]
+    InReview,
+    #[serde(rename = "graded")]
+    Graded,
+    #[serde(rename = "released")]
+    Released,
     #[serde(rename = "failed")]
     Failed,
+    #[serde(rename = "regrade")]
+    Regrade,
+}
+
+impl ProjectStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectStatus::Pending => "pending",
+            ProjectStatus::Analyzing => "analyzing",
+            ProjectStatus::Analyzed => "analyzed",
+            ProjectStatus::InReview => "in_review",
+            ProjectStatus::Graded => "graded",
+            ProjectStatus::Released => "released",
+            ProjectStatus::Failed => "failed",
+            ProjectStatus::Regrade => "regrade",
+        }
+    }
+
+    pub fn allowed_transitions(&self) -> &'static [ProjectStatus] {
+        match self {
+            ProjectStatus::Pending => &[ProjectStatus::Analyzing],
+            ProjectStatus::Analyzing => &[ProjectStatus::Analyzed, ProjectStatus::Failed],
+            ProjectStatus::Analyzed => &[ProjectStatus::Analyzing, ProjectStatus::InReview],
+            ProjectStatus::InReview => &[ProjectStatus::Analyzing, ProjectStatus::Graded],
+            ProjectStatus::Graded => &[ProjectStatus::InReview, ProjectStatus::Released],
+            ProjectStatus::Released => &[ProjectStatus::Regrade],
+            ProjectStatus::Failed => &[ProjectStatus::Pending, ProjectStatus::Analyzing],
+            ProjectStatus::Regrade => &[ProjectStatus::Analyzing, ProjectStatus::InReview],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: ProjectStatus) -> bool {
+        self.allowed_transitions().contains(&next)
+    }
+}
+
+impl std::str::FromStr for ProjectStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "pending" => Ok(ProjectStatus::Pending),
+            "analyzing" => Ok(ProjectStatus::Analyzing),
+            "analyzed" => Ok(ProjectStatus::Analyzed),
+            "in_review" => Ok(ProjectStatus::InReview),
+            "graded" => Ok(ProjectStatus::Graded),
+            "released" => Ok(ProjectStatus::Released),
+            "failed" => Ok(ProjectStatus::Failed),
+            "regrade" => Ok(ProjectStatus::Regrade),
+            other => Err(format!("Unknown project status '{}'", other)),
+        }
+    }
+}
+
+// Review comment category enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommentCategory {
+    #[serde(rename = "bug")]
+    Bug,
+    #[serde(rename = "style")]
+    Style,
+    #[serde(rename = "praise")]
+    Praise,
+    #[serde(rename = "question")]
+    Question,
+}
+
+impl CommentCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommentCategory::Bug => "bug",
+            CommentCategory::Style => "style",
+            CommentCategory::Praise => "praise",
+            CommentCategory::Question => "question",
+        }
+    }
+}
+
+// Which side wins when a synced field changed both in the sheet and locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    #[serde(rename = "sheet_wins")]
+    SheetWins,
+    #[serde(rename = "local_wins")]
+    LocalWins,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::SheetWins => "sheet_wins",
+            ConflictPolicy::LocalWins => "local_wins",
+        }
+    }
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "sheet_wins" => Ok(ConflictPolicy::SheetWins),
+            "local_wins" => Ok(ConflictPolicy::LocalWins),
+            other => Err(format!("Unknown conflict policy: {}", other)),
+        }
+    }
 }
 
 // Playground session status enum
@@ -168,4 +737,38 @@ pub enum PlaygroundStatus {
     Stopped,
     #[serde(rename = "error")]
     Error,
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grading_only_follows_review() {
+        assert!(ProjectStatus::InReview.can_transition_to(ProjectStatus::Graded));
+        assert!(ProjectStatus::Graded.can_transition_to(ProjectStatus::Released));
+        assert!(ProjectStatus::Released.can_transition_to(ProjectStatus::Regrade));
+
+        assert!(!ProjectStatus::Analyzed.can_transition_to(ProjectStatus::Graded));
+        assert!(!ProjectStatus::InReview.can_transition_to(ProjectStatus::Released));
+        assert!(!ProjectStatus::Released.can_transition_to(ProjectStatus::InReview));
+        assert!(!ProjectStatus::Pending.can_transition_to(ProjectStatus::Pending));
+    }
+
+    #[test]
+    fn statuses_round_trip_through_their_names() {
+        for status in [
+            ProjectStatus::Pending,
+            ProjectStatus::Analyzing,
+            ProjectStatus::Analyzed,
+            ProjectStatus::InReview,
+            ProjectStatus::Graded,
+            ProjectStatus::Released,
+            ProjectStatus::Failed,
+            ProjectStatus::Regrade,
+        ] {
+            assert_eq!(status.as_str().parse::<ProjectStatus>(), Ok(status));
+            assert_eq!(serde_json::to_value(status).unwrap(), serde_json::json!(status.as_str()));
+        }
+        assert!("done".parse::<ProjectStatus>().is_err());
+    }
+}
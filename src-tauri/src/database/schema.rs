@@ -1,166 +1,272 @@
-use sqlx::{SqlitePool, Row};
+use sqlx::{Connection, QueryBuilder, Sqlite, SqliteConnection, SqliteExecutor, SqlitePool, Row};
 use anyhow::Result;
 use crate::database::models::*;
 use chrono::{DateTime, Utc};
 
-pub async fn create_tables(pool: &SqlitePool) -> Result<()> {
-    // Create students table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS students (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            email TEXT UNIQUE,
-            github_username TEXT,
-            cohort TEXT,
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
+// Student CRUD operations
+pub async fn create_student(executor: impl SqliteExecutor<'_>, student: CreateStudent) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO students (name, email, github_username, cohort, cohort_id) VALUES (?, ?, ?, ?, ?)"
     )
-    .execute(pool)
+    .bind(&student.name)
+    .bind(&student.email)
+    .bind(&student.github_username)
+    .bind(&student.cohort)
+    .bind(student.cohort_id)
+    .execute(executor)
     .await?;
+    
+    Ok(result.last_insert_rowid())
+}
 
-    // Create projects table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS projects (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            student_id INTEGER NOT NULL,
-            name TEXT NOT NULL,
-            description TEXT,
-            github_url TEXT NOT NULL,
-            technology_stack TEXT, -- JSON array as string
-            status TEXT DEFAULT 'pending',
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY (student_id) REFERENCES students(id)
-        )
-        "#,
+pub async fn get_student_by_id(executor: impl SqliteExecutor<'_>, id: i64) -> Result<Option<Student>> {
+    let student = sqlx::query_as::<_, Student>(
+        "SELECT * FROM students WHERE id = ?"
     )
-    .execute(pool)
+    .bind(id)
+    .fetch_optional(executor)
     .await?;
+    
+    Ok(student)
+}
 
-    // Create analysis_results table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS analysis_results (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            project_id INTEGER NOT NULL,
-            code_quality_score INTEGER,
-            structure_score INTEGER,
-            documentation_score INTEGER,
-            functionality_score INTEGER,
-            total_score INTEGER,
-            feedback TEXT,
-            analysis_data TEXT, -- JSON as string
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY (project_id) REFERENCES projects(id)
-        )
-        "#,
+pub async fn get_all_students(executor: impl SqliteExecutor<'_>) -> Result<Vec<Student>> {
+    let students = sqlx::query_as::<_, Student>(
+        "SELECT * FROM students ORDER BY created_at DESC"
     )
-    .execute(pool)
+    .fetch_all(executor)
     .await?;
+    
+    Ok(students)
+}
+
+// Fills in identity fields the record is missing; the roster's cohort replaces the stored one
+pub async fn update_student_from_import(conn: &mut sqlx::SqliteConnection, id: i64, student: &CreateStudent) -> Result<()> {
+    // An email already on another record would trip UNIQUE(email); name the owner instead
+    if let Some(email) = student.email.as_deref() {
+        let owner: Option<(i64, String)> = sqlx::query_as(
+            "SELECT id, name FROM students WHERE email = ? AND id != ? AND (SELECT email FROM students WHERE id = ?) IS NULL"
+        )
+        .bind(email)
+        .bind(id)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        if let Some((owner_id, owner_name)) = owner {
+            return Err(anyhow::anyhow!("Email {} already belongs to {} (student {})", email, owner_name, owner_id));
+        }
+    }
 
-    // Create playground_sessions table
     sqlx::query(
         r#"
-        CREATE TABLE IF NOT EXISTS playground_sessions (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            project_id INTEGER NOT NULL,
-            container_id TEXT UNIQUE,
-            port INTEGER,
-            status TEXT DEFAULT 'starting',
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            FOREIGN KEY (project_id) REFERENCES projects(id)
-        )
-        "#,
+        UPDATE students SET
+            email = COALESCE(email, ?),
+            github_username = COALESCE(github_username, ?),
+            cohort = COALESCE(?, cohort),
+            cohort_id = COALESCE(?, cohort_id)
+        WHERE id = ?
+        "#
     )
-    .execute(pool)
+    .bind(&student.email)
+    .bind(&student.github_username)
+    .bind(&student.cohort)
+    .bind(student.cohort_id)
+    .bind(id)
+    .execute(conn)
     .await?;
-
-    // Create indices for better performance
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_projects_student_id ON projects(student_id)")
-        .execute(pool)
-        .await?;
-    
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_analysis_results_project_id ON analysis_results(project_id)")
-        .execute(pool)
-        .await?;
     
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_playground_sessions_project_id ON playground_sessions(project_id)")
-        .execute(pool)
-        .await?;
-
     Ok(())
 }
 
-// Student CRUD operations
-pub async fn create_student(pool: &SqlitePool, student: CreateStudent) -> Result<i64> {
-    let result = sqlx::query(
-        "INSERT INTO students (name, email, github_username, cohort) VALUES (?, ?, ?, ?)"
+// Overwrites the synced fields, used when the roster sheet wins a sync
+pub async fn update_student_details(executor: impl SqliteExecutor<'_>, id: i64, student: &CreateStudent) -> Result<()> {
+    sqlx::query(
+        "UPDATE students SET name = ?, email = ?, github_username = ?, cohort = ?, cohort_id = COALESCE(?, cohort_id) WHERE id = ?"
     )
     .bind(&student.name)
     .bind(&student.email)
     .bind(&student.github_username)
     .bind(&student.cohort)
-    .execute(pool)
+    .bind(student.cohort_id)
+    .bind(id)
+    .execute(executor)
     .await?;
     
-    Ok(result.last_insert_rowid())
+    Ok(())
 }
 
-pub async fn get_student_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Student>> {
-    let student = sqlx::query_as::<_, Student>(
-        "SELECT * FROM students WHERE id = ?"
+// Moves everything owned by `merged_id` onto `surviving_id`, then removes the duplicate.
+// Tables referencing students must be handled here as they are added.
+pub async fn merge_students(pool: &SqlitePool, surviving_id: i64, merged_id: i64) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let merged = sqlx::query_as::<_, Student>("SELECT * FROM students WHERE id = ?")
+        .bind(merged_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Student {} not found", merged_id))?;
+
+    sqlx::query("INSERT INTO student_merges (surviving_student_id, merged_student_id, merged_record) VALUES (?, ?, ?)")
+        .bind(surviving_id)
+        .bind(merged_id)
+        .bind(serde_json::to_string(&merged)?)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE projects SET student_id = ? WHERE student_id = ?")
+        .bind(surviving_id)
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO team_members (team_id, student_id, role, grade_weight)
+        SELECT team_id, ?, role, grade_weight FROM team_members WHERE student_id = ?
+        "#
     )
-    .bind(id)
-    .fetch_optional(pool)
+    .bind(surviving_id)
+    .bind(merged_id)
+    .execute(&mut *tx)
     .await?;
-    
-    Ok(student)
-}
 
-pub async fn get_all_students(pool: &SqlitePool) -> Result<Vec<Student>> {
-    let students = sqlx::query_as::<_, Student>(
-        "SELECT * FROM students ORDER BY created_at DESC"
+    sqlx::query("DELETE FROM team_members WHERE student_id = ?")
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE project_contributions SET student_id = ? WHERE student_id = ?")
+        .bind(surviving_id)
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE roster_source_rows SET student_id = ? WHERE student_id = ?")
+        .bind(surviving_id)
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Free the UNIQUE email before the survivor inherits it
+    sqlx::query("UPDATE students SET email = NULL WHERE id = ?")
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE students SET
+            email = COALESCE(email, ?),
+            github_username = COALESCE(github_username, ?),
+            cohort = COALESCE(cohort, ?),
+            cohort_id = COALESCE(cohort_id, ?)
+        WHERE id = ?
+        "#
     )
-    .fetch_all(pool)
+    .bind(&merged.email)
+    .bind(&merged.github_username)
+    .bind(&merged.cohort)
+    .bind(merged.cohort_id)
+    .bind(surviving_id)
+    .execute(&mut *tx)
     .await?;
+
+    sqlx::query("DELETE FROM students WHERE id = ?")
+        .bind(merged_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
     
-    Ok(students)
+    Ok(())
 }
 
 // Project CRUD operations
-pub async fn create_project(pool: &SqlitePool, project: CreateProject) -> Result<i64> {
+pub async fn create_project(executor: impl SqliteExecutor<'_>, project: CreateProject) -> Result<i64> {
     let tech_stack_json = match project.technology_stack {
         Some(stack) => Some(serde_json::to_string(&stack)?),
         None => None,
     };
     
     let result = sqlx::query(
-        "INSERT INTO projects (student_id, name, description, github_url, technology_stack) VALUES (?, ?, ?, ?, ?)"
+        "INSERT INTO projects (student_id, name, description, github_url, technology_stack, assignment_id, team_id) VALUES (?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(project.student_id)
     .bind(&project.name)
     .bind(&project.description)
     .bind(&project.github_url)
     .bind(&tech_stack_json)
-    .execute(pool)
+    .bind(project.assignment_id)
+    .bind(project.team_id)
+    .execute(executor)
     .await?;
     
     Ok(result.last_insert_rowid())
 }
 
-pub async fn get_project_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Project>> {
+pub async fn get_project_by_id(executor: impl SqliteExecutor<'_>, id: i64) -> Result<Option<Project>> {
     let project = sqlx::query_as::<_, Project>(
         "SELECT * FROM projects WHERE id = ?"
     )
     .bind(id)
-    .fetch_optional(pool)
+    .fetch_optional(executor)
+    .await?;
+    
+    Ok(project)
+}
+
+// The same repository submitted to the same assignment is the same project
+pub async fn find_project_by_url(executor: impl SqliteExecutor<'_>, github_url: &str, assignment_id: Option<i64>) -> Result<Option<Project>> {
+    let project = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects WHERE lower(rtrim(github_url, '/')) = lower(rtrim(?, '/')) AND assignment_id IS ? ORDER BY id LIMIT 1"
+    )
+    .bind(github_url.trim())
+    .bind(assignment_id)
+    .fetch_optional(executor)
     .await?;
     
     Ok(project)
 }
 
+// Owners of every project on the repository, whatever the assignment
+pub async fn get_project_owner_ids_by_url(executor: impl SqliteExecutor<'_>, github_url: &str) -> Result<Vec<i64>> {
+    let owners = sqlx::query_scalar::<_, i64>(
+        "SELECT DISTINCT student_id FROM projects WHERE lower(rtrim(github_url, '/')) = lower(rtrim(?, '/'))"
+    )
+    .bind(github_url.trim())
+    .fetch_all(executor)
+    .await?;
+    
+    Ok(owners)
+}
+
+// Through a project or team of the course, or a cohort of it
+pub async fn is_student_in_course(executor: impl SqliteExecutor<'_>, student_id: i64, course_id: i64) -> Result<bool> {
+    let in_course = sqlx::query_scalar::<_, bool>(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM projects p
+            LEFT JOIN assignments a ON a.id = p.assignment_id
+            LEFT JOIN teams t ON t.id = p.team_id
+            WHERE p.student_id = ?1 AND (a.course_id = ?2 OR t.course_id = ?2)
+            UNION ALL
+            SELECT 1 FROM team_members m JOIN teams t ON t.id = m.team_id
+            WHERE m.student_id = ?1 AND t.course_id = ?2
+            UNION ALL
+            SELECT 1 FROM students s JOIN cohorts c ON c.id = s.cohort_id
+            WHERE s.id = ?1 AND c.course_id = ?2
+        )
+        "#
+    )
+    .bind(student_id)
+    .bind(course_id)
+    .fetch_one(executor)
+    .await?;
+    
+    Ok(in_course)
+}
+
 pub async fn get_all_projects(pool: &SqlitePool) -> Result<Vec<Project>> {
     let projects = sqlx::query_as::<_, Project>(
         "SELECT * FROM projects ORDER BY created_at DESC"
@@ -176,7 +282,7 @@ pub async fn get_projects_with_students(pool: &SqlitePool) -> Result<Vec<Project
         r#"
         SELECT 
             p.id, p.student_id, p.name, p.description, p.github_url, 
-            p.technology_stack, p.status, p.created_at,
+            p.technology_stack, p.status, p.assignment_id, p.team_id, p.created_at,
             s.name as student_name, s.email as student_email, 
             s.github_username as student_github_username
         FROM projects p
@@ -187,35 +293,205 @@ pub async fn get_projects_with_students(pool: &SqlitePool) -> Result<Vec<Project
     .fetch_all(pool)
     .await?;
     
-    let mut projects = Vec::new();
-    for row in rows {
-        let tech_stack_str: Option<String> = row.get("technology_stack");
-        let technology_stack: Option<Vec<String>> = match tech_stack_str {
-            Some(json_str) => serde_json::from_str(&json_str).ok(),
-            None => None,
-        };
-        
-        projects.push(ProjectWithStudent {
-            id: row.get("id"),
-            student_id: row.get("student_id"),
-            name: row.get("name"),
-            description: row.get("description"),
-            github_url: row.get("github_url"),
-            technology_stack,
-            status: row.get("status"),
-            created_at: row.get("created_at"),
-            student_name: row.get("student_name"),
-            student_email: row.get("student_email"),
-            student_github_username: row.get("student_github_username"),
-        });
+    Ok(rows.iter().map(project_with_student_from_row).collect())
+}
+
+fn project_with_student_from_row(row: &sqlx::sqlite::SqliteRow) -> ProjectWithStudent {
+    let tech_stack_str: Option<String> = row.get("technology_stack");
+    let technology_stack: Option<Vec<String>> = match tech_stack_str {
+        Some(json_str) => serde_json::from_str(&json_str).ok(),
+        None => None,
+    };
+
+    ProjectWithStudent {
+        id: row.get("id"),
+        student_id: row.get("student_id"),
+        name: row.get("name"),
+        description: row.get("description"),
+        github_url: row.get("github_url"),
+        technology_stack,
+        status: row.get("status"),
+        assignment_id: row.get("assignment_id"),
+        team_id: row.get("team_id"),
+        created_at: row.get("created_at"),
+        student_name: row.get("student_name"),
+        student_email: row.get("student_email"),
+        student_github_username: row.get("student_github_username"),
+    }
+}
+
+// Search operations
+const DEFAULT_SEARCH_PAGE_SIZE: u32 = 25;
+const MAX_SEARCH_PAGE_SIZE: u32 = 200;
+
+// Quotes every term so user input can't inject FTS5 syntax; the last term matches as a prefix
+fn fts_match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"", term))
+        .collect();
+
+    if terms.is_empty() {
+        return None;
+    }
+
+    Some(format!("{}*", terms.join(" ")))
+}
+
+fn push_search_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &ProjectSearchQuery, match_expression: &Option<String>) {
+    builder.push(
+        r#"
+        FROM projects p
+        JOIN students s ON p.student_id = s.id
+        LEFT JOIN analysis_results a ON a.id = (
+            SELECT id FROM analysis_results
+            WHERE project_id = p.id
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+        )
+        LEFT JOIN final_grades fg ON fg.project_id = p.id
+        LEFT JOIN assignments asg ON asg.id = p.assignment_id
+        "#
+    );
+
+    if match_expression.is_some() {
+        builder.push(" JOIN project_search ON project_search.rowid = p.id ");
+    }
+
+    builder.push(" WHERE 1 = 1 ");
+
+    if let Some(expression) = match_expression {
+        builder.push(" AND project_search MATCH ").push_bind(expression.clone());
+    }
+    if let Some(course_id) = query.course_id {
+        builder.push(" AND asg.course_id = ").push_bind(course_id);
+    }
+    if let Some(cohort_id) = query.cohort_id {
+        builder.push(" AND s.cohort_id = ").push_bind(cohort_id);
+    }
+    if let Some(assignment_id) = query.assignment_id {
+        builder.push(" AND p.assignment_id = ").push_bind(assignment_id);
+    }
+    if let Some(statuses) = query.statuses.as_ref().filter(|s| !s.is_empty()) {
+        builder.push(" AND p.status IN (");
+        let mut separated = builder.separated(", ");
+        for status in statuses {
+            separated.push_bind(status.clone());
+        }
+        separated.push_unseparated(")");
+    }
+    if let Some(stack) = query.stack.as_ref().filter(|s| !s.trim().is_empty()) {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM json_each(COALESCE(p.technology_stack, '[]')) WHERE lower(value) = lower(")
+            .push_bind(stack.trim().to_string())
+            .push("))");
+    }
+    if let Some(min_score) = query.min_score {
+        builder.push(" AND COALESCE(fg.final_score, a.total_score) >= ").push_bind(min_score);
+    }
+    if let Some(max_score) = query.max_score {
+        builder.push(" AND COALESCE(fg.final_score, a.total_score) <= ").push_bind(max_score);
+    }
+    match query.has_security_issues {
+        Some(true) => {
+            builder.push(" AND COALESCE(json_array_length(a.analysis_data, '$.code_quality.security_issues'), 0) > 0");
+        }
+        Some(false) => {
+            builder.push(" AND COALESCE(json_array_length(a.analysis_data, '$.code_quality.security_issues'), 0) = 0");
+        }
+        None => {}
+    }
+}
+
+pub async fn search_projects(pool: &SqlitePool, query: &ProjectSearchQuery) -> Result<ProjectSearchPage> {
+    let match_expression = query.text.as_deref().and_then(fts_match_expression);
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(DEFAULT_SEARCH_PAGE_SIZE).clamp(1, MAX_SEARCH_PAGE_SIZE);
+
+    let mut count_builder: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT COUNT(*) ");
+    push_search_filters(&mut count_builder, query, &match_expression);
+    let (total,): (i64,) = count_builder.build_query_as().fetch_one(pool).await?;
+
+    let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+        r#"
+        SELECT 
+            p.id, p.student_id, p.name, p.description, p.github_url, 
+            p.technology_stack, p.status, p.assignment_id, p.team_id, p.created_at,
+            s.name as student_name, s.email as student_email, 
+            s.github_username as student_github_username,
+            COALESCE(fg.final_score, a.total_score) as score,
+            COALESCE(json_array_length(a.analysis_data, '$.code_quality.security_issues'), 0) as security_issue_count
+        "#
+    );
+    if match_expression.is_some() {
+        builder.push(", snippet(project_search, -1, '[', ']', '…', 12) as snippet ");
+    } else {
+        builder.push(", NULL as snippet ");
     }
+    push_search_filters(&mut builder, query, &match_expression);
+
+    // Relevance is only meaningful with a text query; fall back to newest first
+    let sort = match (query.sort, &match_expression) {
+        (Some(ProjectSearchSort::Relevance), None) | (None, None) => ProjectSearchSort::CreatedAt,
+        (None, Some(_)) => ProjectSearchSort::Relevance,
+        (Some(sort), _) => sort,
+    };
+    let descending = query.descending.unwrap_or(!matches!(
+        sort,
+        ProjectSearchSort::Relevance | ProjectSearchSort::StudentName | ProjectSearchSort::ProjectName | ProjectSearchSort::Status
+    ));
+    let direction = if descending { "DESC" } else { "ASC" };
+
+    // bm25 scores better matches lower, so ascending puts the best matches first
+    let order = match sort {
+        ProjectSearchSort::Relevance => format!("bm25(project_search) {}", direction),
+        ProjectSearchSort::CreatedAt => format!("p.created_at {}", direction),
+        ProjectSearchSort::Score => format!("score IS NULL, score {}", direction),
+        ProjectSearchSort::StudentName => format!("s.name COLLATE NOCASE {}", direction),
+        ProjectSearchSort::ProjectName => format!("p.name COLLATE NOCASE {}", direction),
+        ProjectSearchSort::Status => format!("p.status {}", direction),
+    };
+    builder.push(format!(" ORDER BY {}, p.id DESC ", order));
+    builder.push(" LIMIT ").push_bind(page_size as i64);
+    // In i64 so a far-off page can't overflow the multiplication
+    builder.push(" OFFSET ").push_bind((page as i64 - 1) * page_size as i64);
+
+    let rows = builder.build().fetch_all(pool).await?;
+
+    let items = rows
+        .iter()
+        .map(|row| ProjectSearchHit {
+            project: project_with_student_from_row(row),
+            score: row.get("score"),
+            security_issue_count: row.get("security_issue_count"),
+            snippet: row.get("snippet"),
+        })
+        .collect();
+
+    Ok(ProjectSearchPage {
+        items,
+        total,
+        page,
+        page_size,
+    })
+}
+
+pub async fn set_project_submitted_at(pool: &SqlitePool, id: i64, submitted_at: DateTime<Utc>) -> Result<()> {
+    sqlx::query("UPDATE projects SET submitted_at = ? WHERE id = ?")
+        .bind(submitted_at)
+        .bind(id)
+        .execute(pool)
+        .await?;
     
-    Ok(projects)
+    Ok(())
 }
 
-pub async fn update_project_status(pool: &SqlitePool, id: i64, status: &str) -> Result<()> {
-    sqlx::query("UPDATE projects SET status = ? WHERE id = ?")
-        .bind(status)
+// Imports leave the stack unknown; each analysis records what it detected, which the search stack filter reads
+pub async fn set_project_technology_stack(pool: &SqlitePool, id: i64, stack: &[TechnologyStack]) -> Result<()> {
+    sqlx::query("UPDATE projects SET technology_stack = ? WHERE id = ?")
+        .bind(serde_json::to_string(stack)?)
         .bind(id)
         .execute(pool)
         .await?;
@@ -223,79 +499,1828 @@ pub async fn update_project_status(pool: &SqlitePool, id: i64, status: &str) ->
     Ok(())
 }
 
-// Analysis results CRUD operations
-pub async fn create_analysis_result(pool: &SqlitePool, analysis: CreateAnalysisResult) -> Result<i64> {
-    let analysis_data_json = match analysis.analysis_data {
-        Some(data) => Some(serde_json::to_string(&data)?),
-        None => None,
-    };
+pub async fn set_project_assignment(pool: &SqlitePool, id: i64, assignment_id: Option<i64>) -> Result<()> {
+    sqlx::query("UPDATE projects SET assignment_id = ? WHERE id = ?")
+        .bind(assignment_id)
+        .bind(id)
+        .execute(pool)
+        .await?;
     
-    let result = sqlx::query(
-        r#"
-        INSERT INTO analysis_results (
-            project_id, code_quality_score, structure_score, 
-            documentation_score, functionality_score, total_score, 
-            feedback, analysis_data
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-        "#
-    )
-    .bind(analysis.project_id)
-    .bind(analysis.code_quality_score)
-    .bind(analysis.structure_score)
-    .bind(analysis.documentation_score)
-    .bind(analysis.functionality_score)
-    .bind(analysis.total_score)
-    .bind(&analysis.feedback)
-    .bind(&analysis_data_json)
-    .execute(pool)
-    .await?;
+    Ok(())
+}
+
+pub async fn update_project_details(
+    executor: impl SqliteExecutor<'_>,
+    id: i64,
+    name: &str,
+    description: Option<&str>,
+    github_url: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE projects SET name = ?, description = ?, github_url = ? WHERE id = ?")
+        .bind(name)
+        .bind(description)
+        .bind(github_url)
+        .bind(id)
+        .execute(executor)
+        .await?;
     
-    Ok(result.last_insert_rowid())
+    Ok(())
 }
 
-pub async fn get_analysis_by_project_id(pool: &SqlitePool, project_id: i64) -> Result<Option<AnalysisResult>> {
-    let analysis = sqlx::query_as::<_, AnalysisResult>(
-        "SELECT * FROM analysis_results WHERE project_id = ? ORDER BY created_at DESC LIMIT 1"
-    )
-    .bind(project_id)
-    .fetch_optional(pool)
-    .await?;
+pub async fn set_project_team(executor: impl SqliteExecutor<'_>, id: i64, team_id: Option<i64>) -> Result<()> {
+    sqlx::query("UPDATE projects SET team_id = ? WHERE id = ?")
+        .bind(team_id)
+        .bind(id)
+        .execute(executor)
+        .await?;
     
-    Ok(analysis)
+    Ok(())
 }
 
-// Playground session CRUD operations
-pub async fn create_playground_session(pool: &SqlitePool, session: CreatePlaygroundSession) -> Result<i64> {
-    let result = sqlx::query(
-        "INSERT INTO playground_sessions (project_id, container_id, port, status) VALUES (?, ?, ?, ?)"
-    )
-    .bind(session.project_id)
-    .bind(&session.container_id)
-    .bind(session.port)
-    .bind(&session.status)
-    .execute(pool)
-    .await?;
+// Moves a project between workflow states; callers validate the transition.
+// Returns false when the project is no longer in `from`, e.g. after a concurrent update.
+pub async fn transition_project_status(
+    pool: &SqlitePool,
+    id: i64,
+    from: &str,
+    to: &str,
+    note: Option<&str>,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    if !record_status_transition(&mut tx, id, from, to, note).await? {
+        return Ok(false);
+    }
+
+    tx.commit().await?;
     
-    Ok(result.last_insert_rowid())
+    Ok(true)
 }
 
-pub async fn get_playground_session_by_project_id(pool: &SqlitePool, project_id: i64) -> Result<Option<PlaygroundSession>> {
-    let session = sqlx::query_as::<_, PlaygroundSession>(
-        "SELECT * FROM playground_sessions WHERE project_id = ? ORDER BY created_at DESC LIMIT 1"
+// Compare-and-set on the current status, so a concurrent change leaves the project untouched
+async fn record_status_transition(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    id: i64,
+    from: &str,
+    to: &str,
+    note: Option<&str>,
+) -> Result<bool> {
+    let result = sqlx::query("UPDATE projects SET status = ? WHERE id = ? AND status = ?")
+        .bind(to)
+        .bind(id)
+        .bind(from)
+        .execute(&mut **tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query("INSERT INTO project_status_history (project_id, from_status, to_status, note) VALUES (?, ?, ?, ?)")
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .bind(note)
+        .execute(&mut **tx)
+        .await?;
+    
+    Ok(true)
+}
+
+pub async fn get_project_status_history(pool: &SqlitePool, project_id: i64) -> Result<Vec<ProjectStatusChange>> {
+    let history = sqlx::query_as::<_, ProjectStatusChange>(
+        "SELECT * FROM project_status_history WHERE project_id = ? ORDER BY created_at, id"
     )
     .bind(project_id)
-    .fetch_optional(pool)
+    .fetch_all(pool)
     .await?;
     
-    Ok(session)
+    Ok(history)
 }
 
-pub async fn update_playground_session_status(pool: &SqlitePool, id: i64, status: &str) -> Result<()> {
-    sqlx::query("UPDATE playground_sessions SET status = ? WHERE id = ?")
+// Grade review operations
+pub async fn upsert_grade_adjustment(pool: &SqlitePool, adjustment: CreateGradeAdjustment) -> Result<i64> {
+    let (id,): (i64,) = sqlx::query_as(
+        r#"
+        INSERT INTO grade_adjustments (
+            project_id, criterion, automated_score, adjusted_score,
+            max_score, justification, reviewer
+        ) VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(project_id, criterion) DO UPDATE SET
+            automated_score = excluded.automated_score,
+            adjusted_score = excluded.adjusted_score,
+            max_score = excluded.max_score,
+            justification = excluded.justification,
+            reviewer = excluded.reviewer,
+            updated_at = CURRENT_TIMESTAMP
+        RETURNING id
+        "#
+    )
+    .bind(adjustment.project_id)
+    .bind(&adjustment.criterion)
+    .bind(adjustment.automated_score)
+    .bind(adjustment.adjusted_score)
+    .bind(adjustment.max_score)
+    .bind(&adjustment.justification)
+    .bind(&adjustment.reviewer)
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(id)
+}
+
+pub async fn get_grade_adjustment_by_id(pool: &SqlitePool, id: i64) -> Result<Option<GradeAdjustment>> {
+    let adjustment = sqlx::query_as::<_, GradeAdjustment>(
+        "SELECT * FROM grade_adjustments WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(adjustment)
+}
+
+pub async fn get_grade_adjustments(pool: &SqlitePool, project_id: i64) -> Result<Vec<GradeAdjustment>> {
+    let adjustments = sqlx::query_as::<_, GradeAdjustment>(
+        "SELECT * FROM grade_adjustments WHERE project_id = ? ORDER BY criterion"
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(adjustments)
+}
+
+pub async fn delete_grade_adjustment(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM grade_adjustments WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+// The grade is written together with the move to 'graded', so a failed transition leaves no grade behind
+pub async fn record_final_grade(
+    pool: &SqlitePool,
+    project_id: i64,
+    from_status: &str,
+    automated_score: Option<i32>,
+    final_score: i32,
+    notes: Option<&str>,
+    graded_by: Option<&str>,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    if !record_status_transition(&mut tx, project_id, from_status, ProjectStatus::Graded.as_str(), notes).await? {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO final_grades (project_id, automated_score, final_score, notes, graded_by)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(project_id) DO UPDATE SET
+            automated_score = excluded.automated_score,
+            final_score = excluded.final_score,
+            notes = excluded.notes,
+            graded_by = excluded.graded_by,
+            graded_at = CURRENT_TIMESTAMP,
+            released_at = NULL
+        "#
+    )
+    .bind(project_id)
+    .bind(automated_score)
+    .bind(final_score)
+    .bind(notes)
+    .bind(graded_by)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    
+    Ok(true)
+}
+
+pub async fn get_final_grade(pool: &SqlitePool, project_id: i64) -> Result<Option<FinalGrade>> {
+    let grade = sqlx::query_as::<_, FinalGrade>(
+        "SELECT * FROM final_grades WHERE project_id = ?"
+    )
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(grade)
+}
+
+pub async fn release_final_grade(pool: &SqlitePool, project_id: i64, from_status: &str) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    if !record_status_transition(&mut tx, project_id, from_status, ProjectStatus::Released.as_str(), None).await? {
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE final_grades SET released_at = CURRENT_TIMESTAMP WHERE project_id = ?")
+        .bind(project_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(true)
+}
+
+// Review comment CRUD operations
+pub async fn create_review_comment(pool: &SqlitePool, comment: CreateReviewComment) -> Result<i64> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO review_comments (
+            project_id, file_path, start_line, end_line, commit_sha, category, body,
+            points_deducted, author, original_commit_sha, original_file_path,
+            original_start_line, original_end_line
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(comment.project_id)
+    .bind(&comment.file_path)
+    .bind(comment.start_line)
+    .bind(comment.end_line)
+    .bind(&comment.commit_sha)
+    .bind(comment.category.as_str())
+    .bind(&comment.body)
+    .bind(comment.points_deducted)
+    .bind(&comment.author)
+    .bind(&comment.commit_sha)
+    .bind(&comment.file_path)
+    .bind(comment.start_line)
+    .bind(comment.end_line)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_review_comment_by_id(pool: &SqlitePool, id: i64) -> Result<Option<ReviewComment>> {
+    let comment = sqlx::query_as::<_, ReviewComment>(
+        "SELECT * FROM review_comments WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(comment)
+}
+
+pub async fn get_review_comments_by_project_id(pool: &SqlitePool, project_id: i64, include_outdated: bool) -> Result<Vec<ReviewComment>> {
+    let comments = sqlx::query_as::<_, ReviewComment>(
+        "SELECT * FROM review_comments WHERE project_id = ? AND (outdated = 0 OR ?) ORDER BY file_path, start_line, id"
+    )
+    .bind(project_id)
+    .bind(include_outdated)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(comments)
+}
+
+pub async fn update_review_comment(pool: &SqlitePool, id: i64, comment: UpdateReviewComment) -> Result<()> {
+    sqlx::query(
+        "UPDATE review_comments SET category = ?, body = ?, points_deducted = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?"
+    )
+    .bind(comment.category.as_str())
+    .bind(&comment.body)
+    .bind(comment.points_deducted)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn reanchor_review_comment(
+    pool: &SqlitePool,
+    id: i64,
+    file_path: &str,
+    start_line: i64,
+    end_line: i64,
+    commit_sha: &str,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE review_comments SET file_path = ?, start_line = ?, end_line = ?, commit_sha = ?, outdated = 0 WHERE id = ?"
+    )
+    .bind(file_path)
+    .bind(start_line)
+    .bind(end_line)
+    .bind(commit_sha)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn mark_review_comment_outdated(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("UPDATE review_comments SET outdated = 1 WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn delete_review_comment(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM review_comments WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+// Team CRUD operations
+// Runs as a savepoint when called inside a caller's transaction
+pub async fn create_team(conn: &mut SqliteConnection, team: CreateTeam) -> Result<i64> {
+    let mut tx = conn.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO teams (name, course_id, assignment_id) VALUES (?, ?, ?)"
+    )
+    .bind(&team.name)
+    .bind(team.course_id)
+    .bind(team.assignment_id)
+    .execute(&mut *tx)
+    .await?;
+    let team_id = result.last_insert_rowid();
+
+    for student_id in &team.member_ids {
+        sqlx::query("INSERT OR IGNORE INTO team_members (team_id, student_id) VALUES (?, ?)")
+            .bind(team_id)
+            .bind(student_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    
+    Ok(team_id)
+}
+
+pub async fn get_team_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Team>> {
+    let team = sqlx::query_as::<_, Team>(
+        "SELECT * FROM teams WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(team)
+}
+
+// Team names are unique per assignment in practice; imports reuse an existing team
+pub async fn find_team_by_name(executor: impl SqliteExecutor<'_>, name: &str, assignment_id: Option<i64>) -> Result<Option<Team>> {
+    let team = sqlx::query_as::<_, Team>(
+        "SELECT * FROM teams WHERE name = ? COLLATE NOCASE AND assignment_id IS ? ORDER BY id LIMIT 1"
+    )
+    .bind(name.trim())
+    .bind(assignment_id)
+    .fetch_optional(executor)
+    .await?;
+    
+    Ok(team)
+}
+
+pub async fn get_teams(pool: &SqlitePool, assignment_id: Option<i64>) -> Result<Vec<Team>> {
+    let teams = match assignment_id {
+        Some(assignment_id) => sqlx::query_as::<_, Team>(
+            "SELECT * FROM teams WHERE assignment_id = ? ORDER BY name"
+        )
+        .bind(assignment_id)
+        .fetch_all(pool)
+        .await?,
+        None => sqlx::query_as::<_, Team>(
+            "SELECT * FROM teams ORDER BY name"
+        )
+        .fetch_all(pool)
+        .await?,
+    };
+    
+    Ok(teams)
+}
+
+pub async fn rename_team(pool: &SqlitePool, id: i64, name: &str) -> Result<()> {
+    sqlx::query("UPDATE teams SET name = ? WHERE id = ?")
+        .bind(name)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+// The team's projects fall back to being owned by their submitting student
+pub async fn delete_team(pool: &SqlitePool, id: i64) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE projects SET team_id = NULL WHERE team_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM team_members WHERE team_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM teams WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+pub async fn add_team_member(executor: impl SqliteExecutor<'_>, team_id: i64, student_id: i64, role: Option<&str>) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO team_members (team_id, student_id, role) VALUES (?, ?, ?)
+        ON CONFLICT(team_id, student_id) DO UPDATE SET role = COALESCE(excluded.role, team_members.role)
+        "#
+    )
+    .bind(team_id)
+    .bind(student_id)
+    .bind(role)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn get_team_member_ids(executor: impl SqliteExecutor<'_>, team_id: i64) -> Result<Vec<i64>> {
+    let ids: Vec<(i64,)> = sqlx::query_as("SELECT student_id FROM team_members WHERE team_id = ?")
+        .bind(team_id)
+        .fetch_all(executor)
+        .await?;
+    
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+pub async fn remove_team_member(pool: &SqlitePool, team_id: i64, student_id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM team_members WHERE team_id = ? AND student_id = ?")
+        .bind(team_id)
+        .bind(student_id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn set_team_member_grade_weight(pool: &SqlitePool, team_id: i64, student_id: i64, grade_weight: f64) -> Result<()> {
+    sqlx::query("UPDATE team_members SET grade_weight = ? WHERE team_id = ? AND student_id = ?")
+        .bind(grade_weight)
+        .bind(team_id)
+        .bind(student_id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn get_team_members(pool: &SqlitePool, team_id: i64) -> Result<Vec<TeamMemberWithStudent>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT 
+            m.team_id, m.student_id, m.role, m.grade_weight,
+            s.name as student_name, s.email as student_email,
+            s.github_username as student_github_username
+        FROM team_members m
+        JOIN students s ON m.student_id = s.id
+        WHERE m.team_id = ?
+        ORDER BY s.name
+        "#
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| TeamMemberWithStudent {
+            team_id: row.get("team_id"),
+            student_id: row.get("student_id"),
+            role: row.get("role"),
+            grade_weight: row.get("grade_weight"),
+            student_name: row.get("student_name"),
+            student_email: row.get("student_email"),
+            student_github_username: row.get("student_github_username"),
+        })
+        .collect())
+}
+
+pub async fn get_team_students(pool: &SqlitePool, team_id: i64) -> Result<Vec<Student>> {
+    let students = sqlx::query_as::<_, Student>(
+        r#"
+        SELECT s.* FROM students s
+        JOIN team_members m ON m.student_id = s.id
+        WHERE m.team_id = ?
+        ORDER BY s.name
+        "#
+    )
+    .bind(team_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(students)
+}
+
+// Contribution stats are recomputed as a whole; the previous set is replaced
+pub async fn replace_project_contributions(
+    pool: &SqlitePool,
+    project_id: i64,
+    contributions: Vec<CreateProjectContribution>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM project_contributions WHERE project_id = ?")
+        .bind(project_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for contribution in contributions {
+        sqlx::query(
+            r#"
+            INSERT INTO project_contributions (
+                project_id, student_id, author_name, author_email, commits,
+                lines_added, lines_deleted, files_touched, share,
+                first_commit_at, last_commit_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(project_id)
+        .bind(contribution.student_id)
+        .bind(&contribution.author_name)
+        .bind(&contribution.author_email)
+        .bind(contribution.commits)
+        .bind(contribution.lines_added)
+        .bind(contribution.lines_deleted)
+        .bind(contribution.files_touched)
+        .bind(contribution.share)
+        .bind(contribution.first_commit_at)
+        .bind(contribution.last_commit_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+pub async fn get_project_contributions(pool: &SqlitePool, project_id: i64) -> Result<Vec<ProjectContribution>> {
+    let contributions = sqlx::query_as::<_, ProjectContribution>(
+        "SELECT * FROM project_contributions WHERE project_id = ? ORDER BY share DESC"
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(contributions)
+}
+
+// Course CRUD operations
+pub async fn create_course(pool: &SqlitePool, course: CreateCourse) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO courses (code, name, description, term, archived) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(&course.code)
+    .bind(&course.name)
+    .bind(&course.description)
+    .bind(&course.term)
+    .bind(course.archived.unwrap_or(false))
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_course_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Course>> {
+    let course = sqlx::query_as::<_, Course>(
+        "SELECT * FROM courses WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(course)
+}
+
+pub async fn get_courses(pool: &SqlitePool, include_archived: bool) -> Result<Vec<Course>> {
+    let courses = sqlx::query_as::<_, Course>(
+        "SELECT * FROM courses WHERE archived = 0 OR ? ORDER BY archived, name"
+    )
+    .bind(include_archived)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(courses)
+}
+
+pub async fn update_course(pool: &SqlitePool, id: i64, course: CreateCourse) -> Result<()> {
+    sqlx::query(
+        "UPDATE courses SET code = ?, name = ?, description = ?, term = ?, archived = COALESCE(?, archived) WHERE id = ?"
+    )
+    .bind(&course.code)
+    .bind(&course.name)
+    .bind(&course.description)
+    .bind(&course.term)
+    .bind(course.archived)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+// Courses with assignments must be archived instead; deleting would orphan submissions
+pub async fn delete_course(pool: &SqlitePool, id: i64) -> Result<()> {
+    let (assignment_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM assignments WHERE course_id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    if assignment_count > 0 {
+        return Err(anyhow::anyhow!(
+            "Course has {} assignment(s); archive it or delete the assignments first",
+            assignment_count
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE students SET cohort_id = NULL WHERE cohort_id IN (SELECT id FROM cohorts WHERE course_id = ?)")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE teams SET course_id = NULL WHERE course_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE roster_sources SET course_id = NULL WHERE course_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM cohorts WHERE course_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM courses WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+// Cohort CRUD operations
+pub async fn create_cohort(executor: impl SqliteExecutor<'_>, cohort: CreateCohort) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO cohorts (course_id, name, starts_on, ends_on) VALUES (?, ?, ?, ?)"
+    )
+    .bind(cohort.course_id)
+    .bind(&cohort.name)
+    .bind(cohort.starts_on)
+    .bind(cohort.ends_on)
+    .execute(executor)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_cohorts_by_course_id(pool: &SqlitePool, course_id: i64) -> Result<Vec<Cohort>> {
+    let cohorts = sqlx::query_as::<_, Cohort>(
+        "SELECT * FROM cohorts WHERE course_id = ? ORDER BY starts_on, name"
+    )
+    .bind(course_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(cohorts)
+}
+
+// Used by imports, where the sheet only carries the cohort's name
+pub async fn find_or_create_cohort(conn: &mut SqliteConnection, course_id: i64, name: &str) -> Result<i64> {
+    let existing: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM cohorts WHERE course_id = ? AND name = ? COLLATE NOCASE"
+    )
+    .bind(course_id)
+    .bind(name.trim())
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match existing {
+        Some((id,)) => Ok(id),
+        None => create_cohort(&mut *conn, CreateCohort {
+            course_id,
+            name: name.trim().to_string(),
+            starts_on: None,
+            ends_on: None,
+        }).await,
+    }
+}
+
+pub async fn update_cohort(pool: &SqlitePool, id: i64, cohort: CreateCohort) -> Result<()> {
+    sqlx::query("UPDATE cohorts SET course_id = ?, name = ?, starts_on = ?, ends_on = ? WHERE id = ?")
+        .bind(cohort.course_id)
+        .bind(&cohort.name)
+        .bind(cohort.starts_on)
+        .bind(cohort.ends_on)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn delete_cohort(pool: &SqlitePool, id: i64) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE students SET cohort_id = NULL WHERE cohort_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE assignments SET cohort_id = NULL WHERE cohort_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM cohorts WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+// Assignment CRUD operations
+pub async fn create_assignment(pool: &SqlitePool, assignment: CreateAssignment) -> Result<i64> {
+    let rubric_json = match assignment.rubric {
+        Some(rubric) => Some(serde_json::to_string(&rubric)?),
+        None => None,
+    };
+    let allowed_stacks_json = match assignment.allowed_stacks {
+        Some(stacks) => Some(serde_json::to_string(&stacks)?),
+        None => None,
+    };
+    
+    let result = sqlx::query(
+        r#"
+        INSERT INTO assignments (
+            course_id, cohort_id, title, description, due_at,
+            rubric, starter_repo_url, allowed_stacks
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(assignment.course_id)
+    .bind(assignment.cohort_id)
+    .bind(&assignment.title)
+    .bind(&assignment.description)
+    .bind(assignment.due_at)
+    .bind(&rubric_json)
+    .bind(&assignment.starter_repo_url)
+    .bind(&allowed_stacks_json)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_assignment_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Assignment>> {
+    let assignment = sqlx::query_as::<_, Assignment>(
+        "SELECT * FROM assignments WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(assignment)
+}
+
+pub async fn get_assignments(executor: impl SqliteExecutor<'_>, course_id: Option<i64>) -> Result<Vec<Assignment>> {
+    let assignments = match course_id {
+        Some(course_id) => sqlx::query_as::<_, Assignment>(
+            "SELECT * FROM assignments WHERE course_id = ? ORDER BY due_at IS NULL, due_at, title"
+        )
+        .bind(course_id)
+        .fetch_all(executor)
+        .await?,
+        None => sqlx::query_as::<_, Assignment>(
+            "SELECT * FROM assignments ORDER BY due_at IS NULL, due_at, title"
+        )
+        .fetch_all(executor)
+        .await?,
+    };
+    
+    Ok(assignments)
+}
+
+pub async fn update_assignment(pool: &SqlitePool, id: i64, assignment: CreateAssignment) -> Result<()> {
+    let rubric_json = match assignment.rubric {
+        Some(rubric) => Some(serde_json::to_string(&rubric)?),
+        None => None,
+    };
+    let allowed_stacks_json = match assignment.allowed_stacks {
+        Some(stacks) => Some(serde_json::to_string(&stacks)?),
+        None => None,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE assignments SET
+            course_id = ?, cohort_id = ?, title = ?, description = ?, due_at = ?,
+            rubric = ?, starter_repo_url = ?, allowed_stacks = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(assignment.course_id)
+    .bind(assignment.cohort_id)
+    .bind(&assignment.title)
+    .bind(&assignment.description)
+    .bind(assignment.due_at)
+    .bind(&rubric_json)
+    .bind(&assignment.starter_repo_url)
+    .bind(&allowed_stacks_json)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+// Submissions stay in the database as unassigned projects
+pub async fn delete_assignment(pool: &SqlitePool, id: i64) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE projects SET assignment_id = NULL WHERE assignment_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE teams SET assignment_id = NULL WHERE assignment_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE roster_sources SET assignment_id = NULL WHERE assignment_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM assignments WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+pub async fn get_assignment_submissions(pool: &SqlitePool, assignment_id: i64) -> Result<Vec<AssignmentSubmission>> {
+    let assignment = get_assignment_by_id(pool, assignment_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Assignment not found"))?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT 
+            p.id, p.student_id, p.name, p.description, p.github_url, 
+            p.technology_stack, p.status, p.assignment_id, p.team_id, p.created_at, p.submitted_at,
+            s.name as student_name, s.email as student_email, 
+            s.github_username as student_github_username, s.cohort_id,
+            a.total_score, a.created_at as analyzed_at
+        FROM projects p
+        JOIN students s ON p.student_id = s.id
+        LEFT JOIN analysis_results a ON a.id = (
+            SELECT id FROM analysis_results
+            WHERE project_id = p.id
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+        )
+        WHERE p.assignment_id = ?
+        ORDER BY s.name
+        "#
+    )
+    .bind(assignment_id)
+    .fetch_all(pool)
+    .await?;
+
+    let submissions = rows
+        .iter()
+        .map(|row| {
+            let project = project_with_student_from_row(row);
+            // created_at is when the project was imported, which says nothing about when it was submitted
+            let submitted_at: Option<DateTime<Utc>> = row.get("submitted_at");
+            let is_late = match (assignment.due_at, submitted_at) {
+                (Some(due_at), Some(submitted_at)) => submitted_at > due_at,
+                _ => false,
+            };
+
+            AssignmentSubmission {
+                project,
+                cohort_id: row.get("cohort_id"),
+                total_score: row.get("total_score"),
+                analyzed_at: row.get("analyzed_at"),
+                submitted_at,
+                is_late,
+            }
+        })
+        .collect();
+
+    Ok(submissions)
+}
+
+// Analysis results CRUD operations
+pub async fn create_analysis_result(pool: &SqlitePool, analysis: CreateAnalysisResult) -> Result<i64> {
+    let analysis_data_json = match analysis.analysis_data {
+        Some(data) => Some(serde_json::to_string(&data)?),
+        None => None,
+    };
+    
+    let result = sqlx::query(
+        r#"
+        INSERT INTO analysis_results (
+            project_id, code_quality_score, structure_score, 
+            documentation_score, functionality_score, total_score, 
+            feedback, analysis_data, commit_sha, analyzer_version, rubric_version
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(analysis.project_id)
+    .bind(analysis.code_quality_score)
+    .bind(analysis.structure_score)
+    .bind(analysis.documentation_score)
+    .bind(analysis.functionality_score)
+    .bind(analysis.total_score)
+    .bind(&analysis.feedback)
+    .bind(&analysis_data_json)
+    .bind(&analysis.commit_sha)
+    .bind(&analysis.analyzer_version)
+    .bind(&analysis.rubric_version)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_analysis_by_project_id(pool: &SqlitePool, project_id: i64) -> Result<Option<AnalysisResult>> {
+    let analysis = sqlx::query_as::<_, AnalysisResult>(
+        "SELECT * FROM analysis_results WHERE project_id = ? ORDER BY created_at DESC, id DESC LIMIT 1"
+    )
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(analysis)
+}
+
+// Newest first
+pub async fn get_analysis_history(pool: &SqlitePool, project_id: i64) -> Result<Vec<AnalysisResult>> {
+    let history = sqlx::query_as::<_, AnalysisResult>(
+        "SELECT * FROM analysis_results WHERE project_id = ? ORDER BY created_at DESC, id DESC"
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(history)
+}
+
+// Playground session CRUD operations
+pub async fn create_playground_session(pool: &SqlitePool, session: CreatePlaygroundSession) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO playground_sessions (project_id, container_id, port, status, snapshot_id) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(session.project_id)
+    .bind(&session.container_id)
+    .bind(session.port)
+    .bind(&session.status)
+    .bind(session.snapshot_id)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_playground_session_by_project_id(pool: &SqlitePool, project_id: i64) -> Result<Option<PlaygroundSession>> {
+    let session = sqlx::query_as::<_, PlaygroundSession>(
+        "SELECT * FROM playground_sessions WHERE project_id = ? ORDER BY created_at DESC LIMIT 1"
+    )
+    .bind(project_id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(session)
+}
+
+pub async fn update_playground_session_status(pool: &SqlitePool, id: i64, status: &str) -> Result<()> {
+    sqlx::query("UPDATE playground_sessions SET status = ? WHERE id = ?")
         .bind(status)
         .bind(id)
         .execute(pool)
         .await?;
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Playground snapshot CRUD operations
+pub async fn create_playground_snapshot(pool: &SqlitePool, snapshot: CreatePlaygroundSnapshot) -> Result<i64> {
+    let environment_json = serde_json::to_string(&snapshot.environment)?;
+    
+    let result = sqlx::query(
+        r#"
+        INSERT INTO playground_snapshots (
+            project_id, session_id, label, image, workspace_path, environment, size_bytes
+        ) VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(snapshot.project_id)
+    .bind(snapshot.session_id)
+    .bind(&snapshot.label)
+    .bind(&snapshot.image)
+    .bind(&snapshot.workspace_path)
+    .bind(&environment_json)
+    .bind(snapshot.size_bytes)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_playground_snapshot_by_id(pool: &SqlitePool, id: i64) -> Result<Option<PlaygroundSnapshot>> {
+    let snapshot = sqlx::query_as::<_, PlaygroundSnapshot>(
+        "SELECT * FROM playground_snapshots WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(snapshot)
+}
+
+pub async fn get_playground_snapshots_by_project_id(pool: &SqlitePool, project_id: i64) -> Result<Vec<PlaygroundSnapshot>> {
+    let snapshots = sqlx::query_as::<_, PlaygroundSnapshot>(
+        "SELECT * FROM playground_snapshots WHERE project_id = ? ORDER BY created_at DESC, id DESC"
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(snapshots)
+}
+
+// Sessions restored from the snapshot keep running; they just no longer point at it
+pub async fn delete_playground_snapshot(pool: &SqlitePool, id: i64) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE playground_sessions SET snapshot_id = NULL WHERE snapshot_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM playground_snapshots WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+// Smoke test CRUD operations
+pub async fn create_smoke_scenario(pool: &SqlitePool, scenario: CreateSmokeScenario) -> Result<i64> {
+    let steps_json = serde_json::to_string(&scenario.steps)?;
+    
+    let result = sqlx::query(
+        "INSERT INTO smoke_scenarios (assignment_id, name, steps) VALUES (?, ?, ?)"
+    )
+    .bind(scenario.assignment_id)
+    .bind(&scenario.name)
+    .bind(&steps_json)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_smoke_scenario_by_id(pool: &SqlitePool, id: i64) -> Result<Option<SmokeScenario>> {
+    let scenario = sqlx::query_as::<_, SmokeScenario>(
+        "SELECT * FROM smoke_scenarios WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(scenario)
+}
+
+pub async fn get_smoke_scenarios(pool: &SqlitePool, assignment_id: Option<i64>) -> Result<Vec<SmokeScenario>> {
+    let scenarios = sqlx::query_as::<_, SmokeScenario>(
+        "SELECT * FROM smoke_scenarios WHERE ? IS NULL OR assignment_id = ? ORDER BY created_at DESC"
+    )
+    .bind(assignment_id)
+    .bind(assignment_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(scenarios)
+}
+
+pub async fn update_smoke_scenario(pool: &SqlitePool, id: i64, scenario: CreateSmokeScenario) -> Result<()> {
+    let steps_json = serde_json::to_string(&scenario.steps)?;
+    
+    sqlx::query("UPDATE smoke_scenarios SET assignment_id = ?, name = ?, steps = ? WHERE id = ?")
+        .bind(scenario.assignment_id)
+        .bind(&scenario.name)
+        .bind(&steps_json)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn delete_smoke_scenario(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM smoke_test_runs WHERE scenario_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("DELETE FROM smoke_scenarios WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn create_smoke_test_run(pool: &SqlitePool, run: CreateSmokeTestRun) -> Result<i64> {
+    let results_json = match run.results {
+        Some(data) => Some(serde_json::to_string(&data)?),
+        None => None,
+    };
+    
+    let result = sqlx::query(
+        r#"
+        INSERT INTO smoke_test_runs (
+            project_id, playground_session_id, scenario_id,
+            passed, total_steps, passed_steps, results
+        ) VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(run.project_id)
+    .bind(run.playground_session_id)
+    .bind(run.scenario_id)
+    .bind(run.passed)
+    .bind(run.total_steps)
+    .bind(run.passed_steps)
+    .bind(&results_json)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_smoke_test_runs_by_project_id(pool: &SqlitePool, project_id: i64) -> Result<Vec<SmokeTestRun>> {
+    let runs = sqlx::query_as::<_, SmokeTestRun>(
+        "SELECT * FROM smoke_test_runs WHERE project_id = ? ORDER BY created_at DESC, id DESC"
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(runs)
+}
+
+// The most recent run of each scenario the project was tested with
+pub async fn get_latest_smoke_test_runs(pool: &SqlitePool, project_id: i64) -> Result<Vec<SmokeTestRun>> {
+    let runs = sqlx::query_as::<_, SmokeTestRun>(
+        r#"
+        SELECT r.* FROM smoke_test_runs r
+        WHERE r.project_id = ?
+          AND r.id = (
+              SELECT id FROM smoke_test_runs
+              WHERE project_id = r.project_id AND scenario_id = r.scenario_id
+              ORDER BY created_at DESC, id DESC
+              LIMIT 1
+          )
+        ORDER BY r.scenario_id
+        "#
+    )
+    .bind(project_id)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(runs)
+}
+
+// App settings operations
+pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>> {
+    let value = sqlx::query_scalar::<_, String>("SELECT value FROM app_settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    
+    Ok(value)
+}
+
+pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO app_settings (key, value, updated_at) VALUES (?, ?, CURRENT_TIMESTAMP)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP
+        "#
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+// Roster sync operations
+pub async fn create_roster_source(pool: &SqlitePool, source: CreateRosterSource) -> Result<i64> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO roster_sources (name, spreadsheet_id, range, mapping, course_id, assignment_id,
+                                    interval_minutes, enabled, conflict_policy, status_column, score_column)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&source.name)
+    .bind(&source.spreadsheet_id)
+    .bind(&source.range)
+    .bind(serde_json::to_string(&source.mapping)?)
+    .bind(source.course_id)
+    .bind(source.assignment_id)
+    .bind(source.interval_minutes.unwrap_or(60))
+    .bind(source.enabled.unwrap_or(true))
+    .bind(source.conflict_policy.unwrap_or(ConflictPolicy::LocalWins).as_str())
+    .bind(&source.status_column)
+    .bind(&source.score_column)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_roster_source_by_id(pool: &SqlitePool, id: i64) -> Result<Option<RosterSource>> {
+    let source = sqlx::query_as::<_, RosterSource>(
+        "SELECT * FROM roster_sources WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(source)
+}
+
+pub async fn get_roster_sources(pool: &SqlitePool) -> Result<Vec<RosterSource>> {
+    let sources = sqlx::query_as::<_, RosterSource>(
+        "SELECT * FROM roster_sources ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(sources)
+}
+
+// Enabled sources whose interval has elapsed since the last sync
+pub async fn get_due_roster_sources(pool: &SqlitePool) -> Result<Vec<RosterSource>> {
+    let sources = sqlx::query_as::<_, RosterSource>(
+        r#"
+        SELECT * FROM roster_sources
+        WHERE enabled = 1
+          AND (last_synced_at IS NULL
+               OR (julianday('now') - julianday(last_synced_at)) * 1440 >= interval_minutes)
+        ORDER BY last_synced_at
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(sources)
+}
+
+pub async fn update_roster_source(pool: &SqlitePool, id: i64, source: CreateRosterSource) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE roster_sources SET
+            name = ?, spreadsheet_id = ?, range = ?, mapping = ?, course_id = ?, assignment_id = ?,
+            interval_minutes = COALESCE(?, interval_minutes),
+            enabled = COALESCE(?, enabled),
+            conflict_policy = COALESCE(?, conflict_policy),
+            status_column = ?, score_column = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&source.name)
+    .bind(&source.spreadsheet_id)
+    .bind(&source.range)
+    .bind(serde_json::to_string(&source.mapping)?)
+    .bind(source.course_id)
+    .bind(source.assignment_id)
+    .bind(source.interval_minutes)
+    .bind(source.enabled)
+    .bind(source.conflict_policy.map(|p| p.as_str()))
+    .bind(&source.status_column)
+    .bind(&source.score_column)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+// Imported students and projects stay; only the sync bookkeeping goes
+pub async fn delete_roster_source(pool: &SqlitePool, id: i64) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM roster_source_rows WHERE source_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM roster_sync_runs WHERE source_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM roster_sources WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    
+    Ok(())
+}
+
+pub async fn set_roster_source_sync_result(pool: &SqlitePool, id: i64, error: Option<&str>) -> Result<()> {
+    sqlx::query("UPDATE roster_sources SET last_synced_at = CURRENT_TIMESTAMP, last_error = ? WHERE id = ?")
+        .bind(error)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    
+    Ok(())
+}
+
+pub async fn get_roster_source_rows(executor: impl SqliteExecutor<'_>, source_id: i64) -> Result<Vec<RosterSourceRow>> {
+    let rows = sqlx::query_as::<_, RosterSourceRow>(
+        "SELECT * FROM roster_source_rows WHERE source_id = ? ORDER BY row_number"
+    )
+    .bind(source_id)
+    .fetch_all(executor)
+    .await?;
+    
+    Ok(rows)
+}
+
+// Reappearing rows are revived
+pub async fn upsert_roster_source_row(
+    executor: impl SqliteExecutor<'_>,
+    source_id: i64,
+    row_key: &str,
+    row_number: i64,
+    row_data: &serde_json::Value,
+    student_id: Option<i64>,
+    project_id: Option<i64>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO roster_source_rows (source_id, row_key, row_number, row_data, student_id, project_id)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(source_id, row_key) DO UPDATE SET
+            row_number = excluded.row_number,
+            row_data = excluded.row_data,
+            student_id = COALESCE(excluded.student_id, roster_source_rows.student_id),
+            project_id = COALESCE(excluded.project_id, roster_source_rows.project_id),
+            removed_at = NULL,
+            updated_at = CURRENT_TIMESTAMP
+        "#
+    )
+    .bind(source_id)
+    .bind(row_key)
+    .bind(row_number)
+    .bind(serde_json::to_string(row_data)?)
+    .bind(student_id)
+    .bind(project_id)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn mark_roster_source_row_removed(executor: impl SqliteExecutor<'_>, source_id: i64, row_key: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE roster_source_rows SET removed_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP WHERE source_id = ? AND row_key = ?"
+    )
+    .bind(source_id)
+    .bind(row_key)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
+// Moves a row's baseline to the key its edited identifiers now produce
+pub async fn rename_roster_source_row_key(executor: impl SqliteExecutor<'_>, source_id: i64, row_key: &str, new_key: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE roster_source_rows SET row_key = ?, updated_at = CURRENT_TIMESTAMP WHERE source_id = ? AND row_key = ?"
+    )
+    .bind(new_key)
+    .bind(source_id)
+    .bind(row_key)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn create_roster_sync_run(pool: &SqlitePool, run: CreateRosterSyncRun) -> Result<i64> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO roster_sync_runs (source_id, added, changed, removed, unchanged, written_back, conflicts, errors, started_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(run.source_id)
+    .bind(run.added)
+    .bind(run.changed)
+    .bind(run.removed)
+    .bind(run.unchanged)
+    .bind(run.written_back)
+    .bind(serde_json::to_string(&run.conflicts)?)
+    .bind(serde_json::to_string(&run.errors)?)
+    .bind(run.started_at)
+    .execute(pool)
+    .await?;
+    
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_roster_sync_run_by_id(pool: &SqlitePool, id: i64) -> Result<Option<RosterSyncRun>> {
+    let run = sqlx::query_as::<_, RosterSyncRun>(
+        "SELECT * FROM roster_sync_runs WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(run)
+}
+
+pub async fn get_roster_sync_runs(pool: &SqlitePool, source_id: i64, limit: i64) -> Result<Vec<RosterSyncRun>> {
+    let runs = sqlx::query_as::<_, RosterSyncRun>(
+        "SELECT * FROM roster_sync_runs WHERE source_id = ? ORDER BY started_at DESC LIMIT ?"
+    )
+    .bind(source_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn latest_smoke_test_runs_are_per_scenario_and_break_timestamp_ties_by_id() {
+        let pool = test_support::pool().await;
+        let student_id = test_support::student(&pool, "Ada", None).await;
+        let project_id = test_support::project(&pool, student_id, None).await;
+        let scenario = |name: &str| CreateSmokeScenario {
+            assignment_id: None,
+            name: name.to_string(),
+            steps: serde_json::json!([]),
+        };
+        let scenario_id = create_smoke_scenario(&pool, scenario("health")).await.unwrap();
+        let other_scenario_id = create_smoke_scenario(&pool, scenario("login")).await.unwrap();
+        let run = |scenario_id: i64, passed_steps: i32| CreateSmokeTestRun {
+            project_id,
+            playground_session_id: None,
+            scenario_id,
+            passed: false,
+            total_steps: 2,
+            passed_steps,
+            results: None,
+        };
+
+        let mut run_ids = Vec::new();
+        for passed_steps in [0, 1, 2] {
+            run_ids.push(create_smoke_test_run(&pool, run(scenario_id, passed_steps)).await.unwrap());
+        }
+        let other_run_id = create_smoke_test_run(&pool, run(other_scenario_id, 2)).await.unwrap();
+        // Same second, as happens when runs are recorded back to back
+        sqlx::query("UPDATE smoke_test_runs SET created_at = '2026-01-01 00:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // The other scenario ran last, but the health scenario's latest run still counts
+        let latest = get_latest_smoke_test_runs(&pool, project_id).await.unwrap();
+        assert_eq!(latest.iter().map(|run| run.id).collect::<Vec<_>>(), vec![run_ids[2], other_run_id]);
+
+        let runs = get_smoke_test_runs_by_project_id(&pool, project_id).await.unwrap();
+        assert_eq!(runs.iter().map(|run| run.id).collect::<Vec<_>>(), vec![other_run_id, run_ids[2], run_ids[1], run_ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn restored_snapshots_can_be_deleted() {
+        let pool = test_support::pool().await;
+        let student_id = test_support::student(&pool, "Ada", None).await;
+        let project_id = test_support::project(&pool, student_id, None).await;
+        let session = |snapshot_id: Option<i64>| CreatePlaygroundSession {
+            project_id,
+            container_id: Some("container".to_string()),
+            port: Some(3000),
+            status: "running".to_string(),
+            snapshot_id,
+        };
+        let session_id = create_playground_session(&pool, session(None)).await.unwrap();
+        let snapshot_id = create_playground_snapshot(&pool, CreatePlaygroundSnapshot {
+            project_id,
+            session_id,
+            label: "before refactor".to_string(),
+            image: "r3viewer-snapshot:1".to_string(),
+            workspace_path: "/tmp/snapshot".to_string(),
+            environment: serde_json::json!({}),
+            size_bytes: None,
+        })
+        .await
+        .unwrap();
+        let restored_id = create_playground_session(&pool, session(Some(snapshot_id))).await.unwrap();
+
+        delete_playground_snapshot(&pool, snapshot_id).await.unwrap();
+
+        assert!(get_playground_snapshot_by_id(&pool, snapshot_id).await.unwrap().is_none());
+        let restored: Option<i64> = sqlx::query_scalar("SELECT snapshot_id FROM playground_sessions WHERE id = ?")
+            .bind(restored_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(restored, None);
+    }
+
+    #[tokio::test]
+    async fn submissions_are_late_only_when_committed_after_the_due_date() {
+        let pool = test_support::pool().await;
+        let due_at = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let course_id = create_course(&pool, CreateCourse {
+            code: "CS101".to_string(),
+            name: "Intro".to_string(),
+            description: None,
+            term: None,
+            archived: None,
+        })
+        .await
+        .unwrap();
+        let assignment_id = create_assignment(&pool, CreateAssignment {
+            course_id,
+            cohort_id: None,
+            title: "Project 1".to_string(),
+            description: None,
+            due_at: Some(due_at),
+            rubric: None,
+            starter_repo_url: None,
+            allowed_stacks: None,
+        })
+        .await
+        .unwrap();
+
+        let mut project_ids = Vec::new();
+        for name in ["On time", "Late", "Unanalyzed"] {
+            let student_id = test_support::student(&pool, name, None).await;
+            project_ids.push(test_support::project(&pool, student_id, Some(assignment_id)).await);
+        }
+        set_project_submitted_at(&pool, project_ids[0], due_at - chrono::Duration::hours(1)).await.unwrap();
+        set_project_submitted_at(&pool, project_ids[1], due_at + chrono::Duration::minutes(1)).await.unwrap();
+        // Imported well after the deadline, which must not count against the student
+        sqlx::query("UPDATE projects SET created_at = '2026-04-01 00:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let submissions = get_assignment_submissions(&pool, assignment_id).await.unwrap();
+        let late = |project_id: i64| submissions.iter().find(|s| s.project.id == project_id).unwrap().is_late;
+        assert!(!late(project_ids[0]));
+        assert!(late(project_ids[1]));
+        assert!(!late(project_ids[2]));
+    }
+
+    #[tokio::test]
+    async fn imports_refuse_an_email_held_by_another_student() {
+        let pool = test_support::pool().await;
+        let ada = test_support::student(&pool, "Ada", Some("ada@example.com")).await;
+        let grace = test_support::student(&pool, "Grace", None).await;
+        let incoming = |email: &str| CreateStudent {
+            name: "Grace".to_string(),
+            email: Some(email.to_string()),
+            github_username: Some("ghopper".to_string()),
+            cohort: None,
+            cohort_id: None,
+        };
+        let mut conn = pool.acquire().await.unwrap();
+
+        let error = update_student_from_import(&mut conn, grace, &incoming("ada@example.com")).await.unwrap_err();
+        assert!(error.to_string().contains("already belongs to Ada"));
+        assert_eq!(get_student_by_id(&mut *conn, grace).await.unwrap().unwrap().github_username, None);
+
+        // Re-importing a student's own email, or one they already have, is fine
+        update_student_from_import(&mut conn, ada, &incoming("ada@example.com")).await.unwrap();
+        update_student_from_import(&mut conn, grace, &incoming("grace@example.com")).await.unwrap();
+        let updated = get_student_by_id(&mut *conn, grace).await.unwrap().unwrap();
+        assert_eq!(updated.email.as_deref(), Some("grace@example.com"));
+    }
+
+    #[test]
+    fn fts_terms_are_quoted_and_the_last_one_is_a_prefix() {
+        assert_eq!(fts_match_expression("ada love"), Some(r#""ada" "love"*"#.to_string()));
+        // FTS5 operators and quotes are searched for literally rather than interpreted
+        assert_eq!(fts_match_expression(r#"NOT "ada" OR"#), Some(r#""NOT" "ada" "OR"*"#.to_string()));
+        assert_eq!(fts_match_expression(r#"  " ""  "#), None);
+        assert_eq!(fts_match_expression(""), None);
+    }
+
+    #[tokio::test]
+    async fn search_pages_through_matches() {
+        let pool = test_support::pool().await;
+        for name in ["Ada Lovelace", "Ada Byron", "Grace Hopper"] {
+            let student_id = test_support::student(&pool, name, None).await;
+            test_support::project(&pool, student_id, None).await;
+        }
+
+        let search = |text: &str, page: Option<u32>, page_size: Option<u32>| ProjectSearchQuery {
+            text: Some(text.to_string()),
+            sort: Some(ProjectSearchSort::StudentName),
+            page,
+            page_size,
+            ..Default::default()
+        };
+
+        let first = search_projects(&pool, &search("ad", Some(0), Some(1))).await.unwrap();
+        assert_eq!((first.total, first.page, first.page_size), (2, 1, 1));
+        assert_eq!(first.items[0].project.student_name, "Ada Byron");
+
+        let second = search_projects(&pool, &search("ad", Some(2), Some(1))).await.unwrap();
+        assert_eq!(second.items[0].project.student_name, "Ada Lovelace");
+
+        let beyond = search_projects(&pool, &search("ad", Some(u32::MAX), Some(200))).await.unwrap();
+        assert_eq!(beyond.total, 2);
+        assert!(beyond.items.is_empty());
+
+        let injected = search_projects(&pool, &search("hopper OR", None, None)).await.unwrap();
+        assert_eq!(injected.total, 0);
+    }
+
+    #[tokio::test]
+    async fn search_filters_by_the_stack_found_in_analysis() {
+        let pool = test_support::pool().await;
+        let ada = test_support::student(&pool, "Ada", None).await;
+        let analysed = test_support::project(&pool, ada, None).await;
+        let grace = test_support::student(&pool, "Grace", None).await;
+        test_support::project(&pool, grace, None).await;
+
+        set_project_technology_stack(&pool, analysed, &[TechnologyStack::NodeJS, TechnologyStack::React]).await.unwrap();
+        create_analysis_result(&pool, CreateAnalysisResult {
+            project_id: analysed,
+            code_quality_score: Some(80),
+            structure_score: None,
+            documentation_score: None,
+            functionality_score: None,
+            total_score: Some(80),
+            feedback: None,
+            analysis_data: None,
+            commit_sha: None,
+            analyzer_version: None,
+            rubric_version: None,
+        })
+        .await
+        .unwrap();
+
+        let by_stack = |stack: &str| ProjectSearchQuery { stack: Some(stack.to_string()), ..Default::default() };
+
+        let react = search_projects(&pool, &by_stack("React")).await.unwrap();
+        assert_eq!(react.total, 1);
+        assert_eq!(react.items[0].project.id, analysed);
+        assert_eq!(react.items[0].project.technology_stack, Some(vec!["nodejs".to_string(), "react".to_string()]));
+
+        assert_eq!(search_projects(&pool, &by_stack("python")).await.unwrap().total, 0);
+    }
+
+    #[tokio::test]
+    async fn outdated_review_comments_can_be_left_out() {
+        let pool = test_support::pool().await;
+        let student_id = test_support::student(&pool, "Ada", None).await;
+        let project_id = test_support::project(&pool, student_id, None).await;
+        let mut comment_ids = Vec::new();
+        for points_deducted in [2, 5] {
+            comment_ids.push(create_review_comment(&pool, CreateReviewComment {
+                project_id,
+                file_path: "src/main.rs".to_string(),
+                start_line: 1,
+                end_line: 2,
+                commit_sha: "abc".to_string(),
+                category: CommentCategory::Bug,
+                body: "Off by one".to_string(),
+                points_deducted: Some(points_deducted),
+                author: None,
+            })
+            .await
+            .unwrap());
+        }
+        mark_review_comment_outdated(&pool, comment_ids[1]).await.unwrap();
+
+        let current = get_review_comments_by_project_id(&pool, project_id, false).await.unwrap();
+        assert_eq!(current.iter().map(|c| c.id).collect::<Vec<_>>(), vec![comment_ids[0]]);
+        assert_eq!(get_review_comments_by_project_id(&pool, project_id, true).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn final_grade_is_only_recorded_with_the_status_change() {
+        let pool = test_support::pool().await;
+        let student_id = test_support::student(&pool, "Ada", None).await;
+        let project_id = test_support::project(&pool, student_id, None).await;
+        sqlx::query("UPDATE projects SET status = 'in_review' WHERE id = ?")
+            .bind(project_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Stale status, as when another window already graded the project
+        assert!(!record_final_grade(&pool, project_id, "analyzed", Some(70), 75, None, None).await.unwrap());
+        assert!(get_final_grade(&pool, project_id).await.unwrap().is_none());
+
+        assert!(record_final_grade(&pool, project_id, "in_review", Some(70), 75, Some("late penalty waived"), None).await.unwrap());
+        let grade = get_final_grade(&pool, project_id).await.unwrap().unwrap();
+        assert_eq!(grade.final_score, 75);
+        assert!(grade.released_at.is_none());
+        assert_eq!(get_project_by_id(&pool, project_id).await.unwrap().unwrap().status, "graded");
+
+        assert!(!release_final_grade(&pool, project_id, "in_review").await.unwrap());
+        assert!(release_final_grade(&pool, project_id, "graded").await.unwrap());
+        assert!(get_final_grade(&pool, project_id).await.unwrap().unwrap().released_at.is_some());
+
+        let history = get_project_status_history(&pool, project_id).await.unwrap();
+        let moves: Vec<(&str, &str)> = history.iter().map(|h| (h.from_status.as_str(), h.to_status.as_str())).collect();
+        assert_eq!(moves, vec![("in_review", "graded"), ("graded", "released")]);
+    }
+
+    #[tokio::test]
+    async fn import_helpers_roll_back_with_the_callers_transaction() {
+        let pool = test_support::pool().await;
+        let course_id = create_course(&pool, CreateCourse {
+            code: "CS101".to_string(),
+            name: "Intro".to_string(),
+            description: None,
+            term: None,
+            archived: None,
+        })
+        .await
+        .unwrap();
+        let student_id = test_support::student(&pool, "Ada", None).await;
+
+        let mut tx = pool.begin().await.unwrap();
+        let cohort_id = find_or_create_cohort(&mut tx, course_id, "Fall").await.unwrap();
+        assert_eq!(find_or_create_cohort(&mut tx, course_id, "fall").await.unwrap(), cohort_id);
+        let team_id = create_team(&mut tx, CreateTeam {
+            name: "Team A".to_string(),
+            course_id: Some(course_id),
+            assignment_id: None,
+            member_ids: vec![student_id],
+        })
+        .await
+        .unwrap();
+        assert_eq!(get_team_member_ids(&mut *tx, team_id).await.unwrap(), vec![student_id]);
+        tx.rollback().await.unwrap();
+
+        assert!(get_cohorts_by_course_id(&pool, course_id).await.unwrap().is_empty());
+        assert!(get_team_member_ids(&pool, team_id).await.unwrap().is_empty());
+    }
+}
@@ -7,12 +7,14 @@ mod commands;
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 use database::Database;
 use services::*;
 use commands::AppState;
 
+const ROSTER_SYNC_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
@@ -29,6 +31,7 @@ pub fn run() {
                     Ok(app_state) => {
                         app_handle.manage(app_state);
                         println!("✅ r3viewer initialized successfully");
+                        spawn_roster_sync_scheduler(app_handle.clone());
                     }
                     Err(e) => {
                         eprintln!("❌ Failed to initialize r3viewer: {}", e);
@@ -42,23 +45,82 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Authentication Commands
             commands::get_auth_status,
-            commands::generate_google_auth_url,
+            commands::start_google_login,
             commands::exchange_google_code,
+            commands::get_google_oauth_client,
+            commands::set_google_oauth_client,
             commands::validate_github_token,
+            commands::start_github_device_login,
+            commands::get_github_oauth_client,
+            commands::set_github_oauth_client,
+            commands::get_github_app_status,
+            commands::set_github_app,
+            commands::remove_github_app,
             commands::logout,
             
             // Google Sheets Commands
             commands::get_sheet_data,
+            commands::read_roster_file,
+            commands::suggest_sheet_mapping,
             commands::parse_and_validate_sheet_data,
             commands::import_students_from_sheet,
+            commands::find_duplicate_students,
+            commands::merge_students,
             commands::extract_spreadsheet_id,
+            commands::create_roster_source,
+            commands::list_roster_sources,
+            commands::update_roster_source,
+            commands::delete_roster_source,
+            commands::sync_roster_source,
+            commands::list_roster_sync_runs,
             commands::export_results_to_sheet,
             commands::export_project_results,
+            commands::export_gradebook,
             
             // Project Management Commands
             commands::get_all_projects,
+            commands::search_projects,
             commands::get_project_by_id,
             commands::update_project_status,
+            commands::get_allowed_status_transitions,
+            commands::get_project_status_history,
+            commands::upsert_grade_adjustment,
+            commands::delete_grade_adjustment,
+            commands::get_grade_summary,
+            commands::finalize_grade,
+            commands::release_grades,
+            commands::request_regrade,
+            commands::create_review_comment,
+            commands::list_review_comments,
+            commands::update_review_comment,
+            commands::delete_review_comment,
+            commands::assign_project_to_assignment,
+            commands::create_team,
+            commands::list_teams,
+            commands::rename_team,
+            commands::delete_team,
+            commands::get_team_members,
+            commands::add_team_member,
+            commands::remove_team_member,
+            commands::assign_project_to_team,
+            commands::set_member_grade_weight,
+            commands::compute_project_contributions,
+            commands::get_project_contributions,
+            commands::get_member_grades,
+            commands::create_course,
+            commands::list_courses,
+            commands::update_course,
+            commands::delete_course,
+            commands::create_cohort,
+            commands::list_cohorts,
+            commands::update_cohort,
+            commands::delete_cohort,
+            commands::create_assignment,
+            commands::get_assignment_by_id,
+            commands::list_assignments,
+            commands::update_assignment,
+            commands::delete_assignment,
+            commands::list_assignment_submissions,
             
             // GitHub Integration Commands
             commands::get_repository_info,
@@ -69,6 +131,16 @@ pub fn run() {
             // Analysis Commands
             commands::analyze_project,
             commands::get_analysis_by_project_id,
+            commands::get_analysis_history,
+            commands::diff_analysis_runs,
+            
+            // Smoke Test Commands
+            commands::create_smoke_scenario,
+            commands::list_smoke_scenarios,
+            commands::update_smoke_scenario,
+            commands::delete_smoke_scenario,
+            commands::run_smoke_tests,
+            commands::get_smoke_test_runs,
             
             // Playground Commands
             commands::start_playground,
@@ -77,10 +149,27 @@ pub fn run() {
             commands::get_playground_resource_usage,
             commands::list_active_playgrounds,
             commands::cleanup_old_containers,
+            commands::create_playground_snapshot,
+            commands::list_playground_snapshots,
+            commands::restore_playground_snapshot,
+            commands::delete_playground_snapshot,
+            commands::update_snapshot_retention,
+            commands::get_dependency_cache_usage,
+            commands::prune_dependency_caches,
+            commands::get_image_catalog,
+            commands::update_image_catalog,
+            commands::prepull_images,
             
             // Utility Commands
             commands::get_app_data_dir,
             commands::check_docker_status,
+            commands::get_database_encryption_status,
+            commands::enable_database_encryption,
+            commands::create_database_backup,
+            commands::list_database_backups,
+            commands::restore_database_backup,
+            commands::export_course_archive,
+            commands::import_course_archive,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -96,33 +185,29 @@ async fn initialize_app_state(app_handle: &tauri::AppHandle) -> anyhow::Result<A
     // Initialize auth service
     println!("🔐 Setting up authentication...");
     let auth_service = Arc::new(AuthService::new());
+
+    // Initialize the shared authenticated HTTP client
+    let api_client = Arc::new(ApiClient::new((*auth_service).clone()));
     
     // Initialize GitHub service
     println!("🐙 Setting up GitHub integration...");
-    let mut github_service = GitHubService::new((*auth_service).clone());
-    if let Err(e) = github_service.initialize().await {
-        eprintln!("⚠️  GitHub service initialization failed: {}. GitHub features may be limited.", e);
-    }
-    let github_service = Arc::new(Mutex::new(github_service));
+    let github_service = Arc::new(Mutex::new(GitHubService::new((*api_client).clone())));
     
     // Initialize Google Sheets service
     println!("📊 Setting up Google Sheets integration...");
-    let sheets_service = Arc::new(SheetsService::new((*auth_service).clone()));
+    let sheets_service = Arc::new(SheetsService::new((*api_client).clone()));
     
-    // Initialize Docker service
-    println!("🐳 Setting up Docker playground...");
-    let docker_service = match DockerService::new().await {
-        Ok(service) => {
-            println!("✅ Docker service initialized successfully");
-            Arc::new(Mutex::new(service))
-        }
-        Err(e) => {
-            eprintln!("⚠️  Docker service initialization failed: {}. Playground features will be disabled.", e);
-            // For now, we'll create a placeholder that panics - this should be improved
-            // to return a proper dummy service
-            Arc::new(Mutex::new(create_dummy_docker_service().unwrap()))
-        }
-    };
+    // Initialize container runtime
+    println!("🐳 Setting up container runtime...");
+    let selection = select_container_runtime(RuntimePreference::from_env()).await;
+    for failure in &selection.status.failures {
+        eprintln!("⚠️  {} runtime unavailable: {}", failure.runtime.as_str(), failure.reason);
+    }
+    if selection.status.available {
+        println!("✅ Using {} container runtime", selection.status.active_runtime.as_str());
+    } else {
+        eprintln!("⚠️  No container runtime available. Playground features will be disabled.");
+    }
     
     // Initialize analysis service
     println!("🔍 Setting up analysis engine...");
@@ -131,22 +216,66 @@ async fn initialize_app_state(app_handle: &tauri::AppHandle) -> anyhow::Result<A
         (*github_guard).clone()
     };
     let analysis_service = Arc::new(AnalysisService::new(github_service_clone));
+
+    // Initialize smoke test runner
+    let smoke_test_service = Arc::new(SmokeTestService::new());
+
+    // Initialize student identity matching
+    let identity_service = Arc::new(IdentityService::new());
+
+    // Initialize local roster file import
+    let roster_file_service = Arc::new(RosterFileService::new());
+
+    // Initialize gradebook exporters
+    let gradebook_export_service = Arc::new(GradebookExportService::new());
     
     println!("✅ All services initialized successfully");
 
     Ok(AppState {
         db,
         auth_service,
+        api_client,
         github_service,
         sheets_service,
-        docker_service,
+        container_runtime: selection.runtime,
+        container_runtime_status: selection.status,
         analysis_service,
+        smoke_test_service,
+        identity_service,
+        roster_file_service,
+        gradebook_export_service,
+        roster_sync_lock: Arc::new(Mutex::new(())),
     })
 }
 
-// Create a dummy docker service for when Docker is not available
-fn create_dummy_docker_service() -> anyhow::Result<DockerService> {
-    // In a real implementation, this would return a mock/dummy service
-    // For now, we'll return an error to indicate Docker is unavailable
-    Err(anyhow::anyhow!("Docker service is not available"))
+// Syncs every roster source whose interval has elapsed; results reach the frontend as events
+fn spawn_roster_sync_scheduler(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(ROSTER_SYNC_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let state = app_handle.state::<AppState>();
+            let sources = match database::schema::get_due_roster_sources(&state.db.pool).await {
+                Ok(sources) => sources,
+                Err(e) => {
+                    eprintln!("⚠️  Failed to load roster sources: {}", e);
+                    continue;
+                }
+            };
+
+            for source in sources {
+                match commands::run_roster_sync(&state, &source).await {
+                    Ok(run) => {
+                        let _ = app_handle.emit("roster-sync-completed", &run);
+                    }
+                    Err(e) => {
+                        eprintln!("⚠️  Roster sync for {} failed: {}", source.name, e);
+                        let _ = app_handle.emit("roster-sync-failed", serde_json::json!({ "source_id": source.id, "error": e }));
+                    }
+                }
+            }
+        }
+    });
 }
+
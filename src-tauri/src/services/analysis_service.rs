@@ -3,8 +3,31 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::HashMap;
-use crate::database::models::{CreateAnalysisResult, TechnologyStack};
-use crate::services::{GitHubService, ProjectStructure, FileInfo};
+use crate::database::models::{AnalysisResult as StoredAnalysisResult, CreateAnalysisResult, GradeAdjustment, TechnologyStack};
+use crate::services::{GitHubService, ProjectStructure, FileInfo, SmokeTestReport};
+
+// Criteria scored by the automated analysis and their weight in the total score.
+// Reviewer adjustments use these names to override a criterion.
+pub const SCORE_CRITERIA: &[(&str, f64)] = &[
+    ("code_quality", 0.25),
+    ("structure", 0.20),
+    ("documentation", 0.15),
+    ("functionality", 0.40),
+];
+
+// Bump the suffix whenever scoring changes so runs from different analyzers can be told apart
+pub const ANALYZER_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+scoring.2");
+
+// Automated and adjusted totals share this so an unadjusted project keeps its automated score
+fn weighted_total(scores: impl IntoIterator<Item = f64>) -> i32 {
+    let total: f64 = SCORE_CRITERIA
+        .iter()
+        .zip(scores)
+        .map(|((_, weight), score)| score * weight)
+        .sum();
+
+    (total.round() as i32).clamp(0, 100)
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisResult {
@@ -53,6 +76,7 @@ pub struct FunctionalityMetrics {
     pub feature_completeness_score: i32,
     pub error_handling_score: i32,
     pub performance_score: i32,
+    pub smoke_test_pass_rate: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +96,45 @@ pub enum SecuritySeverity {
     Critical,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalysisFinding {
+    pub kind: String, // 'security' or 'recommendation'
+    pub severity: Option<String>,
+    pub description: String,
+    pub file_path: Option<String>,
+    pub line_number: Option<usize>,
+}
+
+impl AnalysisFinding {
+    // Line numbers are left out so a finding that merely moved is not reported as resolved and re-added
+    fn key(&self) -> (String, String, Option<String>) {
+        (self.kind.clone(), self.description.clone(), self.file_path.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreDelta {
+    pub criterion: String,
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+    pub delta: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisDiff {
+    pub project_id: i64,
+    pub from_run_id: i64,
+    pub to_run_id: i64,
+    pub from_commit_sha: Option<String>,
+    pub to_commit_sha: Option<String>,
+    pub analyzer_changed: bool,
+    pub rubric_changed: bool,
+    pub score_deltas: Vec<ScoreDelta>,
+    pub findings_added: Vec<AnalysisFinding>,
+    pub findings_resolved: Vec<AnalysisFinding>,
+    pub findings_unchanged: usize,
+}
+
 pub struct AnalysisService {
     github_service: GitHubService,
 }
@@ -81,7 +144,7 @@ impl AnalysisService {
         Self { github_service }
     }
 
-    pub async fn analyze_project(&self, project_path: &Path, tech_stack: &[TechnologyStack]) -> Result<AnalysisResult> {
+    pub async fn analyze_project(&self, project_path: &Path, tech_stack: &[TechnologyStack], smoke_results: Option<&SmokeTestReport>) -> Result<AnalysisResult> {
         // Analyze project structure
         let structure = self.github_service.analyze_project_structure(project_path).await?;
         
@@ -89,7 +152,7 @@ impl AnalysisService {
         let code_quality = self.analyze_code_quality(project_path, tech_stack, &structure).await?;
         let structure_metrics = self.analyze_structure(project_path, &structure).await?;
         let documentation = self.analyze_documentation(project_path, &structure).await?;
-        let functionality = self.analyze_functionality(project_path, tech_stack, &structure).await?;
+        let functionality = self.analyze_functionality(project_path, tech_stack, &structure, smoke_results).await?;
 
         // Calculate total score
         let total_score = self.calculate_total_score(&code_quality, &structure_metrics, &documentation, &functionality);
@@ -184,10 +247,10 @@ impl AnalysisService {
         })
     }
 
-    async fn analyze_functionality(&self, project_path: &Path, tech_stack: &[TechnologyStack], structure: &ProjectStructure) -> Result<FunctionalityMetrics> {
+    async fn analyze_functionality(&self, project_path: &Path, tech_stack: &[TechnologyStack], structure: &ProjectStructure, smoke_results: Option<&SmokeTestReport>) -> Result<FunctionalityMetrics> {
         let build_success = self.test_build_success(project_path, tech_stack).await?;
         let tests_passing = self.run_tests(project_path, tech_stack).await?;
-        let feature_completeness_score = self.evaluate_feature_completeness(project_path, structure).await?;
+        let feature_completeness_score = self.evaluate_feature_completeness(project_path, structure, smoke_results).await?;
         let error_handling_score = self.evaluate_error_handling(&structure.files).await?;
         let performance_score = self.evaluate_performance_indicators(&structure.files).await?;
 
@@ -204,6 +267,7 @@ impl AnalysisService {
             feature_completeness_score,
             error_handling_score,
             performance_score,
+            smoke_test_pass_rate: smoke_results.map(|report| report.pass_rate() * 100.0),
         })
     }
 
@@ -670,7 +734,12 @@ impl AnalysisService {
         Ok(false)
     }
 
-    async fn evaluate_feature_completeness(&self, _project_path: &Path, structure: &ProjectStructure) -> Result<i32> {
+    async fn evaluate_feature_completeness(&self, _project_path: &Path, structure: &ProjectStructure, smoke_results: Option<&SmokeTestReport>) -> Result<i32> {
+        // Smoke scenarios exercise the running app, so their pass rate replaces the file-count heuristic
+        if let Some(report) = smoke_results.filter(|r| r.total_steps > 0) {
+            return Ok((report.pass_rate() * 100.0).round() as i32);
+        }
+
         let mut score = 50; // Base score
 
         // Basic feature completeness based on file count and structure
@@ -725,14 +794,41 @@ impl AnalysisService {
     // Scoring and Feedback Methods
     fn calculate_total_score(&self, code_quality: &CodeQualityMetrics, structure: &StructureMetrics, documentation: &DocumentationMetrics, functionality: &FunctionalityMetrics) -> i32 {
         // Weighted scoring as per architecture specs
-        let weighted_score = (
-            (code_quality.score as f64 * 0.25) +
-            (structure.score as f64 * 0.20) +
-            (documentation.score as f64 * 0.15) +
-            (functionality.score as f64 * 0.40)
-        ) as i32;
+        let scores = [code_quality.score, structure.score, documentation.score, functionality.score];
+        weighted_total(scores.map(|score| score as f64))
+    }
+
+    pub fn automated_criterion_score(&self, analysis: &StoredAnalysisResult, criterion: &str) -> Option<i32> {
+        match criterion {
+            "code_quality" => analysis.code_quality_score,
+            "structure" => analysis.structure_score,
+            "documentation" => analysis.documentation_score,
+            "functionality" => analysis.functionality_score,
+            _ => None,
+        }
+    }
+
+    // Recomputes the weighted total with reviewer adjustments replacing automated criterion scores.
+    // Adjustments to criteria outside SCORE_CRITERIA are kept for the record but not weighted.
+    pub fn apply_grade_adjustments(&self, analysis: &StoredAnalysisResult, adjustments: &[GradeAdjustment]) -> Option<i32> {
+        let mut scores = Vec::with_capacity(SCORE_CRITERIA.len());
+
+        for (criterion, _) in SCORE_CRITERIA {
+            let adjusted = adjustments
+                .iter()
+                .find(|a| a.criterion == *criterion)
+                .map(|a| match a.max_score {
+                    Some(max) if max > 0 => a.adjusted_score as f64 * 100.0 / max as f64,
+                    _ => a.adjusted_score as f64,
+                });
+
+            scores.push(match adjusted {
+                Some(score) => score,
+                None => self.automated_criterion_score(analysis, criterion)? as f64,
+            });
+        }
 
-        weighted_score.max(0).min(100)
+        Some(weighted_total(scores))
     }
 
     fn generate_feedback(&self, code_quality: &CodeQualityMetrics, structure: &StructureMetrics, documentation: &DocumentationMetrics, functionality: &FunctionalityMetrics) -> String {
@@ -794,6 +890,14 @@ impl AnalysisService {
             feedback.push_str("⚠️ No tests detected or tests are failing.\n");
         }
 
+        if let Some(pass_rate) = functionality.smoke_test_pass_rate {
+            if pass_rate >= 100.0 {
+                feedback.push_str("✅ All smoke test scenarios passed against the running app.\n");
+            } else {
+                feedback.push_str(&format!("⚠️ {:.0}% of smoke test steps passed against the running app.\n", pass_rate));
+            }
+        }
+
         feedback
     }
 
@@ -853,6 +957,240 @@ impl AnalysisService {
             total_score: Some(analysis.total_score),
             feedback: Some(analysis.feedback.clone()),
             analysis_data,
+            commit_sha: None,
+            analyzer_version: Some(ANALYZER_VERSION.to_string()),
+            rubric_version: None,
+        }
+    }
+
+    // Short content hash, so any edit to an assignment rubric yields a new version
+    pub fn rubric_version(&self, rubric_json: &str) -> String {
+        use sha2::{Digest, Sha256};
+
+        let normalized = serde_json::from_str::<serde_json::Value>(rubric_json)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|_| rubric_json.trim().to_string());
+        let digest = Sha256::digest(normalized.as_bytes());
+        digest.iter().take(6).map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn findings(&self, analysis: &StoredAnalysisResult) -> Vec<AnalysisFinding> {
+        let Some(result) = analysis.analysis_data
+            .as_deref()
+            .and_then(|json| serde_json::from_str::<AnalysisResult>(json).ok())
+        else {
+            return Vec::new();
+        };
+
+        let security = result.code_quality.security_issues.iter().map(|issue| AnalysisFinding {
+            kind: "security".to_string(),
+            severity: Some(format!("{:?}", issue.severity).to_lowercase()),
+            description: issue.description.clone(),
+            file_path: Some(issue.file_path.clone()),
+            line_number: issue.line_number,
+        });
+
+        let recommendations = result.recommendations.iter().map(|recommendation| AnalysisFinding {
+            kind: "recommendation".to_string(),
+            severity: None,
+            description: recommendation.clone(),
+            file_path: None,
+            line_number: None,
+        });
+
+        security.chain(recommendations).collect()
+    }
+
+    pub fn diff_runs(&self, from: &StoredAnalysisResult, to: &StoredAnalysisResult) -> AnalysisDiff {
+        let score = |criterion: &str, analysis: &StoredAnalysisResult| match criterion {
+            "total" => analysis.total_score,
+            other => self.automated_criterion_score(analysis, other),
+        };
+
+        let score_deltas = SCORE_CRITERIA
+            .iter()
+            .map(|(criterion, _)| *criterion)
+            .chain(std::iter::once("total"))
+            .map(|criterion| {
+                let before = score(criterion, from);
+                let after = score(criterion, to);
+                ScoreDelta {
+                    criterion: criterion.to_string(),
+                    from: before,
+                    to: after,
+                    delta: before.zip(after).map(|(b, a)| a - b),
+                }
+            })
+            .collect();
+
+        let before = self.findings(from);
+        let after = self.findings(to);
+        let before_keys: std::collections::HashSet<_> = before.iter().map(|f| f.key()).collect();
+        let after_keys: std::collections::HashSet<_> = after.iter().map(|f| f.key()).collect();
+
+        let findings_added: Vec<AnalysisFinding> = after.iter().filter(|f| !before_keys.contains(&f.key())).cloned().collect();
+        let findings_resolved: Vec<AnalysisFinding> = before.iter().filter(|f| !after_keys.contains(&f.key())).cloned().collect();
+        let findings_unchanged = after.len() - findings_added.len();
+
+        AnalysisDiff {
+            project_id: to.project_id,
+            from_run_id: from.id,
+            to_run_id: to.id,
+            from_commit_sha: from.commit_sha.clone(),
+            to_commit_sha: to.commit_sha.clone(),
+            analyzer_changed: from.analyzer_version != to.analyzer_version,
+            rubric_changed: from.rubric_version != to.rubric_version,
+            score_deltas,
+            findings_added,
+            findings_resolved,
+            findings_unchanged,
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{ApiClient, AuthService};
+    use chrono::Utc;
+
+    fn service() -> AnalysisService {
+        AnalysisService::new(GitHubService::new(ApiClient::new(AuthService::new())))
+    }
+
+    fn analysis(scores: [i32; 4]) -> StoredAnalysisResult {
+        StoredAnalysisResult {
+            id: 1,
+            project_id: 1,
+            code_quality_score: Some(scores[0]),
+            structure_score: Some(scores[1]),
+            documentation_score: Some(scores[2]),
+            functionality_score: Some(scores[3]),
+            total_score: None,
+            feedback: None,
+            analysis_data: None,
+            commit_sha: None,
+            analyzer_version: None,
+            rubric_version: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn adjustment(criterion: &str, adjusted_score: i32, max_score: Option<i32>) -> GradeAdjustment {
+        GradeAdjustment {
+            id: 1,
+            project_id: 1,
+            criterion: criterion.to_string(),
+            automated_score: None,
+            adjusted_score,
+            max_score,
+            justification: "reviewed by hand".to_string(),
+            reviewer: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn unadjusted_total_matches_the_automated_total() {
+        let service = service();
+        // Weighs in at 74.7, which truncating and rounding disagree on
+        let automated = service.calculate_total_score(
+            &CodeQualityMetrics {
+                score: 77,
+                lint_issues: 0,
+                complexity_score: 0,
+                duplicate_code_percentage: 0.0,
+                test_coverage_percentage: 0.0,
+                security_issues: Vec::new(),
+            },
+            &StructureMetrics {
+                score: 63,
+                organization_score: 0,
+                naming_convention_score: 0,
+                file_structure_score: 0,
+                configuration_score: 0,
+            },
+            &DocumentationMetrics {
+                score: 51,
+                readme_quality: 0,
+                code_comments_percentage: 0.0,
+                api_documentation_score: 0,
+                inline_documentation_score: 0,
+            },
+            &FunctionalityMetrics {
+                score: 88,
+                build_success: true,
+                tests_passing: true,
+                feature_completeness_score: 0,
+                error_handling_score: 0,
+                performance_score: 0,
+                smoke_test_pass_rate: None,
+            },
+        );
+
+        assert_eq!(automated, 75);
+        assert_eq!(service.apply_grade_adjustments(&analysis([77, 63, 51, 88]), &[]), Some(automated));
+    }
+
+    #[test]
+    fn adjustments_replace_criteria_and_scale_to_their_maximum() {
+        let service = service();
+        let analysis = analysis([80, 80, 80, 80]);
+
+        // 8/10 on functionality is 80, so nothing changes
+        assert_eq!(service.apply_grade_adjustments(&analysis, &[adjustment("functionality", 8, Some(10))]), Some(80));
+        assert_eq!(service.apply_grade_adjustments(&analysis, &[adjustment("functionality", 30, None)]), Some(60));
+        // Criteria outside the weighted set don't move the total
+        assert_eq!(service.apply_grade_adjustments(&analysis, &[adjustment("presentation", 0, None)]), Some(80));
+        assert_eq!(service.apply_grade_adjustments(&analysis, &[adjustment("structure", 500, None)]), Some(100));
+    }
+
+    #[test]
+    fn adjusted_total_needs_every_unadjusted_criterion() {
+        let service = service();
+        let mut analysis = analysis([80, 80, 80, 80]);
+        analysis.documentation_score = None;
+
+        assert_eq!(service.apply_grade_adjustments(&analysis, &[]), None);
+        assert_eq!(service.apply_grade_adjustments(&analysis, &[adjustment("documentation", 80, None)]), Some(80));
+    }
+
+    #[test]
+    fn run_diffs_report_score_deltas_and_version_changes() {
+        let service = service();
+        let mut from = analysis([60, 70, 80, 90]);
+        from.total_score = Some(77);
+        from.commit_sha = None;
+        let mut to = analysis([65, 70, 75, 90]);
+        to.id = 2;
+        to.total_score = Some(78);
+        to.commit_sha = Some("abc123".to_string());
+        to.rubric_version = Some("0a1b2c".to_string());
+
+        let diff = service.diff_runs(&from, &to);
+        let deltas: Vec<(&str, Option<i32>)> = diff.score_deltas.iter().map(|d| (d.criterion.as_str(), d.delta)).collect();
+        assert_eq!(deltas, vec![
+            ("code_quality", Some(5)),
+            ("structure", Some(0)),
+            ("documentation", Some(-5)),
+            ("functionality", Some(0)),
+            ("total", Some(1)),
+        ]);
+        assert_eq!((diff.from_run_id, diff.to_run_id), (1, 2));
+        assert_eq!(diff.from_commit_sha, None);
+        assert!(!diff.analyzer_changed);
+        assert!(diff.rubric_changed);
+        assert!(diff.findings_added.is_empty() && diff.findings_resolved.is_empty());
+    }
+
+    #[test]
+    fn rubric_version_ignores_formatting() {
+        let service = service();
+        let compact = service.rubric_version(r#"{"criteria":[{"name":"tests","points":10}]}"#);
+
+        assert_eq!(compact.len(), 12);
+        assert_eq!(compact, service.rubric_version("{\n  \"criteria\": [ { \"name\": \"tests\", \"points\": 10 } ]\n}"));
+        assert_ne!(compact, service.rubric_version(r#"{"criteria":[{"name":"tests","points":20}]}"#));
+    }
+}
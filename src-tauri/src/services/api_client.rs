@@ -0,0 +1,101 @@
+use anyhow::{Result, anyhow};
+use reqwest::{Method, Response, StatusCode};
+use crate::services::{ApiProvider, AuthService};
+
+const USER_AGENT: &str = "r3viewer";
+const GITHUB_API_VERSION: &str = "2022-11-28";
+
+// The one HTTP client behind every authenticated call to Google and GitHub. Tokens come from AuthService,
+// which refreshes them shortly before they expire; a 401 still forces one refresh and a single retry.
+#[derive(Clone)]
+pub struct ApiClient {
+    auth_service: AuthService,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(auth_service: AuthService) -> Self {
+        let http = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap_or_default();
+
+        Self { auth_service, http }
+    }
+
+    pub fn auth_service(&self) -> &AuthService {
+        &self.auth_service
+    }
+
+    // Unauthenticated requests, e.g. validating a token before it is stored
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    // Returns the response whatever its status; a 401 comes back only when refreshing did not help
+    pub async fn send(
+        &self,
+        provider: ApiProvider,
+        method: Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<Response> {
+        let mut token = self.auth_service.access_token(provider, false).await?;
+        let mut refreshed = false;
+
+        loop {
+            let response = self.request(provider, method.clone(), url, &token, body).send().await?;
+            if response.status() != StatusCode::UNAUTHORIZED || refreshed {
+                return Ok(response);
+            }
+
+            refreshed = true;
+            match self.auth_service.access_token(provider, true).await {
+                Ok(fresh) if fresh != token => token = fresh,
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    // Like send, but fails on any unsuccessful status and decodes the body
+    pub async fn json(
+        &self,
+        provider: ApiProvider,
+        method: Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let response = self.send(provider, method, url, body).await?;
+        let status = response.status();
+
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(anyhow!("{} rejected the stored credentials; sign in again", provider.display_name()));
+        }
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{} request failed: {} {}", provider.display_name(), status, detail));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    fn request(
+        &self,
+        provider: ApiProvider,
+        method: Method,
+        url: &str,
+        token: &str,
+        body: Option<&serde_json::Value>,
+    ) -> reqwest::RequestBuilder {
+        let mut request = self.http.request(method, url).bearer_auth(token);
+        if provider == ApiProvider::GitHub {
+            request = request
+                .header("Accept", "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", GITHUB_API_VERSION);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request
+    }
+}
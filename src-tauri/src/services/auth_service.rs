@@ -6,20 +6,53 @@ use oauth2::{
     reqwest::async_http_client, StandardTokenResponse, EmptyExtraTokenFields,
     RefreshToken, AccessToken,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 use url::Url;
+use crate::services::ApiClient;
+use crate::services::github_auth::{self, GitHubAppConfig, GitHubAppStatus, GitHubDeviceCode, GitHubDeviceLogin};
 
 const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 const GOOGLE_TOKEN_URL: &str = "https://www.googleapis.com/oauth2/v4/token";
-const GOOGLE_CLIENT_ID: &str = "your-google-client-id"; // This should be configurable
-const GOOGLE_REDIRECT_URI: &str = "http://localhost:8080/auth/callback";
+const GOOGLE_CLIENT_ID_ENV_VAR: &str = "R3VIEWER_GOOGLE_CLIENT_ID";
+const GOOGLE_CLIENT_SECRET_ENV_VAR: &str = "R3VIEWER_GOOGLE_CLIENT_SECRET";
+const GOOGLE_CALLBACK_PATH: &str = "/callback";
+const GITHUB_CLIENT_ID_ENV_VAR: &str = "R3VIEWER_GITHUB_CLIENT_ID";
+const GITHUB_CLIENT_SECRET_ENV_VAR: &str = "R3VIEWER_GITHUB_CLIENT_SECRET";
+const MAX_CALLBACK_REQUEST_BYTES: usize = 16 * 1024;
+const CALLBACK_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+// Tokens this close to expiring are refreshed before use rather than after a 401
+const TOKEN_REFRESH_MARGIN_SECONDS: i64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiProvider {
+    #[serde(rename = "google")]
+    Google,
+    #[serde(rename = "github")]
+    GitHub,
+}
+
+impl ApiProvider {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ApiProvider::Google => "Google",
+            ApiProvider::GitHub => "GitHub",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthCredentials {
     pub google_access_token: Option<String>,
     pub google_refresh_token: Option<String>,
+    pub google_token_expires_at: Option<DateTime<Utc>>, // None for tokens stored before expiry was tracked
     pub github_token: Option<String>,
+    pub github_refresh_token: Option<String>, // only for device logins through an app with expiring user tokens
+    pub github_token_expires_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,58 +60,291 @@ pub struct AuthStatus {
     pub google_authenticated: bool,
     pub github_authenticated: bool,
     pub user_email: Option<String>,
-    pub github_username: Option<String>,
+    pub github_username: Option<String>, // the installation's account when signed in as a GitHub App
+    pub github_auth_method: Option<String>, // "app", "device" or "token"
 }
 
+// The verifier and CSRF token never leave the backend; the browser only sees the authorization URL
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GoogleAuthUrl {
     pub auth_url: String,
-    pub csrf_token: String,
-    pub pkce_verifier: String,
+    pub redirect_uri: String,
+}
+
+// Client settings are looked up in the keychain, then the environment, then the values baked in at build time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoogleOAuthClient {
+    pub client_id: String,
+    pub client_secret: Option<String>, // Desktop-app clients still send theirs alongside PKCE
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubOAuthClient {
+    pub client_id: String,
+    pub client_secret: Option<String>, // only needed to refresh expiring user tokens
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubOAuthClientStatus {
+    pub configured: bool,
+    pub client_id: Option<String>,
+    pub has_client_secret: bool,
+    pub source: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoogleOAuthClientStatus {
+    pub configured: bool,
+    pub client_id: Option<String>,
+    pub has_client_secret: bool,
+    pub source: Option<String>, // "keychain", "environment" or "build"
+}
+
+// The sign-in started most recently; a callback must carry its CSRF token
+struct PendingGoogleLogin {
+    csrf_token: String,
+    pkce_verifier: String,
+    redirect_uri: String,
+}
+
+#[derive(Clone)]
 pub struct AuthService {
     keyring_service: String,
+    pending_google_login: Arc<Mutex<Option<PendingGoogleLogin>>>,
+    // Held while refreshing so concurrent callers wait for one refresh instead of each starting their own
+    refresh_lock: Arc<tokio::sync::Mutex<()>>,
+    // Installation tokens live an hour and are never written to the keychain
+    installation_token: Arc<Mutex<Option<(String, DateTime<Utc>)>>>,
+    // Device code of the GitHub device login started most recently; older ones stop polling
+    pending_github_device_code: Arc<Mutex<Option<String>>>,
+    http: reqwest::Client,
 }
 
 impl AuthService {
     pub fn new() -> Self {
         Self {
             keyring_service: "r3viewer".to_string(),
+            pending_google_login: Arc::new(Mutex::new(None)),
+            refresh_lock: Arc::new(tokio::sync::Mutex::new(())),
+            installation_token: Arc::new(Mutex::new(None)),
+            pending_github_device_code: Arc::new(Mutex::new(None)),
+            http: reqwest::Client::builder()
+                .user_agent("r3viewer")
+                .build()
+                .unwrap_or_default(),
         }
     }
 
+    // A token for `provider`, refreshed first when it is about to expire or when `force_refresh` is set
+    // (the API just answered 401)
+    pub async fn access_token(&self, provider: ApiProvider, force_refresh: bool) -> Result<String> {
+        match provider {
+            ApiProvider::Google => self.google_access_token(force_refresh).await,
+            ApiProvider::GitHub => self.github_access_token(force_refresh).await,
+        }
+    }
+
+    async fn google_access_token(&self, force_refresh: bool) -> Result<String> {
+        let credentials = self.get_stored_credentials()?;
+        let token = credentials.google_access_token
+            .ok_or_else(|| anyhow!("No Google access token available"))?;
+        if !force_refresh && !is_expiring(credentials.google_token_expires_at) {
+            return Ok(token);
+        }
+
+        let _guard = self.refresh_lock.lock().await;
+
+        // Someone else may have refreshed while we waited for the lock
+        let current = self.get_stored_credentials()?;
+        if let Some(current_token) = current.google_access_token {
+            if current_token != token && !is_expiring(current.google_token_expires_at) {
+                return Ok(current_token);
+            }
+        }
+
+        match self.refresh_google_token().await {
+            // An early refresh that fails (offline, revoked refresh token) leaves a token that still works for now
+            Err(_) if !force_refresh && !is_expired(credentials.google_token_expires_at) => Ok(token),
+            outcome => outcome,
+        }
+    }
+
+    // A configured GitHub App takes precedence over the signed-in user
+    async fn github_access_token(&self, force_refresh: bool) -> Result<String> {
+        if let Some(app) = self.github_app_config() {
+            return self.installation_access_token(&app, force_refresh).await;
+        }
+
+        let credentials = self.get_stored_credentials()?;
+        let token = credentials.github_token
+            .ok_or_else(|| anyhow!("No GitHub token available"))?;
+        if !force_refresh && !is_expiring(credentials.github_token_expires_at) {
+            return Ok(token);
+        }
+        if credentials.github_refresh_token.is_none() {
+            // Personal access tokens and classic OAuth tokens cannot be refreshed
+            if force_refresh {
+                return Err(anyhow!("GitHub rejected the stored token; sign in again"));
+            }
+            return Ok(token);
+        }
+
+        let _guard = self.refresh_lock.lock().await;
+
+        let current = self.get_stored_credentials()?;
+        if let Some(current_token) = current.github_token {
+            if current_token != token && !is_expiring(current.github_token_expires_at) {
+                return Ok(current_token);
+            }
+        }
+
+        match self.refresh_github_token().await {
+            Err(_) if !force_refresh && !is_expired(credentials.github_token_expires_at) => Ok(token),
+            outcome => outcome,
+        }
+    }
+
+    async fn installation_access_token(&self, app: &GitHubAppConfig, force_refresh: bool) -> Result<String> {
+        let installation_id = app.installation_id
+            .ok_or_else(|| anyhow!("The GitHub App has no installation selected"))?;
+        let cached = self.cached_installation_token()?;
+        if let Some((token, expires_at)) = &cached {
+            if !force_refresh && !is_expiring(Some(*expires_at)) {
+                return Ok(token.clone());
+            }
+        }
+
+        let _guard = self.refresh_lock.lock().await;
+
+        if let Some((token, expires_at)) = self.cached_installation_token()? {
+            if Some(&token) != cached.as_ref().map(|(t, _)| t) && !is_expiring(Some(expires_at)) {
+                return Ok(token);
+            }
+        }
+
+        let jwt = github_auth::app_jwt(&app.app_id, &app.private_key)?;
+        let (token, expires_at) = github_auth::create_installation_token(&self.http, &jwt, installation_id).await?;
+        *self.installation_token.lock().map_err(|_| anyhow!("Installation token cache is poisoned"))? =
+            Some((token.clone(), expires_at));
+
+        Ok(token)
+    }
+
+    fn cached_installation_token(&self) -> Result<Option<(String, DateTime<Utc>)>> {
+        Ok(self.installation_token.lock().map_err(|_| anyhow!("Installation token cache is poisoned"))?.clone())
+    }
+
     // Google OAuth2 Flow
-    pub fn generate_google_auth_url(&self) -> Result<GoogleAuthUrl> {
-        let client = self.create_google_oauth_client()?;
+    // Listens on an ephemeral loopback port for the redirect, which Google accepts for desktop clients
+    // without registering the port. Hand the listener to complete_google_login.
+    pub async fn begin_google_login(&self) -> Result<(GoogleAuthUrl, TcpListener)> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let redirect_uri = format!("http://127.0.0.1:{}{}", listener.local_addr()?.port(), GOOGLE_CALLBACK_PATH);
+        let client = self.create_google_oauth_client(&redirect_uri)?;
         
         let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
         
         let (auth_url, csrf_token) = client
             .authorize_url(CsrfToken::new_random)
-            .add_scope(Scope::new("https://www.googleapis.com/auth/spreadsheets.readonly".to_string()))
+            // Read rosters and write results back; tokens granted before exports existed must sign in again
+            .add_scope(Scope::new("https://www.googleapis.com/auth/spreadsheets".to_string()))
             .add_scope(Scope::new("https://www.googleapis.com/auth/userinfo.email".to_string()))
             .set_pkce_challenge(pkce_challenge)
+            // Ask for a refresh token every time so a re-login never leaves us without one
+            .add_extra_param("access_type", "offline")
+            .add_extra_param("prompt", "consent")
             .url();
 
-        Ok(GoogleAuthUrl {
-            auth_url: auth_url.to_string(),
+        *self.pending_google_login.lock().map_err(|_| anyhow!("Sign-in state is poisoned"))? = Some(PendingGoogleLogin {
             csrf_token: csrf_token.secret().clone(),
             pkce_verifier: pkce_verifier.secret().clone(),
-        })
+            redirect_uri: redirect_uri.clone(),
+        });
+
+        Ok((GoogleAuthUrl { auth_url: auth_url.to_string(), redirect_uri }, listener))
     }
 
-    pub async fn exchange_google_code(
-        &self,
-        code: String,
-        csrf_token: String,
-        pkce_verifier: String,
-    ) -> Result<()> {
-        let client = self.create_google_oauth_client()?;
+    // Serves the browser redirect, answering anything else (favicon requests) with 404, then exchanges the code.
+    // Connections that send nothing usable, and callbacks for an older sign-in, are answered and skipped.
+    pub async fn complete_google_login(&self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+
+            // Browsers open speculative connections that may never send a request
+            let target = match tokio::time::timeout(CALLBACK_READ_TIMEOUT, read_request_target(&mut stream)).await {
+                Ok(Ok(target)) => target,
+                _ => continue,
+            };
+            let Ok(url) = Url::parse(&format!("http://127.0.0.1{}", target)) else {
+                let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+                continue;
+            };
+            if url.path() != GOOGLE_CALLBACK_PATH {
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+                continue;
+            }
+
+            // Google echoes the state on success and on error alike. A callback without the pending sign-in's state,
+            // stale or forged by another local process, is answered without ending the sign-in.
+            let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+            let Some(state) = params.get("state").filter(|state| self.is_pending_google_login(state)) else {
+                write_callback_page(&mut stream, "This sign-in link is out of date. Return to r3viewer and use the latest one.").await;
+                continue;
+            };
+
+            let outcome = match (params.get("error"), params.get("code")) {
+                (Some(error), _) => Err(anyhow!("Google sign-in was not completed: {}", error)),
+                (None, Some(code)) => self.exchange_google_code(code.clone(), state.clone()).await,
+                (None, None) => Err(anyhow!("Google redirected back without an authorization code")),
+            };
+
+            let message = match &outcome {
+                Ok(()) => "Signed in to Google. You can close this tab and return to r3viewer.".to_string(),
+                Err(e) => format!("Google sign-in failed: {}. Return to r3viewer and try again.", e),
+            };
+            write_callback_page(&mut stream, &message).await;
+
+            return outcome;
+        }
+    }
+
+    // Drops the sign-in that redirects to `redirect_uri` if it is still the pending one. Returns false when it was
+    // already completed or replaced by a newer sign-in, whose outcome is reported separately.
+    pub fn abandon_google_login(&self, redirect_uri: &str) -> bool {
+        let Ok(mut guard) = self.pending_google_login.lock() else {
+            return false;
+        };
+        if guard.as_ref().is_some_and(|pending| pending.redirect_uri == redirect_uri) {
+            *guard = None;
+            return true;
+        }
+        false
+    }
+
+    fn is_pending_google_login(&self, state: &str) -> bool {
+        self.pending_google_login
+            .lock()
+            .map(|guard| guard.as_ref().is_some_and(|pending| pending.csrf_token == state))
+            .unwrap_or(false)
+    }
+
+    // `state` is the value Google echoed back; it must match the pending sign-in's CSRF token
+    pub async fn exchange_google_code(&self, code: String, state: String) -> Result<()> {
+        let pending = {
+            let mut guard = self.pending_google_login.lock().map_err(|_| anyhow!("Sign-in state is poisoned"))?;
+            match guard.as_ref().map(|pending| pending.csrf_token == state) {
+                Some(true) => guard.take(),
+                Some(false) => return Err(anyhow!("Sign-in state did not match; start the Google sign-in again")),
+                None => None,
+            }
+        }
+        .ok_or_else(|| anyhow!("No Google sign-in in progress"))?;
+
+        let client = self.create_google_oauth_client(&pending.redirect_uri)?;
         
         let token_result = client
             .exchange_code(AuthorizationCode::new(code))
-            .set_pkce_verifier(oauth2::PkceCodeVerifier::new(pkce_verifier))
+            .set_pkce_verifier(oauth2::PkceCodeVerifier::new(pending.pkce_verifier))
             .request_async(async_http_client)
             .await
             .map_err(|e| anyhow!("Failed to exchange authorization code: {}", e))?;
@@ -86,20 +352,18 @@ impl AuthService {
         // Store tokens securely
         self.store_google_tokens(
             token_result.access_token().secret(),
-            token_result.refresh_token().map(|t| t.secret()),
+            token_result.refresh_token().map(|t| t.secret().as_str()),
+            token_result.expires_in(),
         )?;
 
         Ok(())
     }
 
     // GitHub Token Management
-    pub async fn validate_github_token(&self, token: &str) -> Result<String> {
-        let client = reqwest::Client::new();
-        
-        let response = client
+    pub async fn validate_github_token(&self, api: &ApiClient, token: &str) -> Result<String> {
+        let response = api.http()
             .get("https://api.github.com/user")
-            .header("Authorization", format!("token {}", token))
-            .header("User-Agent", "r3viewer")
+            .bearer_auth(token)
             .send()
             .await?;
 
@@ -114,16 +378,101 @@ impl AuthService {
             .to_string();
 
         // Store GitHub token securely
-        self.store_github_token(token)?;
+        self.store_github_tokens(token, None, None, "token")?;
 
         Ok(username)
     }
 
+    // GitHub device flow: the user enters the returned code at the verification URI, then
+    // complete_github_device_login polls until they approve
+    pub async fn begin_github_device_login(&self) -> Result<(GitHubDeviceLogin, GitHubDeviceCode)> {
+        let (client, _) = self.github_oauth_client()?;
+        let device = github_auth::request_device_code(&self.http, &client.client_id).await?;
+        *self.pending_github_device_code.lock().map_err(|_| anyhow!("Sign-in state is poisoned"))? =
+            Some(device.device_code.clone());
+
+        Ok((
+            GitHubDeviceLogin {
+                user_code: device.user_code.clone(),
+                verification_uri: device.verification_uri.clone(),
+                expires_in: device.expires_in,
+            },
+            device,
+        ))
+    }
+
+    // Returns the signed-in username, or None when a newer device login replaced this one
+    pub async fn complete_github_device_login(&self, device: GitHubDeviceCode) -> Result<Option<String>> {
+        let (client, _) = self.github_oauth_client()?;
+        let is_current = || {
+            self.pending_github_device_code
+                .lock()
+                .map(|pending| pending.as_deref() == Some(device.device_code.as_str()))
+                .unwrap_or(false)
+        };
+        let Some(token) = github_auth::poll_device_token(&self.http, &client.client_id, &device, is_current).await? else {
+            return Ok(None);
+        };
+        if let Ok(mut pending) = self.pending_github_device_code.lock() {
+            *pending = None;
+        }
+
+        let response = self.http
+            .get("https://api.github.com/user")
+            .bearer_auth(&token.access_token)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("GitHub issued a token that cannot read the signed-in user"));
+        }
+        let user_info: serde_json::Value = response.json().await?;
+        let username = user_info["login"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Failed to get username from GitHub"))?
+            .to_string();
+
+        self.store_github_tokens(&token.access_token, token.refresh_token.as_deref(), token.expires_in, "device")?;
+
+        Ok(Some(username))
+    }
+
+    // Callers go through github_access_token, which serializes refreshes
+    async fn refresh_github_token(&self) -> Result<String> {
+        let refresh_token = self.get_stored_credentials()?.github_refresh_token
+            .ok_or_else(|| anyhow!("No GitHub refresh token available"))?;
+        let (client, _) = self.github_oauth_client()?;
+
+        let token = github_auth::refresh_user_token(
+            &self.http,
+            &client.client_id,
+            client.client_secret.as_deref(),
+            &refresh_token,
+        ).await?;
+
+        // GitHub rotates the refresh token on every use
+        self.store_github_tokens(&token.access_token, token.refresh_token.as_deref(), token.expires_in, "device")?;
+
+        Ok(token.access_token)
+    }
+
     // Credential Storage (OS Keychain)
-    fn store_google_tokens(&self, access_token: &str, refresh_token: Option<&str>) -> Result<()> {
+    fn store_google_tokens(
+        &self,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        expires_in: Option<std::time::Duration>,
+    ) -> Result<()> {
         let access_entry = Entry::new(&self.keyring_service, "google_access_token")?;
         access_entry.set_password(access_token)?;
 
+        let expiry_entry = Entry::new(&self.keyring_service, "google_token_expires_at")?;
+        match expires_in.and_then(|d| chrono::Duration::from_std(d).ok()) {
+            Some(expires_in) => expiry_entry.set_password(&(Utc::now() + expires_in).to_rfc3339())?,
+            None => {
+                let _ = expiry_entry.delete_password();
+            }
+        }
+
         if let Some(refresh_token) = refresh_token {
             let refresh_entry = Entry::new(&self.keyring_service, "google_refresh_token")?;
             refresh_entry.set_password(refresh_token)?;
@@ -132,21 +481,55 @@ impl AuthService {
         Ok(())
     }
 
-    fn store_github_token(&self, token: &str) -> Result<()> {
-        let entry = Entry::new(&self.keyring_service, "github_token")?;
-        entry.set_password(token)?;
+    fn store_github_tokens(
+        &self,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        expires_in: Option<i64>,
+        method: &str,
+    ) -> Result<()> {
+        for (name, value) in github_token_entries(access_token, refresh_token, expires_in, method, Utc::now()) {
+            let entry = Entry::new(&self.keyring_service, name)?;
+            match value {
+                Some(value) => entry.set_password(&value)?,
+                None => {
+                    let _ = entry.delete_password();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete_github_tokens(&self) -> Result<()> {
+        let _ = Entry::new(&self.keyring_service, "github_token")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "github_refresh_token")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "github_token_expires_at")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "github_auth_method")?.delete_password();
         Ok(())
     }
 
     pub fn get_stored_credentials(&self) -> Result<AuthCredentials> {
         let google_access_token = self.get_credential("google_access_token").ok();
         let google_refresh_token = self.get_credential("google_refresh_token").ok();
+        let google_token_expires_at = self.get_credential("google_token_expires_at")
+            .ok()
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|value| value.with_timezone(&Utc));
         let github_token = self.get_credential("github_token").ok();
+        let github_refresh_token = self.get_credential("github_refresh_token").ok();
+        let github_token_expires_at = self.get_credential("github_token_expires_at")
+            .ok()
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|value| value.with_timezone(&Utc));
 
         Ok(AuthCredentials {
             google_access_token,
             google_refresh_token,
+            google_token_expires_at,
             github_token,
+            github_refresh_token,
+            github_token_expires_at,
         })
     }
 
@@ -155,13 +538,15 @@ impl AuthService {
         entry.get_password().map_err(|e| anyhow!("Failed to get credential: {}", e))
     }
 
-    pub async fn refresh_google_token(&self) -> Result<String> {
+    // Callers go through google_access_token, which serializes refreshes
+    async fn refresh_google_token(&self) -> Result<String> {
         let credentials = self.get_stored_credentials()?;
         
         let refresh_token = credentials.google_refresh_token
             .ok_or_else(|| anyhow!("No refresh token available"))?;
 
-        let client = self.create_google_oauth_client()?;
+        // Refresh requests carry no redirect, any valid URI satisfies the client builder
+        let client = self.create_google_oauth_client(&format!("http://127.0.0.1{}", GOOGLE_CALLBACK_PATH))?;
         
         let token_result = client
             .exchange_refresh_token(&RefreshToken::new(refresh_token))
@@ -171,30 +556,40 @@ impl AuthService {
 
         let new_access_token = token_result.access_token().secret().clone();
         
-        // Store new access token
-        self.store_google_tokens(&new_access_token, None)?;
+        // Store new access token; Google may rotate the refresh token too
+        self.store_google_tokens(
+            &new_access_token,
+            token_result.refresh_token().map(|t| t.secret().as_str()),
+            token_result.expires_in(),
+        )?;
         
         Ok(new_access_token)
     }
 
-    pub async fn get_auth_status(&self) -> Result<AuthStatus> {
+    pub async fn get_auth_status(&self, api: &ApiClient) -> Result<AuthStatus> {
         let credentials = self.get_stored_credentials()?;
         
         let google_authenticated = credentials.google_access_token.is_some();
-        let github_authenticated = credentials.github_token.is_some();
+        let github_app = self.github_app_config();
+        let github_authenticated = github_app.is_some() || credentials.github_token.is_some();
         
         // Get user email from Google if authenticated
         let user_email = if google_authenticated {
-            self.get_google_user_email().await.ok()
+            self.get_google_user_email(api).await.ok()
         } else {
             None
         };
 
-        // Get GitHub username if authenticated
-        let github_username = if github_authenticated {
-            self.get_github_username().await.ok()
+        // Get GitHub username if authenticated; installation tokens cannot read /user
+        let (github_username, github_auth_method) = if github_app.is_some() {
+            (self.get_credential("github_app_account").ok(), Some("app".to_string()))
+        } else if github_authenticated {
+            (
+                self.get_github_username(api).await.ok(),
+                Some(self.get_credential("github_auth_method").unwrap_or_else(|_| "token".to_string())),
+            )
         } else {
-            None
+            (None, None)
         };
 
         Ok(AuthStatus {
@@ -202,26 +597,14 @@ impl AuthService {
             github_authenticated,
             user_email,
             github_username,
+            github_auth_method,
         })
     }
 
-    async fn get_google_user_email(&self) -> Result<String> {
-        let credentials = self.get_stored_credentials()?;
-        let access_token = credentials.google_access_token
-            .ok_or_else(|| anyhow!("No Google access token"))?;
-
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://www.googleapis.com/oauth2/v2/userinfo")
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
+    async fn get_google_user_email(&self, api: &ApiClient) -> Result<String> {
+        let user_info = api
+            .json(ApiProvider::Google, reqwest::Method::GET, "https://www.googleapis.com/oauth2/v2/userinfo", None)
             .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("Failed to get user info"));
-        }
-
-        let user_info: serde_json::Value = response.json().await?;
         let email = user_info["email"]
             .as_str()
             .ok_or_else(|| anyhow!("No email in user info"))?
@@ -230,24 +613,10 @@ impl AuthService {
         Ok(email)
     }
 
-    async fn get_github_username(&self) -> Result<String> {
-        let credentials = self.get_stored_credentials()?;
-        let token = credentials.github_token
-            .ok_or_else(|| anyhow!("No GitHub token"))?;
-
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://api.github.com/user")
-            .header("Authorization", format!("token {}", token))
-            .header("User-Agent", "r3viewer")
-            .send()
+    async fn get_github_username(&self, api: &ApiClient) -> Result<String> {
+        let user_info = api
+            .json(ApiProvider::GitHub, reqwest::Method::GET, "https://api.github.com/user", None)
             .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("Failed to get GitHub user info"));
-        }
-
-        let user_info: serde_json::Value = response.json().await?;
         let username = user_info["login"]
             .as_str()
             .ok_or_else(|| anyhow!("No login in user info"))?
@@ -256,29 +625,311 @@ impl AuthService {
         Ok(username)
     }
 
+    // Removes all stored credentials. A GitHub App is configuration shared by the organization, not a sign-in,
+    // so it stays until remove_github_app.
     pub fn logout(&self) -> Result<()> {
-        // Remove all stored credentials
         let _ = Entry::new(&self.keyring_service, "google_access_token")?.delete_password();
         let _ = Entry::new(&self.keyring_service, "google_refresh_token")?.delete_password();
-        let _ = Entry::new(&self.keyring_service, "github_token")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "google_token_expires_at")?.delete_password();
+        self.delete_github_tokens()?;
+        if let Ok(mut cached) = self.installation_token.lock() {
+            *cached = None;
+        }
         Ok(())
     }
 
-    fn create_google_oauth_client(&self) -> Result<BasicClient> {
-        let client_id = ClientId::new(GOOGLE_CLIENT_ID.to_string());
-        let client_secret = ClientSecret::new("".to_string()); // PKCE doesn't require client secret
+    // OAuth client settings
+    pub fn google_oauth_client_status(&self) -> GoogleOAuthClientStatus {
+        match self.google_oauth_client() {
+            Ok((client, source)) => GoogleOAuthClientStatus {
+                configured: true,
+                client_id: Some(client.client_id),
+                has_client_secret: client.client_secret.is_some(),
+                source: Some(source.to_string()),
+            },
+            Err(_) => GoogleOAuthClientStatus { configured: false, client_id: None, has_client_secret: false, source: None },
+        }
+    }
+
+    // Saving replaces any earlier keychain settings; existing tokens were issued to the old client, so they are dropped
+    pub fn set_google_oauth_client(&self, client: &GoogleOAuthClient) -> Result<()> {
+        let client_id = client.client_id.trim();
+        if client_id.is_empty() {
+            return Err(anyhow!("Client ID is required"));
+        }
+
+        Entry::new(&self.keyring_service, "google_client_id")?.set_password(client_id)?;
+        let secret_entry = Entry::new(&self.keyring_service, "google_client_secret")?;
+        match client.client_secret.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(secret) => secret_entry.set_password(secret)?,
+            None => {
+                let _ = secret_entry.delete_password();
+            }
+        }
+
+        let _ = Entry::new(&self.keyring_service, "google_access_token")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "google_refresh_token")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "google_token_expires_at")?.delete_password();
+        Ok(())
+    }
+
+    fn google_oauth_client(&self) -> Result<(GoogleOAuthClient, &'static str)> {
+        if let Ok(client_id) = self.get_credential("google_client_id") {
+            let client_secret = self.get_credential("google_client_secret").ok();
+            return Ok((GoogleOAuthClient { client_id, client_secret }, "keychain"));
+        }
+
+        let non_empty = |value: String| Some(value).filter(|v| !v.trim().is_empty());
+        if let Some(client_id) = std::env::var(GOOGLE_CLIENT_ID_ENV_VAR).ok().and_then(non_empty) {
+            let client_secret = std::env::var(GOOGLE_CLIENT_SECRET_ENV_VAR).ok().and_then(non_empty);
+            return Ok((GoogleOAuthClient { client_id, client_secret }, "environment"));
+        }
+
+        if let Some(client_id) = option_env!("R3VIEWER_GOOGLE_CLIENT_ID").filter(|v| !v.trim().is_empty()) {
+            let client_secret = option_env!("R3VIEWER_GOOGLE_CLIENT_SECRET").filter(|v| !v.trim().is_empty());
+            return Ok((
+                GoogleOAuthClient { client_id: client_id.to_string(), client_secret: client_secret.map(str::to_string) },
+                "build",
+            ));
+        }
+
+        Err(anyhow!(
+            "Google sign-in is not configured. Enter an OAuth client ID in settings or set {}",
+            GOOGLE_CLIENT_ID_ENV_VAR
+        ))
+    }
+
+    pub fn github_oauth_client_status(&self) -> GitHubOAuthClientStatus {
+        match self.github_oauth_client() {
+            Ok((client, source)) => GitHubOAuthClientStatus {
+                configured: true,
+                client_id: Some(client.client_id),
+                has_client_secret: client.client_secret.is_some(),
+                source: Some(source.to_string()),
+            },
+            Err(_) => GitHubOAuthClientStatus { configured: false, client_id: None, has_client_secret: false, source: None },
+        }
+    }
+
+    // Tokens from the device flow belong to the old client and are dropped; a pasted token is kept
+    pub fn set_github_oauth_client(&self, client: &GitHubOAuthClient) -> Result<()> {
+        let client_id = client.client_id.trim();
+        if client_id.is_empty() {
+            return Err(anyhow!("Client ID is required"));
+        }
+
+        Entry::new(&self.keyring_service, "github_client_id")?.set_password(client_id)?;
+        let secret_entry = Entry::new(&self.keyring_service, "github_client_secret")?;
+        match client.client_secret.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(secret) => secret_entry.set_password(secret)?,
+            None => {
+                let _ = secret_entry.delete_password();
+            }
+        }
+
+        if self.get_credential("github_auth_method").ok().as_deref() == Some("device") {
+            self.delete_github_tokens()?;
+        }
+        Ok(())
+    }
+
+    fn github_oauth_client(&self) -> Result<(GitHubOAuthClient, &'static str)> {
+        if let Ok(client_id) = self.get_credential("github_client_id") {
+            let client_secret = self.get_credential("github_client_secret").ok();
+            return Ok((GitHubOAuthClient { client_id, client_secret }, "keychain"));
+        }
+
+        let non_empty = |value: String| Some(value).filter(|v| !v.trim().is_empty());
+        if let Some(client_id) = std::env::var(GITHUB_CLIENT_ID_ENV_VAR).ok().and_then(non_empty) {
+            let client_secret = std::env::var(GITHUB_CLIENT_SECRET_ENV_VAR).ok().and_then(non_empty);
+            return Ok((GitHubOAuthClient { client_id, client_secret }, "environment"));
+        }
+
+        if let Some(client_id) = option_env!("R3VIEWER_GITHUB_CLIENT_ID").filter(|v| !v.trim().is_empty()) {
+            let client_secret = option_env!("R3VIEWER_GITHUB_CLIENT_SECRET").filter(|v| !v.trim().is_empty());
+            return Ok((
+                GitHubOAuthClient { client_id: client_id.to_string(), client_secret: client_secret.map(str::to_string) },
+                "build",
+            ));
+        }
+
+        Err(anyhow!(
+            "GitHub sign-in is not configured. Enter an OAuth or GitHub App client ID in settings or set {}",
+            GITHUB_CLIENT_ID_ENV_VAR
+        ))
+    }
+
+    // GitHub App settings
+    pub fn github_app_status(&self) -> GitHubAppStatus {
+        match self.github_app_config() {
+            Some(app) => GitHubAppStatus {
+                configured: true,
+                app_id: Some(app.app_id),
+                installation_id: app.installation_id,
+                account: self.get_credential("github_app_account").ok(),
+            },
+            None => GitHubAppStatus { configured: false, app_id: None, installation_id: None, account: None },
+        }
+    }
+
+    // Verified by minting an installation token before anything is saved; see select_installation for the
+    // installation used when none is given
+    pub async fn set_github_app(&self, config: &GitHubAppConfig) -> Result<GitHubAppStatus> {
+        let app_id = config.app_id.trim();
+        if app_id.is_empty() {
+            return Err(anyhow!("App ID is required"));
+        }
+
+        let jwt = github_auth::app_jwt(app_id, &config.private_key)?;
+        let installation = match config.installation_id {
+            Some(installation_id) => github_auth::get_installation(&self.http, &jwt, installation_id).await?,
+            None => github_auth::select_installation(github_auth::list_installations(&self.http, &jwt).await?)?,
+        };
+        let (token, expires_at) = github_auth::create_installation_token(&self.http, &jwt, installation.id).await?;
+
+        Entry::new(&self.keyring_service, "github_app_id")?.set_password(app_id)?;
+        Entry::new(&self.keyring_service, "github_app_installation_id")?.set_password(&installation.id.to_string())?;
+        Entry::new(&self.keyring_service, "github_app_private_key")?.set_password(config.private_key.trim())?;
+        Entry::new(&self.keyring_service, "github_app_account")?.set_password(&installation.account)?;
+        *self.installation_token.lock().map_err(|_| anyhow!("Installation token cache is poisoned"))? =
+            Some((token, expires_at));
+
+        Ok(self.github_app_status())
+    }
+
+    pub fn remove_github_app(&self) -> Result<()> {
+        let _ = Entry::new(&self.keyring_service, "github_app_id")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "github_app_installation_id")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "github_app_private_key")?.delete_password();
+        let _ = Entry::new(&self.keyring_service, "github_app_account")?.delete_password();
+        *self.installation_token.lock().map_err(|_| anyhow!("Installation token cache is poisoned"))? = None;
+        Ok(())
+    }
+
+    fn github_app_config(&self) -> Option<GitHubAppConfig> {
+        Some(GitHubAppConfig {
+            app_id: self.get_credential("github_app_id").ok()?,
+            installation_id: self.get_credential("github_app_installation_id").ok().and_then(|id| id.parse().ok()),
+            private_key: self.get_credential("github_app_private_key").ok()?,
+        })
+    }
+
+    fn create_google_oauth_client(&self, redirect_uri: &str) -> Result<BasicClient> {
+        let (config, _) = self.google_oauth_client()?;
+        let client_id = ClientId::new(config.client_id);
+        let client_secret = config.client_secret.map(ClientSecret::new);
         let auth_url = AuthUrl::new(GOOGLE_AUTH_URL.to_string())
             .map_err(|e| anyhow!("Invalid auth URL: {}", e))?;
         let token_url = TokenUrl::new(GOOGLE_TOKEN_URL.to_string())
             .map_err(|e| anyhow!("Invalid token URL: {}", e))?;
-        let redirect_url = RedirectUrl::new(GOOGLE_REDIRECT_URI.to_string())
+        let redirect_url = RedirectUrl::new(redirect_uri.to_string())
             .map_err(|e| anyhow!("Invalid redirect URL: {}", e))?;
 
         Ok(BasicClient::new(
             client_id,
-            Some(client_secret),
+            client_secret,
             auth_url,
             Some(token_url),
         ).set_redirect_uri(redirect_url))
     }
-} 
\ No newline at end of file
+}
+
+fn is_expiring(expires_at: Option<DateTime<Utc>>) -> bool {
+    expires_at.is_some_and(|at| at - Utc::now() < chrono::Duration::seconds(TOKEN_REFRESH_MARGIN_SECONDS))
+}
+
+fn is_expired(expires_at: Option<DateTime<Utc>>) -> bool {
+    expires_at.is_some_and(|at| at <= Utc::now())
+}
+
+// Reads an HTTP request head and returns its target, e.g. "/callback?code=..."
+async fn read_request_target(stream: &mut TcpStream) -> Result<String> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_CALLBACK_REQUEST_BYTES {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    String::from_utf8_lossy(&request)
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Not an HTTP request"))
+}
+
+async fn write_callback_page(stream: &mut TcpStream, message: &str) {
+    let body = format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>r3viewer</title></head><body><p>{}</p></body></html>",
+        html_escape(message)
+    );
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+// The keychain entries a GitHub sign-in writes, None deleting the entry. A missing refresh token or lifetime clears
+// the stored one, so a pasted token never inherits a device login's.
+fn github_token_entries(
+    access_token: &str,
+    refresh_token: Option<&str>,
+    expires_in: Option<i64>,
+    method: &str,
+    now: DateTime<Utc>,
+) -> [(&'static str, Option<String>); 4] {
+    [
+        ("github_token", Some(access_token.to_string())),
+        ("github_auth_method", Some(method.to_string())),
+        ("github_refresh_token", refresh_token.map(str::to_string)),
+        ("github_token_expires_at", expires_in.map(|seconds| (now + chrono::Duration::seconds(seconds)).to_rfc3339())),
+    ]
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_inside_the_refresh_margin_are_expiring_but_not_expired() {
+        let soon = Some(Utc::now() + chrono::Duration::seconds(TOKEN_REFRESH_MARGIN_SECONDS / 2));
+        assert!(is_expiring(soon));
+        assert!(!is_expired(soon));
+
+        let later = Some(Utc::now() + chrono::Duration::hours(1));
+        assert!(!is_expiring(later));
+
+        let past = Some(Utc::now() - chrono::Duration::seconds(1));
+        assert!(is_expiring(past));
+        assert!(is_expired(past));
+
+        // Tokens stored before expiry was tracked are only refreshed after a 401
+        assert!(!is_expiring(None));
+        assert!(!is_expired(None));
+    }
+
+    #[test]
+    fn pasted_github_tokens_clear_the_refresh_token_and_expiry() {
+        let now = Utc::now();
+        let device = github_token_entries("gho_device", Some("ghr_refresh"), Some(28800), "device", now);
+        assert_eq!(device[2], ("github_refresh_token", Some("ghr_refresh".to_string())));
+        assert_eq!(device[3], ("github_token_expires_at", Some((now + chrono::Duration::hours(8)).to_rfc3339())));
+
+        let pasted = github_token_entries("ghp_pasted", None, None, "token", now);
+        assert_eq!(pasted[0], ("github_token", Some("ghp_pasted".to_string())));
+        assert_eq!(pasted[1], ("github_auth_method", Some("token".to_string())));
+        assert_eq!(pasted[2], ("github_refresh_token", None));
+        assert_eq!(pasted[3], ("github_token_expires_at", None));
+    }
+}
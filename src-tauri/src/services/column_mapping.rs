@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use crate::services::identity_service::{name_similarity, normalize_name};
+use crate::services::{ProjectColumns, SheetData, SheetMapping};
+
+// Below this a field is left unmapped rather than guessed
+const MIN_CONFIDENCE: f64 = 0.45;
+const CONTENT_SAMPLE_SIZE: usize = 50;
+const HEADER_WEIGHT: f64 = 0.65;
+const CONTENT_WEIGHT: f64 = 0.35;
+// Share of repository links that makes a leftover column an additional repository column
+const EXTRA_REPOSITORY_THRESHOLD: f64 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MappedField {
+    #[serde(rename = "name")]
+    Name,
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "github_username")]
+    GitHubUsername,
+    #[serde(rename = "github_url")]
+    GitHubUrl,
+    #[serde(rename = "project_name")]
+    ProjectName,
+    #[serde(rename = "project_description")]
+    ProjectDescription,
+    #[serde(rename = "cohort")]
+    Cohort,
+    #[serde(rename = "team")]
+    Team,
+}
+
+const ALL_FIELDS: [MappedField; 8] = [
+    MappedField::Name,
+    MappedField::Email,
+    MappedField::GitHubUsername,
+    MappedField::GitHubUrl,
+    MappedField::ProjectName,
+    MappedField::ProjectDescription,
+    MappedField::Cohort,
+    MappedField::Team,
+];
+
+impl MappedField {
+    fn synonyms(&self) -> &'static [&'static str] {
+        match self {
+            MappedField::Name => &["name", "full name", "student", "student name", "learner", "participant", "names"],
+            MappedField::Email => &["email", "e-mail", "email address", "mail", "student email", "school email"],
+            MappedField::GitHubUsername => &["github username", "github", "github handle", "github user", "github id", "handle", "username", "gh"],
+            MappedField::GitHubUrl => &["github url", "repo", "repository", "repo link", "repo url", "repository url", "github link", "project url", "project link", "link", "url", "submission"],
+            MappedField::ProjectName => &["project name", "project", "project title", "title", "app name"],
+            MappedField::ProjectDescription => &["project description", "description", "summary", "about", "notes", "details"],
+            MappedField::Cohort => &["cohort", "class", "section", "group", "intake", "batch", "stream"],
+            MappedField::Team => &["team", "team name", "squad", "pair", "group name"],
+        }
+    }
+
+    // Fields whose cells are distinctive enough to map on content alone
+    fn content_is_decisive(&self) -> bool {
+        matches!(self, MappedField::Email | MappedField::GitHubUrl)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldSuggestion {
+    pub field: MappedField,
+    pub header: Option<String>,
+    pub column_index: Option<usize>,
+    pub confidence: f64, // 0.0 to 1.0
+    pub header_score: f64,
+    pub content_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingSuggestion {
+    pub mapping: SheetMapping,
+    pub fields: Vec<FieldSuggestion>,
+    pub unmapped_headers: Vec<String>,
+}
+
+// Scores every (field, column) pair on its header and a sample of its cells, then assigns greedily
+// from the most confident pair down so each column feeds at most one field
+pub fn suggest_mapping(sheet: &SheetData) -> MappingSuggestion {
+    let profiles: Vec<ColumnProfile> = (0..sheet.headers.len())
+        .map(|index| ColumnProfile::sample(sheet, index))
+        .collect();
+
+    let mut candidates = Vec::new();
+    for field in ALL_FIELDS {
+        for (index, header) in sheet.headers.iter().enumerate() {
+            let header_score = header_score(field, header);
+            let content_score = profiles[index].content_score(field);
+            let mut confidence = HEADER_WEIGHT * header_score + CONTENT_WEIGHT * content_score;
+            if field.content_is_decisive() {
+                confidence = confidence.max(0.7 * content_score);
+            }
+            candidates.push((field, index, confidence, header_score, content_score));
+        }
+    }
+    candidates.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut fields: Vec<FieldSuggestion> = Vec::new();
+    let mut used_columns = Vec::new();
+    for (field, index, confidence, header_score, content_score) in candidates {
+        if confidence < MIN_CONFIDENCE || used_columns.contains(&index) || fields.iter().any(|f| f.field == field) {
+            continue;
+        }
+        used_columns.push(index);
+        fields.push(FieldSuggestion {
+            field,
+            header: Some(sheet.headers[index].clone()),
+            column_index: Some(index),
+            confidence: round(confidence),
+            header_score: round(header_score),
+            content_score: round(content_score),
+        });
+    }
+
+    // The name column is required; fall back to the most name-like column left, flagged by its low confidence
+    if !fields.iter().any(|f| f.field == MappedField::Name) {
+        let fallback = (0..sheet.headers.len())
+            .filter(|index| !used_columns.contains(index))
+            .max_by(|a, b| {
+                profiles[*a]
+                    .content_score(MappedField::Name)
+                    .partial_cmp(&profiles[*b].content_score(MappedField::Name))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        if let Some(index) = fallback {
+            used_columns.push(index);
+            let content_score = profiles[index].content_score(MappedField::Name);
+            fields.push(FieldSuggestion {
+                field: MappedField::Name,
+                header: Some(sheet.headers[index].clone()),
+                column_index: Some(index),
+                confidence: round(CONTENT_WEIGHT * content_score),
+                header_score: 0.0,
+                content_score: round(content_score),
+            });
+        }
+    }
+
+    for field in ALL_FIELDS {
+        if !fields.iter().any(|f| f.field == field) {
+            fields.push(FieldSuggestion {
+                field,
+                header: None,
+                column_index: None,
+                confidence: 0.0,
+                header_score: 0.0,
+                content_score: 0.0,
+            });
+        }
+    }
+    fields.sort_by_key(|f| ALL_FIELDS.iter().position(|field| *field == f.field));
+
+    // Rosters with one repository column per assignment: leftover columns full of repository links
+    let extra_project_columns: Vec<usize> = (0..sheet.headers.len())
+        .filter(|index| !used_columns.contains(index) && profiles[*index].github_urls >= EXTRA_REPOSITORY_THRESHOLD)
+        .collect();
+    used_columns.extend(&extra_project_columns);
+
+    let header_for = |field: MappedField| fields.iter().find(|f| f.field == field).and_then(|f| f.header.clone());
+    let mapping = SheetMapping {
+        name_column: header_for(MappedField::Name).unwrap_or_else(|| SheetMapping::default().name_column),
+        email_column: header_for(MappedField::Email),
+        github_username_column: header_for(MappedField::GitHubUsername),
+        github_url_column: header_for(MappedField::GitHubUrl),
+        project_name_column: header_for(MappedField::ProjectName),
+        project_description_column: header_for(MappedField::ProjectDescription),
+        cohort_column: header_for(MappedField::Cohort),
+        team_column: header_for(MappedField::Team),
+        assignment_column: None,
+        extra_projects: extra_project_columns
+            .iter()
+            .map(|index| ProjectColumns {
+                github_url_column: sheet.headers[*index].clone(),
+                project_name_column: None,
+                project_description_column: None,
+                assignment_id: None,
+            })
+            .collect(),
+    };
+
+    let unmapped_headers = sheet
+        .headers
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !used_columns.contains(index))
+        .map(|(_, header)| header.clone())
+        .collect();
+
+    MappingSuggestion { mapping, fields, unmapped_headers }
+}
+
+fn header_score(field: MappedField, header: &str) -> f64 {
+    let header = normalize_header(header);
+    if header.is_empty() {
+        return 0.0;
+    }
+
+    field
+        .synonyms()
+        .iter()
+        .map(|synonym| {
+            let synonym = normalize_header(synonym);
+            if header == synonym {
+                1.0
+            } else if header.split(' ').collect::<Vec<_>>().windows(synonym.split(' ').count()).any(|w| w.join(" ") == synonym) {
+                // "Student GitHub handle" contains "github handle"
+                0.85
+            } else {
+                name_similarity(&normalize_name(&header), &normalize_name(&synonym))
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+// Lowercase words only; punctuation and underscores become spaces so "github_url" equals "GitHub URL"
+fn normalize_header(header: &str) -> String {
+    header
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// Fractions of the sampled non-empty cells that look like each kind of value
+struct ColumnProfile {
+    emails: f64,
+    github_urls: f64,
+    usernames: f64,
+    person_names: f64,
+    long_text: f64,
+    short_text: f64,
+    repetition: f64, // 1 - distinct/total, high for cohort and team columns
+}
+
+impl ColumnProfile {
+    fn sample(sheet: &SheetData, index: usize) -> Self {
+        let cells: Vec<&str> = sheet
+            .rows
+            .iter()
+            .filter_map(|row| row.get(index))
+            .map(|cell| cell.trim())
+            .filter(|cell| !cell.is_empty())
+            .take(CONTENT_SAMPLE_SIZE)
+            .collect();
+
+        if cells.is_empty() {
+            return Self { emails: 0.0, github_urls: 0.0, usernames: 0.0, person_names: 0.0, long_text: 0.0, short_text: 0.0, repetition: 0.0 };
+        }
+
+        let email = regex::Regex::new(r"^[^@\s]+@[^@\s]+\.[A-Za-z]{2,}$").unwrap();
+        let github_url = regex::Regex::new(r"(?i)^(https?://)?(www\.)?github\.com/[^/\s]+").unwrap();
+        let username = regex::Regex::new(r"^@?[A-Za-z0-9](?:[A-Za-z0-9-]{0,38})$").unwrap();
+        let person_name = regex::Regex::new(r"^\p{Lu}[\p{L}'.-]*(?:,?\s+\p{L}[\p{L}'.-]*){1,3}$").unwrap();
+
+        let fraction = |matches: usize| matches as f64 / cells.len() as f64;
+        let mut distinct: Vec<String> = cells.iter().map(|cell| cell.to_lowercase()).collect();
+        distinct.sort();
+        distinct.dedup();
+
+        Self {
+            emails: fraction(cells.iter().filter(|c| email.is_match(c)).count()),
+            github_urls: fraction(cells.iter().filter(|c| github_url.is_match(c)).count()),
+            usernames: fraction(
+                cells.iter().filter(|c| username.is_match(c) && !c.chars().all(|ch| ch.is_ascii_digit())).count(),
+            ),
+            person_names: fraction(cells.iter().filter(|c| person_name.is_match(c)).count()),
+            long_text: fraction(cells.iter().filter(|c| c.split_whitespace().count() >= 6).count()),
+            short_text: fraction(cells.iter().filter(|c| (1..=5).contains(&c.split_whitespace().count()) && !c.contains('@') && !c.contains('/')).count()),
+            repetition: if cells.len() < 3 { 0.0 } else { 1.0 - distinct.len() as f64 / cells.len() as f64 },
+        }
+    }
+
+    fn content_score(&self, field: MappedField) -> f64 {
+        match field {
+            MappedField::Email => self.emails,
+            MappedField::GitHubUrl => self.github_urls,
+            MappedField::GitHubUsername => self.usernames * (1.0 - self.emails) * (1.0 - self.person_names),
+            MappedField::Name => self.person_names * (1.0 - self.repetition),
+            MappedField::ProjectName => self.short_text * (1.0 - self.person_names) * 0.6,
+            MappedField::ProjectDescription => self.long_text,
+            MappedField::Cohort | MappedField::Team => self.short_text * self.repetition,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheet(headers: &[&str], rows: &[&[&str]]) -> SheetData {
+        SheetData {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: rows.iter().map(|row| row.iter().map(|c| c.to_string()).collect()).collect(),
+        }
+    }
+
+    fn column(suggestion: &MappingSuggestion, field: MappedField) -> Option<usize> {
+        suggestion.fields.iter().find(|f| f.field == field).and_then(|f| f.column_index)
+    }
+
+    #[test]
+    fn suggest_mapping_matches_headers_and_synonyms() {
+        let suggestion = suggest_mapping(&sheet(
+            &["Full Name", "E-mail", "GitHub handle", "Repository URL", "Section"],
+            &[
+                &["Ada Lovelace", "ada@example.edu", "ada", "https://github.com/ada/app", "A"],
+                &["Grace Hopper", "grace@example.edu", "grace", "https://github.com/grace/app", "A"],
+                &["Alan Turing", "alan@example.edu", "alan", "https://github.com/alan/app", "B"],
+            ],
+        ));
+
+        assert_eq!(suggestion.mapping.name_column, "Full Name");
+        assert_eq!(suggestion.mapping.email_column.as_deref(), Some("E-mail"));
+        assert_eq!(suggestion.mapping.github_username_column.as_deref(), Some("GitHub handle"));
+        assert_eq!(suggestion.mapping.github_url_column.as_deref(), Some("Repository URL"));
+        assert_eq!(suggestion.mapping.cohort_column.as_deref(), Some("Section"));
+        assert!(suggestion.unmapped_headers.is_empty());
+        // One suggestion per field, in field order, unmapped fields included
+        assert_eq!(suggestion.fields.iter().map(|f| f.field).collect::<Vec<_>>(), ALL_FIELDS);
+    }
+
+    #[test]
+    fn suggest_mapping_recognises_columns_by_content() {
+        let suggestion = suggest_mapping(&sheet(
+            &["Col A", "Col B", "Col C"],
+            &[
+                &["Ada Lovelace", "ada@example.edu", "https://github.com/ada/app"],
+                &["Grace Hopper", "grace@example.edu", "https://github.com/grace/app"],
+            ],
+        ));
+
+        assert_eq!(column(&suggestion, MappedField::Email), Some(1));
+        assert_eq!(column(&suggestion, MappedField::GitHubUrl), Some(2));
+        // The required name column falls back to the most name-like column left
+        assert_eq!(column(&suggestion, MappedField::Name), Some(0));
+    }
+
+    #[test]
+    fn suggest_mapping_uses_each_column_once_and_collects_extra_repositories() {
+        let suggestion = suggest_mapping(&sheet(
+            &["Name", "Repo", "Resubmission", "Favourite colour"],
+            &[
+                &["Ada Lovelace", "https://github.com/ada/one", "https://github.com/ada/two", "red"],
+                &["Grace Hopper", "https://github.com/grace/one", "https://github.com/grace/two", "blue"],
+            ],
+        ));
+
+        assert_eq!(suggestion.mapping.github_url_column.as_deref(), Some("Repo"));
+        let extra: Vec<&str> = suggestion.mapping.extra_projects.iter().map(|p| p.github_url_column.as_str()).collect();
+        assert_eq!(extra, ["Resubmission"]);
+
+        let mut used: Vec<usize> = suggestion.fields.iter().filter_map(|f| f.column_index).collect();
+        let total = used.len();
+        used.sort();
+        used.dedup();
+        assert_eq!(used.len(), total);
+        assert!(suggestion.fields.iter().all(|f| f.column_index.is_none() || f.confidence > 0.0));
+    }
+
+    #[test]
+    fn normalize_header_ignores_case_and_punctuation() {
+        assert_eq!(normalize_header("  GitHub_URL "), "github url");
+        assert_eq!(header_score(MappedField::GitHubUrl, "github_url"), 1.0);
+        assert_eq!(header_score(MappedField::GitHubUsername, "Student GitHub handle"), 0.85);
+        assert_eq!(header_score(MappedField::Name, ""), 0.0);
+    }
+}
@@ -0,0 +1,400 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use bollard::{Docker, API_DEFAULT_VERSION, models::ContainerSummary};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::database::models::TechnologyStack;
+use crate::services::{
+    CachePruneReport, CacheVolumeUsage, CommittedSnapshot, DependencyCache, DockerService,
+    PlaygroundInfo, PlaygroundOptions, PlaygroundStatus, PullProgressCallback, ResourceUsage,
+};
+
+const RUNTIME_ENV_VAR: &str = "R3VIEWER_CONTAINER_RUNTIME";
+const PODMAN_CONNECT_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuntimeKind {
+    #[serde(rename = "docker")]
+    Docker,
+    #[serde(rename = "podman")]
+    Podman,
+    #[serde(rename = "disabled")]
+    Disabled,
+}
+
+impl RuntimeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuntimeKind::Docker => "docker",
+            RuntimeKind::Podman => "podman",
+            RuntimeKind::Disabled => "disabled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuntimePreference {
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "docker")]
+    Docker,
+    #[serde(rename = "podman")]
+    Podman,
+    #[serde(rename = "disabled")]
+    Disabled,
+}
+
+impl RuntimePreference {
+    // Read from R3VIEWER_CONTAINER_RUNTIME, falling back to auto-detection
+    pub fn from_env() -> Self {
+        match std::env::var(RUNTIME_ENV_VAR).map(|v| v.trim().to_lowercase()).as_deref() {
+            Ok("docker") => RuntimePreference::Docker,
+            Ok("podman") => RuntimePreference::Podman,
+            Ok("disabled") | Ok("none") | Ok("off") => RuntimePreference::Disabled,
+            _ => RuntimePreference::Auto,
+        }
+    }
+
+    fn candidates(&self) -> &'static [RuntimeKind] {
+        match self {
+            RuntimePreference::Auto => &[RuntimeKind::Docker, RuntimeKind::Podman],
+            RuntimePreference::Docker => &[RuntimeKind::Docker],
+            RuntimePreference::Podman => &[RuntimeKind::Podman],
+            RuntimePreference::Disabled => &[],
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContainerRuntimeError {
+    #[error("Container runtime unavailable: {reason}")]
+    Unavailable { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeProbeFailure {
+    pub runtime: RuntimeKind,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerRuntimeStatus {
+    pub active_runtime: RuntimeKind,
+    pub available: bool,
+    pub preference: RuntimePreference,
+    pub failures: Vec<RuntimeProbeFailure>,
+}
+
+#[async_trait]
+pub trait ContainerRuntime: Send + Sync {
+    fn kind(&self) -> RuntimeKind;
+
+    async fn ping(&self) -> Result<()>;
+
+    async fn start_playground(&self, project_path: &Path, tech_stack: &[TechnologyStack], options: &PlaygroundOptions) -> Result<PlaygroundInfo>;
+
+    async fn stop_playground(&self, container_id: &str) -> Result<()>;
+
+    async fn get_playground_status(&self, container_id: &str) -> Result<PlaygroundStatus>;
+
+    async fn get_resource_usage(&self, container_id: &str) -> Result<ResourceUsage>;
+
+    async fn list_active_playgrounds(&self) -> Result<Vec<ContainerSummary>>;
+
+    async fn cleanup_old_containers(&self, max_age_hours: u64) -> Result<usize>;
+
+    async fn get_cache_usage(&self) -> Result<Vec<CacheVolumeUsage>>;
+
+    async fn prune_caches(&self, caches: &[DependencyCache]) -> Result<CachePruneReport>;
+
+    async fn pull_image(&self, image: &str, on_progress: Option<&PullProgressCallback>) -> Result<()>;
+
+    async fn snapshot_playground(&self, container_id: &str, tag: &str, comment: &str, workspace_dir: &Path) -> Result<CommittedSnapshot>;
+
+    async fn remove_snapshot_image(&self, image: &str) -> Result<()>;
+}
+
+pub struct RuntimeSelection {
+    pub runtime: Arc<dyn ContainerRuntime>,
+    pub status: ContainerRuntimeStatus,
+}
+
+pub async fn select_container_runtime(preference: RuntimePreference) -> RuntimeSelection {
+    let mut failures = Vec::new();
+
+    for kind in preference.candidates() {
+        let connected: Result<Arc<dyn ContainerRuntime>> = match kind {
+            RuntimeKind::Docker => DockerService::new()
+                .await
+                .map(|service| Arc::new(service) as Arc<dyn ContainerRuntime>),
+            RuntimeKind::Podman => PodmanRuntime::new()
+                .await
+                .map(|runtime| Arc::new(runtime) as Arc<dyn ContainerRuntime>),
+            RuntimeKind::Disabled => continue,
+        };
+
+        match connected {
+            Ok(runtime) => {
+                return RuntimeSelection {
+                    runtime,
+                    status: ContainerRuntimeStatus {
+                        active_runtime: *kind,
+                        available: true,
+                        preference,
+                        failures,
+                    },
+                };
+            }
+            Err(e) => failures.push(RuntimeProbeFailure {
+                runtime: *kind,
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    let reason = if preference == RuntimePreference::Disabled {
+        format!("disabled via {}", RUNTIME_ENV_VAR)
+    } else {
+        failures
+            .iter()
+            .map(|f| format!("{}: {}", f.runtime.as_str(), f.reason))
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    RuntimeSelection {
+        runtime: Arc::new(DisabledRuntime::new(reason)),
+        status: ContainerRuntimeStatus {
+            active_runtime: RuntimeKind::Disabled,
+            available: false,
+            preference,
+            failures,
+        },
+    }
+}
+
+// Rootless Podman exposes a Docker-compatible API socket, so the Docker
+// implementation is reused against that socket instead of /var/run/docker.sock
+pub struct PodmanRuntime {
+    inner: DockerService,
+}
+
+impl PodmanRuntime {
+    pub async fn new() -> Result<Self> {
+        let socket_path = Self::find_socket()
+            .ok_or_else(|| anyhow!("No Podman API socket found (is `podman system service` running?)"))?;
+
+        let docker = Docker::connect_with_socket(
+            &socket_path.to_string_lossy(),
+            PODMAN_CONNECT_TIMEOUT_SECS,
+            API_DEFAULT_VERSION,
+        )?;
+
+        let inner = DockerService::with_client(docker).await?;
+
+        Ok(Self { inner })
+    }
+
+    fn find_socket() -> Option<PathBuf> {
+        Self::socket_candidates().into_iter().find(|path| path.exists())
+    }
+
+    #[cfg(unix)]
+    fn socket_candidates() -> Vec<PathBuf> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut candidates = Vec::new();
+
+        if let Ok(host) = std::env::var("CONTAINER_HOST") {
+            if let Some(path) = host.strip_prefix("unix://") {
+                candidates.push(PathBuf::from(path));
+            }
+        }
+
+        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+            candidates.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+        }
+
+        if let Ok(metadata) = std::fs::metadata("/proc/self") {
+            candidates.push(PathBuf::from(format!("/run/user/{}/podman/podman.sock", metadata.uid())));
+        }
+
+        // Podman machine on macOS
+        if let Ok(home) = std::env::var("HOME") {
+            let machine_dir = PathBuf::from(home).join(".local/share/containers/podman/machine");
+            candidates.push(machine_dir.join("podman.sock"));
+            candidates.push(machine_dir.join("qemu/podman.sock"));
+            candidates.push(machine_dir.join("applehv/podman.sock"));
+        }
+
+        candidates.push(PathBuf::from("/run/podman/podman.sock"));
+
+        candidates
+    }
+
+    #[cfg(windows)]
+    fn socket_candidates() -> Vec<PathBuf> {
+        vec![PathBuf::from(r"\\.\pipe\podman-machine-default")]
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for PodmanRuntime {
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Podman
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.inner.ping().await
+    }
+
+    async fn start_playground(&self, project_path: &Path, tech_stack: &[TechnologyStack], options: &PlaygroundOptions) -> Result<PlaygroundInfo> {
+        self.inner.start_playground(project_path, tech_stack, options).await
+    }
+
+    async fn stop_playground(&self, container_id: &str) -> Result<()> {
+        self.inner.stop_playground(container_id).await
+    }
+
+    async fn get_playground_status(&self, container_id: &str) -> Result<PlaygroundStatus> {
+        self.inner.get_playground_status(container_id).await
+    }
+
+    async fn get_resource_usage(&self, container_id: &str) -> Result<ResourceUsage> {
+        self.inner.get_resource_usage(container_id).await
+    }
+
+    async fn list_active_playgrounds(&self) -> Result<Vec<ContainerSummary>> {
+        self.inner.list_active_playgrounds().await
+    }
+
+    async fn cleanup_old_containers(&self, max_age_hours: u64) -> Result<usize> {
+        self.inner.cleanup_old_containers(max_age_hours).await
+    }
+
+    async fn get_cache_usage(&self) -> Result<Vec<CacheVolumeUsage>> {
+        self.inner.get_cache_usage().await
+    }
+
+    async fn prune_caches(&self, caches: &[DependencyCache]) -> Result<CachePruneReport> {
+        self.inner.prune_caches(caches).await
+    }
+
+    async fn pull_image(&self, image: &str, on_progress: Option<&PullProgressCallback>) -> Result<()> {
+        self.inner.pull_image(image, on_progress).await
+    }
+
+    async fn snapshot_playground(&self, container_id: &str, tag: &str, comment: &str, workspace_dir: &Path) -> Result<CommittedSnapshot> {
+        self.inner.snapshot_playground(container_id, tag, comment, workspace_dir).await
+    }
+
+    async fn remove_snapshot_image(&self, image: &str) -> Result<()> {
+        self.inner.remove_snapshot_image(image).await
+    }
+}
+
+// Used when no runtime could be reached; every operation fails with
+// ContainerRuntimeError::Unavailable so callers can tell it apart from real errors
+pub struct DisabledRuntime {
+    reason: String,
+}
+
+impl DisabledRuntime {
+    pub fn new(reason: String) -> Self {
+        Self { reason }
+    }
+
+    fn unavailable<T>(&self) -> Result<T> {
+        Err(ContainerRuntimeError::Unavailable { reason: self.reason.clone() }.into())
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for DisabledRuntime {
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Disabled
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.unavailable()
+    }
+
+    async fn start_playground(&self, _project_path: &Path, _tech_stack: &[TechnologyStack], _options: &PlaygroundOptions) -> Result<PlaygroundInfo> {
+        self.unavailable()
+    }
+
+    async fn stop_playground(&self, _container_id: &str) -> Result<()> {
+        self.unavailable()
+    }
+
+    async fn get_playground_status(&self, _container_id: &str) -> Result<PlaygroundStatus> {
+        self.unavailable()
+    }
+
+    async fn get_resource_usage(&self, _container_id: &str) -> Result<ResourceUsage> {
+        self.unavailable()
+    }
+
+    async fn list_active_playgrounds(&self) -> Result<Vec<ContainerSummary>> {
+        self.unavailable()
+    }
+
+    async fn cleanup_old_containers(&self, _max_age_hours: u64) -> Result<usize> {
+        self.unavailable()
+    }
+
+    async fn get_cache_usage(&self) -> Result<Vec<CacheVolumeUsage>> {
+        self.unavailable()
+    }
+
+    async fn prune_caches(&self, _caches: &[DependencyCache]) -> Result<CachePruneReport> {
+        self.unavailable()
+    }
+
+    async fn pull_image(&self, _image: &str, _on_progress: Option<&PullProgressCallback>) -> Result<()> {
+        self.unavailable()
+    }
+
+    async fn snapshot_playground(&self, _container_id: &str, _tag: &str, _comment: &str, _workspace_dir: &Path) -> Result<CommittedSnapshot> {
+        self.unavailable()
+    }
+
+    async fn remove_snapshot_image(&self, _image: &str) -> Result<()> {
+        self.unavailable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_tries_docker_before_podman() {
+        assert_eq!(RuntimePreference::Auto.candidates(), &[RuntimeKind::Docker, RuntimeKind::Podman]);
+        assert_eq!(RuntimePreference::Podman.candidates(), &[RuntimeKind::Podman]);
+        assert!(RuntimePreference::Disabled.candidates().is_empty());
+    }
+
+    #[tokio::test]
+    async fn disabled_preference_selects_disabled_runtime_without_probing() {
+        let selection = select_container_runtime(RuntimePreference::Disabled).await;
+
+        assert_eq!(selection.runtime.kind(), RuntimeKind::Disabled);
+        assert_eq!(selection.status.active_runtime, RuntimeKind::Disabled);
+        assert!(!selection.status.available);
+        assert!(selection.status.failures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn disabled_runtime_fails_with_unavailable_error() {
+        let runtime = DisabledRuntime::new("no socket".to_string());
+
+        let error = runtime.ping().await.unwrap_err();
+        match error.downcast_ref::<ContainerRuntimeError>() {
+            Some(ContainerRuntimeError::Unavailable { reason }) => assert_eq!(reason, "no socket"),
+            None => panic!("expected ContainerRuntimeError, got {}", error),
+        }
+        assert!(runtime.stop_playground("abc").await.is_err());
+    }
+}
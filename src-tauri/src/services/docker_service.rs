@@ -1,22 +1,28 @@
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 use bollard::{
     Docker,
     container::{
         Config, CreateContainerOptions, StartContainerOptions, StopContainerOptions,
         RemoveContainerOptions, ListContainersOptions, WaitContainerOptions,
     },
-    image::{CreateImageOptions, ListImagesOptions},
+    image::{CommitContainerOptions, CreateImageOptions, ListImagesOptions, RemoveImageOptions},
     models::{ContainerSummary, HostConfig, PortBinding, ExposedPorts},
     network::{CreateNetworkOptions},
-    volume::{CreateVolumeOptions},
+    volume::{CreateVolumeOptions, RemoveVolumeOptions},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use crate::database::models::{TechnologyStack, CreatePlaygroundSession, PlaygroundSession};
+use crate::services::container_runtime::{ContainerRuntime, RuntimeKind};
+use crate::services::image_catalog::{ImageCatalog, ImagePullProgress, ImageStack, PullProgressCallback};
 use futures::stream::TryStreamExt;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+const SNAPSHOT_REPOSITORY: &str = "r3viewer-snapshot";
+const ENVIRONMENT_LABEL: &str = "r3viewer.environment";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaygroundInfo {
     pub container_id: String,
@@ -53,6 +59,111 @@ pub struct EnvironmentConfig {
     pub start_command: String,
     pub health_check_path: String,
     pub working_dir: String,
+    pub dependency_caches: Vec<DependencyCache>,
+}
+
+// Named volumes shared by every playground of the same stack so that
+// package downloads survive between containers. Build output is deliberately
+// not cached: one student's artifacts must never end up in another's run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DependencyCache {
+    #[serde(rename = "npm")]
+    Npm,
+    #[serde(rename = "pip")]
+    Pip,
+    #[serde(rename = "maven")]
+    Maven,
+    #[serde(rename = "cargo-registry")]
+    CargoRegistry,
+    #[serde(rename = "cargo-git")]
+    CargoGit,
+    #[serde(rename = "go-mod")]
+    GoMod,
+}
+
+impl DependencyCache {
+    pub fn all() -> &'static [DependencyCache] {
+        &[
+            DependencyCache::Npm,
+            DependencyCache::Pip,
+            DependencyCache::Maven,
+            DependencyCache::CargoRegistry,
+            DependencyCache::CargoGit,
+            DependencyCache::GoMod,
+        ]
+    }
+
+    pub fn volume_name(&self) -> &'static str {
+        match self {
+            DependencyCache::Npm => "r3viewer-cache-npm",
+            DependencyCache::Pip => "r3viewer-cache-pip",
+            DependencyCache::Maven => "r3viewer-cache-maven",
+            DependencyCache::CargoRegistry => "r3viewer-cache-cargo-registry",
+            DependencyCache::CargoGit => "r3viewer-cache-cargo-git",
+            DependencyCache::GoMod => "r3viewer-cache-go-mod",
+        }
+    }
+
+    pub fn mount_path(&self) -> &'static str {
+        match self {
+            DependencyCache::Npm => "/root/.npm",
+            DependencyCache::Pip => "/root/.cache/pip",
+            DependencyCache::Maven => "/root/.m2",
+            DependencyCache::CargoRegistry => "/usr/local/cargo/registry",
+            DependencyCache::CargoGit => "/usr/local/cargo/git",
+            DependencyCache::GoMod => "/go/pkg/mod",
+        }
+    }
+
+    // Point the package manager at the mounted path explicitly in case the image
+    // overrides the default location
+    pub fn env(&self) -> String {
+        match self {
+            DependencyCache::Npm => format!("npm_config_cache={}", self.mount_path()),
+            DependencyCache::Pip => format!("PIP_CACHE_DIR={}", self.mount_path()),
+            DependencyCache::Maven => format!("MAVEN_OPTS=-Dmaven.repo.local={}/repository", self.mount_path()),
+            DependencyCache::CargoRegistry | DependencyCache::CargoGit => "CARGO_HOME=/usr/local/cargo".to_string(),
+            DependencyCache::GoMod => format!("GOMODCACHE={}", self.mount_path()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheVolumeUsage {
+    pub cache: DependencyCache,
+    pub volume_name: String,
+    pub exists: bool,
+    pub size_bytes: Option<u64>,
+    pub in_use_by: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachePruneReport {
+    pub removed: Vec<DependencyCache>,
+    pub skipped: Vec<String>,
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct PlaygroundOptions {
+    pub image_catalog: ImageCatalog,
+    pub assignment_id: Option<i64>,
+    pub on_pull_progress: Option<PullProgressCallback>,
+    pub restore_from: Option<SnapshotRestore>,
+}
+
+// A committed playground container plus an archived copy of its bind-mounted workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommittedSnapshot {
+    pub image: String,
+    pub environment: EnvironmentConfig,
+    pub size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotRestore {
+    pub image: String,
+    pub environment: EnvironmentConfig,
 }
 
 pub struct DockerService {
@@ -63,7 +174,11 @@ pub struct DockerService {
 impl DockerService {
     pub async fn new() -> Result<Self> {
         let docker = Docker::connect_with_local_defaults()?;
-        
+        Self::with_client(docker).await
+    }
+
+    // Build the service on top of an existing client, e.g. one pointed at a Podman socket
+    pub async fn with_client(docker: Docker) -> Result<Self> {
         // Test Docker connection
         docker.ping().await?;
         
@@ -78,23 +193,36 @@ impl DockerService {
         Ok(service)
     }
 
+    pub async fn ping(&self) -> Result<()> {
+        self.docker.ping().await?;
+        Ok(())
+    }
+
     async fn initialize(&self) -> Result<()> {
         // Create network if it doesn't exist
         self.ensure_network_exists().await?;
         
-        // Pull base images
-        self.pull_base_images().await?;
-        
+        // Base images are pulled lazily per playground, or ahead of time via pull_image
         Ok(())
     }
 
-    pub async fn start_playground(&self, project_path: &Path, tech_stack: &[TechnologyStack]) -> Result<PlaygroundInfo> {
+    pub async fn start_playground(&self, project_path: &Path, tech_stack: &[TechnologyStack], options: &PlaygroundOptions) -> Result<PlaygroundInfo> {
         let project_name = project_path.file_name()
             .and_then(|name| name.to_str())
             .ok_or_else(|| anyhow!("Invalid project path"))?;
 
-        // Detect environment configuration
-        let env_config = self.detect_environment_config(project_path, tech_stack).await?;
+        // Detect environment configuration, or reuse the one captured with a snapshot
+        let env_config = match &options.restore_from {
+            Some(restore) => EnvironmentConfig {
+                image: restore.image.clone(),
+                setup_commands: vec![], // Already applied before the snapshot was taken
+                ..restore.environment.clone()
+            },
+            None => self.detect_environment_config(project_path, tech_stack, options).await?,
+        };
+
+        // Pull the stack image on first use
+        self.ensure_image(&env_config.image, options.on_pull_progress.as_ref()).await?;
         
         // Find available port
         let port = self.find_available_port().await?;
@@ -246,46 +374,200 @@ impl DockerService {
         Ok(cleaned_count)
     }
 
-    async fn detect_environment_config(&self, project_path: &Path, tech_stack: &[TechnologyStack]) -> Result<EnvironmentConfig> {
+    pub async fn get_cache_usage(&self) -> Result<Vec<CacheVolumeUsage>> {
+        // `system df` is the only endpoint that reports volume sizes
+        let usage = self.docker.df().await?;
+        let volumes = usage.volumes.unwrap_or_default();
+
+        let report = DependencyCache::all()
+            .iter()
+            .map(|cache| {
+                let volume = volumes.iter().find(|v| v.name == cache.volume_name());
+                let usage_data = volume.and_then(|v| v.usage_data.as_ref());
+
+                CacheVolumeUsage {
+                    cache: *cache,
+                    volume_name: cache.volume_name().to_string(),
+                    exists: volume.is_some(),
+                    // Docker reports -1 when the size has not been computed
+                    size_bytes: usage_data.and_then(|d| u64::try_from(d.size).ok()),
+                    in_use_by: usage_data.and_then(|d| u64::try_from(d.ref_count).ok()),
+                }
+            })
+            .collect();
+
+        Ok(report)
+    }
+
+    pub async fn prune_caches(&self, caches: &[DependencyCache]) -> Result<CachePruneReport> {
+        let targets: Vec<DependencyCache> = if caches.is_empty() {
+            DependencyCache::all().to_vec()
+        } else {
+            caches.to_vec()
+        };
+
+        let sizes: HashMap<DependencyCache, u64> = self.get_cache_usage()
+            .await
+            .map(|usage| {
+                usage
+                    .into_iter()
+                    .filter_map(|u| u.size_bytes.map(|size| (u.cache, size)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut removed = Vec::new();
+        let mut skipped = Vec::new();
+        let mut reclaimed_bytes = 0;
+
+        for cache in targets {
+            if self.docker.inspect_volume(cache.volume_name()).await.is_err() {
+                continue;
+            }
+
+            // Without force the daemon refuses to remove volumes still mounted by a playground
+            match self.docker
+                .remove_volume(cache.volume_name(), Some(RemoveVolumeOptions { force: false }))
+                .await
+            {
+                Ok(()) => {
+                    reclaimed_bytes += sizes.get(&cache).copied().unwrap_or(0);
+                    removed.push(cache);
+                }
+                Err(e) => skipped.push(format!("{}: {}", cache.volume_name(), e)),
+            }
+        }
+
+        Ok(CachePruneReport {
+            removed,
+            skipped,
+            reclaimed_bytes,
+        })
+    }
+
+    pub async fn snapshot_playground(&self, container_id: &str, tag: &str, comment: &str, workspace_dir: &Path) -> Result<CommittedSnapshot> {
+        let container = self.docker.inspect_container(container_id, None).await?;
+
+        let environment: EnvironmentConfig = container.config
+            .as_ref()
+            .and_then(|config| config.labels.as_ref())
+            .and_then(|labels| labels.get(ENVIRONMENT_LABEL))
+            .and_then(|json| serde_json::from_str(json).ok())
+            .ok_or_else(|| anyhow!("Container {} is not an r3viewer playground", container_id))?;
+
+        // Bind-mounted project files are not part of a committed image, so archive them separately
+        let workspace_source = container.mounts
+            .unwrap_or_default()
+            .into_iter()
+            .find(|mount| mount.destination.as_deref() == Some(environment.working_dir.as_str()))
+            .and_then(|mount| mount.source)
+            .ok_or_else(|| anyhow!("Could not find the playground workspace mount"))?;
+
+        if let Err(e) = copy_workspace(Path::new(&workspace_source), workspace_dir) {
+            let _ = std::fs::remove_dir_all(workspace_dir);
+            return Err(e);
+        }
+
+        let mut labels = HashMap::new();
+        labels.insert("r3viewer.snapshot", "true");
+
+        let committed = self.docker
+            .commit_container(
+                CommitContainerOptions {
+                    container: container_id,
+                    repo: SNAPSHOT_REPOSITORY,
+                    tag,
+                    comment,
+                    pause: true,
+                    ..Default::default()
+                },
+                Config {
+                    labels: Some(labels),
+                    ..Default::default()
+                },
+            )
+            .await;
+        if let Err(e) = committed {
+            let _ = std::fs::remove_dir_all(workspace_dir);
+            return Err(e.into());
+        }
+
+        let image = format!("{}:{}", SNAPSHOT_REPOSITORY, tag);
+        let size_bytes = self.docker
+            .inspect_image(&image)
+            .await
+            .ok()
+            .and_then(|inspect| inspect.size)
+            .and_then(|size| u64::try_from(size).ok());
+
+        Ok(CommittedSnapshot {
+            image,
+            environment,
+            size_bytes,
+        })
+    }
+
+    pub async fn remove_snapshot_image(&self, image: &str) -> Result<()> {
+        self.docker
+            .remove_image(
+                image,
+                Some(RemoveImageOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn detect_environment_config(&self, project_path: &Path, tech_stack: &[TechnologyStack], options: &PlaygroundOptions) -> Result<EnvironmentConfig> {
         // Check for Dockerfile first
         let dockerfile_path = project_path.join("Dockerfile");
         if dockerfile_path.exists() {
             return self.create_custom_dockerfile_config(project_path).await;
         }
 
+        let image_for = |stack: ImageStack| {
+            options.image_catalog
+                .resolve(stack, project_path, options.assignment_id)
+                .reference
+        };
+
         // Use predefined configurations based on tech stack
         for stack in tech_stack {
-            match stack {
-                TechnologyStack::NodeJS | TechnologyStack::React | TechnologyStack::Vue | TechnologyStack::Angular => {
-                    return self.create_nodejs_config(project_path).await;
+            match ImageStack::from_technology(stack) {
+                ImageStack::Node => {
+                    return self.create_nodejs_config(project_path, image_for(ImageStack::Node)).await;
                 }
-                TechnologyStack::Python | TechnologyStack::Django | TechnologyStack::Flask => {
-                    return self.create_python_config(project_path).await;
+                ImageStack::Python => {
+                    return self.create_python_config(project_path, image_for(ImageStack::Python)).await;
                 }
-                TechnologyStack::Java | TechnologyStack::SpringBoot => {
-                    return self.create_java_config(project_path).await;
+                ImageStack::Java => {
+                    return self.create_java_config(project_path, image_for(ImageStack::Java)).await;
                 }
-                TechnologyStack::Rust => {
-                    return self.create_rust_config(project_path).await;
+                ImageStack::Rust => {
+                    return self.create_rust_config(project_path, image_for(ImageStack::Rust)).await;
                 }
-                TechnologyStack::Go => {
-                    return self.create_go_config(project_path).await;
+                ImageStack::Go => {
+                    return self.create_go_config(project_path, image_for(ImageStack::Go)).await;
                 }
-                TechnologyStack::PHP => {
-                    return self.create_php_config(project_path).await;
+                ImageStack::Php => {
+                    return self.create_php_config(project_path, image_for(ImageStack::Php)).await;
                 }
-                TechnologyStack::Ruby => {
-                    return self.create_ruby_config(project_path).await;
+                ImageStack::Ruby => {
+                    return self.create_ruby_config(project_path, image_for(ImageStack::Ruby)).await;
                 }
-                _ => continue,
+                ImageStack::Generic => continue,
             }
         }
 
         // Default to generic configuration
-        self.create_generic_config(project_path).await
+        self.create_generic_config(project_path, image_for(ImageStack::Generic)).await
     }
 
-    async fn create_nodejs_config(&self, project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_nodejs_config(&self, project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         let package_json_path = project_path.join("package.json");
         let mut setup_commands = vec![
             "npm install".to_string(),
@@ -315,17 +597,18 @@ impl DockerService {
         }
 
         Ok(EnvironmentConfig {
-            image: "node:18-alpine".to_string(),
+            image,
             dockerfile_content: None,
             port: 3000,
             setup_commands,
             start_command,
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![DependencyCache::Npm],
         })
     }
 
-    async fn create_python_config(&self, project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_python_config(&self, project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         let requirements_path = project_path.join("requirements.txt");
         let mut setup_commands = vec![];
 
@@ -347,17 +630,18 @@ impl DockerService {
         };
 
         Ok(EnvironmentConfig {
-            image: "python:3.11-slim".to_string(),
+            image,
             dockerfile_content: None,
             port: 8000,
             setup_commands,
             start_command,
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![DependencyCache::Pip],
         })
     }
 
-    async fn create_java_config(&self, project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_java_config(&self, project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         let mut setup_commands = vec![];
         let start_command = if project_path.join("pom.xml").exists() {
             // Maven project
@@ -372,61 +656,66 @@ impl DockerService {
         };
 
         Ok(EnvironmentConfig {
-            image: "openjdk:17-slim".to_string(),
+            image,
             dockerfile_content: None,
             port: 8080,
             setup_commands,
             start_command,
             health_check_path: "/actuator/health".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![DependencyCache::Maven],
         })
     }
 
-    async fn create_rust_config(&self, _project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_rust_config(&self, _project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         Ok(EnvironmentConfig {
-            image: "rust:1.70".to_string(),
+            image,
             dockerfile_content: None,
             port: 8000,
             setup_commands: vec!["cargo build --release".to_string()],
             start_command: "cargo run --release".to_string(),
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![DependencyCache::CargoRegistry, DependencyCache::CargoGit],
         })
     }
 
-    async fn create_go_config(&self, _project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_go_config(&self, _project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         Ok(EnvironmentConfig {
-            image: "golang:1.21-alpine".to_string(),
+            image,
             dockerfile_content: None,
             port: 8080,
             setup_commands: vec!["go mod download".to_string(), "go build -o main .".to_string()],
             start_command: "./main".to_string(),
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![DependencyCache::GoMod],
         })
     }
 
-    async fn create_php_config(&self, _project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_php_config(&self, _project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         Ok(EnvironmentConfig {
-            image: "php:8.2-apache".to_string(),
+            image,
             dockerfile_content: None,
             port: 80,
             setup_commands: vec!["composer install".to_string()],
             start_command: "apache2-foreground".to_string(),
             health_check_path: "/".to_string(),
             working_dir: "/var/www/html".to_string(),
+            dependency_caches: vec![],
         })
     }
 
-    async fn create_ruby_config(&self, _project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_ruby_config(&self, _project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         Ok(EnvironmentConfig {
-            image: "ruby:3.2".to_string(),
+            image,
             dockerfile_content: None,
             port: 3000,
             setup_commands: vec!["bundle install".to_string()],
             start_command: "rails server -b 0.0.0.0".to_string(),
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![],
         })
     }
 
@@ -441,18 +730,20 @@ impl DockerService {
             start_command: "".to_string(), // Will be defined in Dockerfile
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![],
         })
     }
 
-    async fn create_generic_config(&self, _project_path: &Path) -> Result<EnvironmentConfig> {
+    async fn create_generic_config(&self, _project_path: &Path, image: String) -> Result<EnvironmentConfig> {
         Ok(EnvironmentConfig {
-            image: "alpine:latest".to_string(),
+            image,
             dockerfile_content: None,
             port: 8080,
             setup_commands: vec![],
             start_command: "echo 'No start command configured'".to_string(),
             health_check_path: "/".to_string(),
             working_dir: "/app".to_string(),
+            dependency_caches: vec![],
         })
     }
 
@@ -471,18 +762,32 @@ impl DockerService {
         let mut exposed_ports = HashMap::new();
         exposed_ports.insert(format!("{}/tcp", config.port), HashMap::new());
 
+        let mut binds = vec![format!("{}:{}", project_path.display(), config.working_dir)];
+        let mut env = vec![
+            "NODE_ENV=development".to_string(),
+            "PORT=3000".to_string(),
+        ];
+
+        // Mount shared dependency caches for this stack
+        for cache in &config.dependency_caches {
+            self.ensure_cache_volume(*cache).await?;
+            binds.push(format!("{}:{}", cache.volume_name(), cache.mount_path()));
+            env.push(cache.env());
+        }
+
         let host_config = HostConfig {
             port_bindings: Some(port_bindings),
             memory: Some(1_073_741_824), // 1GB memory limit
             cpu_shares: Some(1024),
             network_mode: Some(self.network_name.clone()),
-            binds: Some(vec![format!("{}:{}", project_path.display(), config.working_dir)]),
+            binds: Some(binds),
             ..Default::default()
         };
 
         let mut labels = HashMap::new();
         labels.insert("r3viewer.playground".to_string(), "true".to_string());
         labels.insert("r3viewer.project".to_string(), project_name.to_string());
+        labels.insert(ENVIRONMENT_LABEL.to_string(), serde_json::to_string(config)?);
 
         let container_config = Config {
             image: Some(config.image.clone()),
@@ -490,10 +795,7 @@ impl DockerService {
             exposed_ports: Some(exposed_ports),
             host_config: Some(host_config),
             labels: Some(labels),
-            env: Some(vec![
-                "NODE_ENV=development".to_string(),
-                "PORT=3000".to_string(),
-            ]),
+            env: Some(env),
             ..Default::default()
         };
 
@@ -567,6 +869,25 @@ impl DockerService {
         Err(anyhow!("No available ports found"))
     }
 
+    async fn ensure_cache_volume(&self, cache: DependencyCache) -> Result<()> {
+        if self.docker.inspect_volume(cache.volume_name()).await.is_ok() {
+            return Ok(());
+        }
+
+        let mut labels = HashMap::new();
+        labels.insert("r3viewer.cache", "true");
+
+        self.docker
+            .create_volume(CreateVolumeOptions {
+                name: cache.volume_name(),
+                labels,
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(())
+    }
+
     async fn ensure_network_exists(&self) -> Result<()> {
         // Check if network exists
         let networks = self.docker.list_networks::<String>(None).await?;
@@ -588,30 +909,51 @@ impl DockerService {
         Ok(())
     }
 
-    async fn pull_base_images(&self) -> Result<()> {
-        let base_images = vec![
-            "node:18-alpine",
-            "python:3.11-slim",
-            "openjdk:17-slim",
-            "rust:1.70",
-            "golang:1.21-alpine",
-            "php:8.2-apache",
-            "ruby:3.2",
-            "alpine:latest",
-        ];
+    pub async fn ensure_image(&self, image: &str, on_progress: Option<&PullProgressCallback>) -> Result<()> {
+        // Dockerfile-based configs have no base image to pull
+        if image.is_empty() || self.docker.inspect_image(image).await.is_ok() {
+            return Ok(());
+        }
+
+        self.pull_image(image, on_progress).await
+    }
 
-        for image in base_images {
-            let _ = self.docker
-                .create_image(
-                    Some(CreateImageOptions {
-                        from_image: image,
-                        ..Default::default()
-                    }),
-                    None,
-                    None,
-                )
-                .try_collect::<Vec<_>>()
-                .await;
+    pub async fn pull_image(&self, image: &str, on_progress: Option<&PullProgressCallback>) -> Result<()> {
+        let mut stream = self.docker.create_image(
+            Some(CreateImageOptions {
+                from_image: image,
+                ..Default::default()
+            }),
+            None,
+            None,
+        );
+
+        while let Some(info) = stream.try_next().await? {
+            if let Some(error) = info.error {
+                return Err(anyhow!("Failed to pull image {}: {}", image, error));
+            }
+
+            if let Some(callback) = on_progress {
+                callback(ImagePullProgress {
+                    image: image.to_string(),
+                    layer_id: info.id,
+                    status: info.status.unwrap_or_default(),
+                    current: info.progress_detail.as_ref().and_then(|d| d.current),
+                    total: info.progress_detail.as_ref().and_then(|d| d.total),
+                    done: false,
+                });
+            }
+        }
+
+        if let Some(callback) = on_progress {
+            callback(ImagePullProgress {
+                image: image.to_string(),
+                layer_id: None,
+                status: "Pull complete".to_string(),
+                current: None,
+                total: None,
+                done: true,
+            });
         }
 
         Ok(())
@@ -630,4 +972,138 @@ impl DockerService {
         
         Ok(0.0)
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl ContainerRuntime for DockerService {
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Docker
+    }
+
+    async fn ping(&self) -> Result<()> {
+        DockerService::ping(self).await
+    }
+
+    async fn start_playground(&self, project_path: &Path, tech_stack: &[TechnologyStack], options: &PlaygroundOptions) -> Result<PlaygroundInfo> {
+        DockerService::start_playground(self, project_path, tech_stack, options).await
+    }
+
+    async fn stop_playground(&self, container_id: &str) -> Result<()> {
+        DockerService::stop_playground(self, container_id).await
+    }
+
+    async fn get_playground_status(&self, container_id: &str) -> Result<PlaygroundStatus> {
+        DockerService::get_playground_status(self, container_id).await
+    }
+
+    async fn get_resource_usage(&self, container_id: &str) -> Result<ResourceUsage> {
+        DockerService::get_resource_usage(self, container_id).await
+    }
+
+    async fn list_active_playgrounds(&self) -> Result<Vec<ContainerSummary>> {
+        DockerService::list_active_playgrounds(self).await
+    }
+
+    async fn cleanup_old_containers(&self, max_age_hours: u64) -> Result<usize> {
+        DockerService::cleanup_old_containers(self, max_age_hours).await
+    }
+
+    async fn get_cache_usage(&self) -> Result<Vec<CacheVolumeUsage>> {
+        DockerService::get_cache_usage(self).await
+    }
+
+    async fn prune_caches(&self, caches: &[DependencyCache]) -> Result<CachePruneReport> {
+        DockerService::prune_caches(self, caches).await
+    }
+
+    async fn pull_image(&self, image: &str, on_progress: Option<&PullProgressCallback>) -> Result<()> {
+        DockerService::pull_image(self, image, on_progress).await
+    }
+
+    async fn snapshot_playground(&self, container_id: &str, tag: &str, comment: &str, workspace_dir: &Path) -> Result<CommittedSnapshot> {
+        DockerService::snapshot_playground(self, container_id, tag, comment, workspace_dir).await
+    }
+
+    async fn remove_snapshot_image(&self, image: &str) -> Result<()> {
+        DockerService::remove_snapshot_image(self, image).await
+    }
+}
+
+// Unique per call: two snapshots taken within the same second must not share an image tag or workspace directory
+pub fn snapshot_tag(project_id: i64) -> String {
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!("project-{}-{}-{}", project_id, chrono::Utc::now().format("%Y%m%d%H%M%S"), &suffix[..8])
+}
+
+// Copy a playground workspace, preserving symlinks such as node_modules/.bin entries
+pub fn copy_workspace(source: &Path, target: &Path) -> Result<()> {
+    std::fs::create_dir_all(target)?;
+
+    for entry in walkdir::WalkDir::new(source).follow_links(false) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source)?;
+        let destination = target.join(relative);
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&destination)?;
+        } else if entry.file_type().is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &destination)?;
+        } else {
+            std::fs::copy(entry.path(), &destination)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn snapshot_tags_are_unique_within_a_second() {
+        let first = snapshot_tag(7);
+        let second = snapshot_tag(7);
+
+        assert!(first.starts_with("project-7-"));
+        assert_ne!(first, second);
+        assert!(first.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+    }
+
+    #[test]
+    fn copy_workspace_copies_nested_files() {
+        let source = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(source.path().join("src/lib")).unwrap();
+        std::fs::write(source.path().join("package.json"), "{}").unwrap();
+        std::fs::write(source.path().join("src/lib/index.js"), "export {}").unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let workspace = target.path().join("snapshot");
+        copy_workspace(source.path(), &workspace).unwrap();
+
+        assert_eq!(std::fs::read_to_string(workspace.join("package.json")).unwrap(), "{}");
+        assert_eq!(std::fs::read_to_string(workspace.join("src/lib/index.js")).unwrap(), "export {}");
+    }
+
+    #[test]
+    fn dependency_caches_use_distinct_prefixed_volumes() {
+        let names: HashSet<&str> = DependencyCache::all().iter().map(|cache| cache.volume_name()).collect();
+
+        assert_eq!(names.len(), DependencyCache::all().len());
+        assert!(names.iter().all(|name| name.starts_with("r3viewer-cache-")));
+    }
+
+    #[test]
+    fn dependency_cache_env_points_at_mount_path() {
+        for cache in DependencyCache::all() {
+            assert!(cache.mount_path().starts_with('/'));
+        }
+
+        assert_eq!(DependencyCache::Npm.env(), "npm_config_cache=/root/.npm");
+        assert_eq!(DependencyCache::Maven.env(), "MAVEN_OPTS=-Dmaven.repo.local=/root/.m2/repository");
+        assert_eq!(DependencyCache::CargoGit.env(), "CARGO_HOME=/usr/local/cargo");
+        assert!(DependencyCache::all().iter().all(|cache| !cache.env().starts_with("CARGO_TARGET_DIR=")));
+    }
+}
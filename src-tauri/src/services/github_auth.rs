@@ -0,0 +1,414 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+const GITHUB_DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
+const GITHUB_ACCESS_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const GITHUB_API_BASE: &str = "https://api.github.com";
+const DEVICE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+// Read student repositories and the signed-in user's profile
+const DEVICE_FLOW_SCOPES: &str = "repo read:user";
+// GitHub rejects app JWTs living longer than ten minutes; backdating covers clock drift
+const APP_JWT_LIFETIME_SECONDS: i64 = 540;
+const APP_JWT_BACKDATE_SECONDS: i64 = 60;
+// The most GitHub returns per page of a list endpoint
+const INSTALLATIONS_PER_PAGE: usize = 100;
+// Added to the polling interval when a slow_down answer does not carry the new one
+const SLOW_DOWN_INCREMENT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// What the user needs to finish a device login; the device code itself stays in the backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubDeviceLogin {
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubDeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+// Tokens from an app with expiring user tokens carry a refresh token and lifetimes; classic OAuth apps' do not
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GitHubUserToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_in: Option<i64>,
+}
+
+// Settings for authenticating as a GitHub App installation, e.g. on the organization behind a Classroom
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubAppConfig {
+    pub app_id: String,
+    pub installation_id: Option<i64>, // discovered when the app has exactly one installation
+    pub private_key: String, // PEM as downloaded from the app's settings
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubAppStatus {
+    pub configured: bool,
+    pub app_id: Option<String>,
+    pub installation_id: Option<i64>,
+    pub account: Option<String>, // organization or user the app is installed on
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitHubInstallation {
+    pub id: i64,
+    pub account: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct AppJwtClaims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+// Device authorization flow
+pub async fn request_device_code(http: &reqwest::Client, client_id: &str) -> Result<GitHubDeviceCode> {
+    let response = http
+        .post(GITHUB_DEVICE_CODE_URL)
+        .header("Accept", "application/json")
+        .form(&[("client_id", client_id), ("scope", DEVICE_FLOW_SCOPES)])
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        return Err(anyhow!("GitHub did not issue a device code: {}", detail));
+    }
+
+    let body: serde_json::Value = response.json().await?;
+    if let Some(error) = body["error"].as_str() {
+        return Err(anyhow!("GitHub did not issue a device code: {}", body["error_description"].as_str().unwrap_or(error)));
+    }
+
+    Ok(serde_json::from_value(body)?)
+}
+
+// Polls at the interval GitHub asks for until the user approves, declines, or the code expires. Returns None
+// as soon as `is_current` says a newer login replaced this one.
+pub async fn poll_device_token(
+    http: &reqwest::Client,
+    client_id: &str,
+    device: &GitHubDeviceCode,
+    is_current: impl Fn() -> bool,
+) -> Result<Option<GitHubUserToken>> {
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(device.expires_in);
+    let mut interval = std::time::Duration::from_secs(device.interval.max(1));
+
+    loop {
+        tokio::time::sleep(interval).await;
+        if !is_current() {
+            return Ok(None);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(anyhow!("The GitHub device code expired before it was approved"));
+        }
+
+        let body = token_request(http, &[
+            ("client_id", client_id),
+            ("device_code", device.device_code.as_str()),
+            ("grant_type", DEVICE_GRANT_TYPE),
+        ]).await?;
+
+        match device_poll_outcome(body, interval)? {
+            DevicePoll::Approved(token) => return Ok(Some(token)),
+            DevicePoll::Pending => {}
+            DevicePoll::SlowDown(slower) => interval = slower,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DevicePoll {
+    Approved(GitHubUserToken),
+    Pending,
+    SlowDown(std::time::Duration), // the interval to poll at from now on
+}
+
+// Reads one answer to a device token request; declined and expired codes end the login
+fn device_poll_outcome(body: serde_json::Value, interval: std::time::Duration) -> Result<DevicePoll> {
+    match body["error"].as_str() {
+        None => Ok(DevicePoll::Approved(serde_json::from_value(body)?)),
+        Some("authorization_pending") => Ok(DevicePoll::Pending),
+        // GitHub returns the new interval; add the documented five seconds if it does not
+        Some("slow_down") => Ok(DevicePoll::SlowDown(
+            body["interval"]
+                .as_u64()
+                .map(std::time::Duration::from_secs)
+                .unwrap_or(interval + SLOW_DOWN_INCREMENT),
+        )),
+        Some("expired_token") => Err(anyhow!("The GitHub device code expired before it was approved")),
+        Some("access_denied") => Err(anyhow!("GitHub sign-in was declined")),
+        Some(error) => Err(anyhow!("GitHub sign-in failed: {}", body["error_description"].as_str().unwrap_or(error))),
+    }
+}
+
+pub async fn refresh_user_token(
+    http: &reqwest::Client,
+    client_id: &str,
+    client_secret: Option<&str>,
+    refresh_token: &str,
+) -> Result<GitHubUserToken> {
+    let mut params = vec![
+        ("client_id", client_id),
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+    ];
+    if let Some(secret) = client_secret {
+        params.push(("client_secret", secret));
+    }
+
+    let body = token_request(http, &params).await?;
+    if let Some(error) = body["error"].as_str() {
+        return Err(anyhow!("Failed to refresh the GitHub token: {}", body["error_description"].as_str().unwrap_or(error)));
+    }
+
+    Ok(serde_json::from_value(body)?)
+}
+
+// GitHub answers token requests with 200 and an `error` field, so the body is returned for the caller to inspect
+async fn token_request(http: &reqwest::Client, params: &[(&str, &str)]) -> Result<serde_json::Value> {
+    let response = http
+        .post(GITHUB_ACCESS_TOKEN_URL)
+        .header("Accept", "application/json")
+        .form(params)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        return Err(anyhow!("GitHub token request failed: {}", detail));
+    }
+
+    Ok(response.json().await?)
+}
+
+// GitHub App authentication
+pub fn app_jwt(app_id: &str, private_key: &str) -> Result<String> {
+    let claims = app_jwt_claims(app_id, Utc::now().timestamp());
+    let key = jsonwebtoken::EncodingKey::from_rsa_pem(private_key.trim().as_bytes())
+        .map_err(|e| anyhow!("Invalid GitHub App private key: {}", e))?;
+
+    Ok(jsonwebtoken::encode(&jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256), &claims, &key)?)
+}
+
+fn app_jwt_claims(app_id: &str, now: i64) -> AppJwtClaims {
+    AppJwtClaims {
+        iat: now - APP_JWT_BACKDATE_SECONDS,
+        exp: now + APP_JWT_LIFETIME_SECONDS,
+        iss: app_id.trim().to_string(),
+    }
+}
+
+// Every installation, following the pages until a short one
+pub async fn list_installations(http: &reqwest::Client, jwt: &str) -> Result<Vec<GitHubInstallation>> {
+    let mut installations = Vec::new();
+
+    for page in 1.. {
+        let url = format!("{}/app/installations?per_page={}&page={}", GITHUB_API_BASE, INSTALLATIONS_PER_PAGE, page);
+        let body = app_request(http, reqwest::Method::GET, &url, jwt).await?;
+        let count = body.as_array().map_or(0, Vec::len);
+        installations.extend(parse_installations(&body));
+        if count < INSTALLATIONS_PER_PAGE {
+            break;
+        }
+    }
+
+    Ok(installations)
+}
+
+fn parse_installations(body: &serde_json::Value) -> Vec<GitHubInstallation> {
+    body.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|installation| {
+            Some(GitHubInstallation {
+                id: installation["id"].as_i64()?,
+                account: installation["account"]["login"].as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Without an installation ID the app's only installation is used; with several, the caller has to pick one
+pub fn select_installation(mut installations: Vec<GitHubInstallation>) -> Result<GitHubInstallation> {
+    match installations.len() {
+        0 => Err(anyhow!("The GitHub App is not installed on any account")),
+        1 => Ok(installations.remove(0)),
+        _ => {
+            let choices = installations
+                .iter()
+                .map(|installation| format!("{} ({})", installation.account, installation.id))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(anyhow!("The GitHub App has several installations; choose one of: {}", choices))
+        }
+    }
+}
+
+pub async fn get_installation(http: &reqwest::Client, jwt: &str, installation_id: i64) -> Result<GitHubInstallation> {
+    let body = app_request(
+        http,
+        reqwest::Method::GET,
+        &format!("{}/app/installations/{}", GITHUB_API_BASE, installation_id),
+        jwt,
+    ).await?;
+
+    Ok(GitHubInstallation {
+        id: installation_id,
+        account: body["account"]["login"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+// Installation tokens last an hour
+pub async fn create_installation_token(
+    http: &reqwest::Client,
+    jwt: &str,
+    installation_id: i64,
+) -> Result<(String, DateTime<Utc>)> {
+    let body = app_request(
+        http,
+        reqwest::Method::POST,
+        &format!("{}/app/installations/{}/access_tokens", GITHUB_API_BASE, installation_id),
+        jwt,
+    ).await?;
+
+    let token = body["token"]
+        .as_str()
+        .ok_or_else(|| anyhow!("GitHub did not return an installation token"))?
+        .to_string();
+    let expires_at = body["expires_at"]
+        .as_str()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|value| value.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc::now() + chrono::Duration::minutes(55));
+
+    Ok((token, expires_at))
+}
+
+async fn app_request(http: &reqwest::Client, method: reqwest::Method, url: &str, jwt: &str) -> Result<serde_json::Value> {
+    let response = http
+        .request(method, url)
+        .bearer_auth(jwt)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(anyhow!("GitHub rejected the app credentials; check the app ID and private key"));
+    }
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(anyhow!("GitHub App installation not found"));
+    }
+    if !status.is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        return Err(anyhow!("GitHub App request failed: {} {}", status, detail));
+    }
+
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(expires_in: u64) -> GitHubDeviceCode {
+        GitHubDeviceCode {
+            device_code: "device".to_string(),
+            user_code: "ABCD-1234".to_string(),
+            verification_uri: "https://github.com/login/device".to_string(),
+            expires_in,
+            interval: 5,
+        }
+    }
+
+    fn installation(id: i64, account: &str) -> GitHubInstallation {
+        GitHubInstallation { id, account: account.to_string() }
+    }
+
+    #[test]
+    fn app_jwt_claims_are_backdated_and_short_lived() {
+        let now = 1_700_000_000;
+        let claims = app_jwt_claims(" 12345\n", now);
+
+        assert_eq!(claims.iss, "12345");
+        assert!(claims.iat < now);
+        assert!(claims.exp > now);
+        assert!(claims.exp - claims.iat <= 600);
+    }
+
+    #[test]
+    fn device_poll_answers_keep_waiting_or_slow_down() {
+        let interval = std::time::Duration::from_secs(5);
+
+        let pending = device_poll_outcome(serde_json::json!({ "error": "authorization_pending" }), interval).unwrap();
+        assert_eq!(pending, DevicePoll::Pending);
+
+        let given = device_poll_outcome(serde_json::json!({ "error": "slow_down", "interval": 12 }), interval).unwrap();
+        assert_eq!(given, DevicePoll::SlowDown(std::time::Duration::from_secs(12)));
+
+        let missing = device_poll_outcome(serde_json::json!({ "error": "slow_down" }), interval).unwrap();
+        assert_eq!(missing, DevicePoll::SlowDown(std::time::Duration::from_secs(10)));
+
+        let approved = device_poll_outcome(
+            serde_json::json!({ "access_token": "gho_token", "refresh_token": "ghr_token", "expires_in": 28800 }),
+            interval,
+        )
+        .unwrap();
+        assert_eq!(approved, DevicePoll::Approved(GitHubUserToken {
+            access_token: "gho_token".to_string(),
+            refresh_token: Some("ghr_token".to_string()),
+            expires_in: Some(28800),
+        }));
+    }
+
+    #[test]
+    fn device_poll_answers_that_end_the_login() {
+        let interval = std::time::Duration::from_secs(5);
+        let error = |body: serde_json::Value| device_poll_outcome(body, interval).unwrap_err().to_string();
+
+        assert!(error(serde_json::json!({ "error": "expired_token" })).contains("expired"));
+        assert!(error(serde_json::json!({ "error": "access_denied" })).contains("declined"));
+        assert!(error(serde_json::json!({ "error": "incorrect_client_credentials", "error_description": "Bad client" })).contains("Bad client"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn polling_stops_without_a_request_once_superseded_or_expired() {
+        let http = reqwest::Client::new();
+
+        let superseded = poll_device_token(&http, "client", &device(900), || false).await.unwrap();
+        assert!(superseded.is_none());
+
+        let expired = poll_device_token(&http, "client", &device(0), || true).await.unwrap_err();
+        assert!(expired.to_string().contains("expired"));
+    }
+
+    #[test]
+    fn the_only_installation_is_picked_and_several_need_a_choice() {
+        assert!(select_installation(Vec::new()).is_err());
+        assert_eq!(select_installation(vec![installation(7, "classroom-org")]).unwrap(), installation(7, "classroom-org"));
+
+        let several = select_installation(vec![installation(7, "classroom-org"), installation(9, "teacher")]).unwrap_err();
+        assert!(several.to_string().contains("classroom-org (7), teacher (9)"));
+    }
+
+    #[test]
+    fn installations_without_an_id_or_account_are_skipped() {
+        let body = serde_json::json!([
+            { "id": 7, "account": { "login": "classroom-org" } },
+            { "id": 8, "account": null },
+            { "account": { "login": "teacher" } },
+        ]);
+
+        assert_eq!(parse_installations(&body), vec![installation(7, "classroom-org")]);
+    }
+}
@@ -1,11 +1,13 @@
 use anyhow::{Result, anyhow};
-use octocrab::{Octocrab, models::Repository};
+use base64::Engine;
 use git2::Repository as GitRepository;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::fs;
-use crate::services::AuthService;
-use crate::database::models::{TechnologyStack, CreateStudent, CreateProject};
+use crate::services::{ApiClient, ApiProvider};
+use crate::database::models::{TechnologyStack, CreateStudent, CreateProject, Student};
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RepositoryInfo {
@@ -61,74 +63,89 @@ pub enum PackageFileType {
     Unknown,
 }
 
-pub struct GitHubService {
-    client: Option<Octocrab>,
-    auth_service: AuthService,
+// Commit authorship aggregated per git author email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorContribution {
+    pub author_name: String,
+    pub author_email: String,
+    pub student_id: Option<i64>,
+    pub commits: i64,
+    pub lines_added: i64,
+    pub lines_deleted: i64,
+    pub files_touched: i64,
+    pub share: f64,
+    pub first_commit_at: Option<DateTime<Utc>>,
+    pub last_commit_at: Option<DateTime<Utc>>,
 }
 
-impl GitHubService {
-    pub fn new(auth_service: AuthService) -> Self {
-        Self {
-            client: None,
-            auth_service,
-        }
-    }
+// Lockfiles and build output would otherwise dominate line counts
+const IGNORED_CONTRIBUTION_PATHS: &[&str] = &[
+    "node_modules/", "dist/", "build/", "target/", "vendor/", ".venv/", "venv/", "__pycache__/",
+];
+const IGNORED_CONTRIBUTION_FILES: &[&str] = &[
+    "package-lock.json", "yarn.lock", "pnpm-lock.yaml", "Cargo.lock", "poetry.lock",
+    "Pipfile.lock", "composer.lock", "Gemfile.lock", "go.sum",
+];
 
-    pub async fn initialize(&mut self) -> Result<()> {
-        let credentials = self.auth_service.get_stored_credentials()?;
-        
-        if let Some(token) = credentials.github_token {
-            let octocrab = Octocrab::builder()
-                .personal_token(token)
-                .build()?;
-            self.client = Some(octocrab);
-        }
+// Where a line range ended up after following a file between two commits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineAnchor {
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+}
 
-        Ok(())
+const GITHUB_API_BASE: &str = "https://api.github.com";
+
+// Tokens are read per request through ApiClient, so signing in after startup takes effect immediately
+#[derive(Clone)]
+pub struct GitHubService {
+    api: ApiClient,
+}
+
+impl GitHubService {
+    pub fn new(api: ApiClient) -> Self {
+        Self { api }
     }
 
     pub async fn get_repository_info(&self, repo_url: &str) -> Result<RepositoryInfo> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("GitHub client not initialized"))?;
-
         let (owner, repo_name) = self.parse_github_url(repo_url)?;
         
-        let repo = client
-            .repos(&owner, &repo_name)
-            .get()
+        let repo = self.api
+            .json(ApiProvider::GitHub, reqwest::Method::GET, &format!("{}/repos/{}/{}", GITHUB_API_BASE, owner, repo_name), None)
             .await?;
+        let text = |field: &str| repo[field].as_str().map(str::to_string);
 
         let technology_stack = self.detect_technology_stack(&owner, &repo_name).await?;
         let readme_content = self.get_readme_content(&owner, &repo_name).await.ok();
 
         Ok(RepositoryInfo {
-            name: repo.name,
-            description: repo.description,
-            url: repo.html_url.map(|u| u.to_string()).unwrap_or_default(),
-            clone_url: repo.clone_url.unwrap_or_default(),
-            default_branch: repo.default_branch.unwrap_or_else(|| "main".to_string()),
+            name: text("name").unwrap_or(repo_name.clone()),
+            description: text("description"),
+            url: text("html_url").unwrap_or_default(),
+            clone_url: text("clone_url").unwrap_or_default(),
+            default_branch: text("default_branch").unwrap_or_else(|| "main".to_string()),
             technology_stack,
             readme_content,
             has_dockerfile: self.check_file_exists(&owner, &repo_name, "Dockerfile").await.unwrap_or(false),
             has_tests: self.detect_test_files(&owner, &repo_name).await.unwrap_or(false),
-            language: repo.language,
-            size: repo.size.unwrap_or(0),
-            created_at: repo.created_at.map(|d| d.to_string()).unwrap_or_default(),
-            updated_at: repo.updated_at.map(|d| d.to_string()).unwrap_or_default(),
+            language: text("language"),
+            size: repo["size"].as_u64().unwrap_or(0) as u32,
+            created_at: text("created_at").unwrap_or_default(),
+            updated_at: text("updated_at").unwrap_or_default(),
         })
     }
 
     pub async fn clone_repository(&self, repo_url: &str, target_dir: &Path) -> Result<PathBuf> {
-        let credentials = self.auth_service.get_stored_credentials()?;
-        let token = credentials.github_token
-            .ok_or_else(|| anyhow!("No GitHub token available"))?;
+        let token = self.api.auth_service().access_token(ApiProvider::GitHub, false).await?;
 
         // Create target directory if it doesn't exist
         fs::create_dir_all(target_dir)?;
 
         // Prepare authenticated clone URL
         let auth_url = if repo_url.starts_with("https://github.com/") {
-            repo_url.replace("https://github.com/", &format!("https://{}@github.com/", token))
+            // x-access-token works for personal and app-issued tokens alike
+            repo_url.replace("https://github.com/", &format!("https://x-access-token:{}@github.com/", token))
         } else {
             repo_url.to_string()
         };
@@ -283,9 +300,6 @@ impl GitHubService {
     }
 
     async fn detect_technology_stack(&self, owner: &str, repo: &str) -> Result<Vec<TechnologyStack>> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("GitHub client not initialized"))?;
-
         let mut stacks = Vec::new();
 
         // Check for common package files
@@ -356,27 +370,27 @@ impl GitHubService {
         Ok(stacks)
     }
 
+    fn contents_url(&self, owner: &str, repo: &str, file_path: &str) -> String {
+        format!("{}/repos/{}/{}/contents/{}", GITHUB_API_BASE, owner, repo, file_path.trim_start_matches('/'))
+    }
+
     async fn check_file_exists(&self, owner: &str, repo: &str, file_path: &str) -> Result<bool> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("GitHub client not initialized"))?;
+        let response = self.api
+            .send(ApiProvider::GitHub, reqwest::Method::GET, &self.contents_url(owner, repo, file_path), None)
+            .await?;
 
-        match client.repos(owner, repo).get_content().path(file_path).send().await {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
-        }
+        Ok(response.status().is_success())
     }
 
     async fn get_file_content(&self, owner: &str, repo: &str, file_path: &str) -> Result<String> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("GitHub client not initialized"))?;
-
-        let content = client.repos(owner, repo).get_content().path(file_path).send().await?;
+        let content = self.api
+            .json(ApiProvider::GitHub, reqwest::Method::GET, &self.contents_url(owner, repo, file_path), None)
+            .await?;
         
-        if let Some(file) = content.items.first() {
-            if let Some(content_str) = &file.content {
-                let decoded = base64::decode(content_str.replace('\n', ""))?;
-                return Ok(String::from_utf8(decoded)?);
-            }
+        // Directories come back as arrays and have no content
+        if let Some(content_str) = content["content"].as_str() {
+            let decoded = base64::engine::general_purpose::STANDARD.decode(content_str.replace('\n', ""))?;
+            return Ok(String::from_utf8(decoded)?);
         }
 
         Err(anyhow!("File content not found"))
@@ -477,9 +491,445 @@ impl GitHubService {
         Ok(dependencies)
     }
 
+    pub fn collect_contributions(&self, repo_path: &Path) -> Result<Vec<AuthorContribution>> {
+        let repo = GitRepository::open(repo_path)
+            .map_err(|e| anyhow!("Failed to open repository: {}", e))?;
+
+        let mut revwalk = repo.revwalk()?;
+        if revwalk.push_head().is_err() {
+            // Empty repository, nothing committed yet
+            return Ok(Vec::new());
+        }
+
+        let mut by_email: HashMap<String, AuthorContribution> = HashMap::new();
+        let mut files_by_email: HashMap<String, HashSet<String>> = HashMap::new();
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+
+            // Merge commits repeat changes already attributed to their authors
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let tree = commit.tree()?;
+            let parent_tree = if commit.parent_count() == 1 {
+                Some(commit.parent(0)?.tree()?)
+            } else {
+                None
+            };
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+            let author = commit.author();
+            let email = author.email().unwrap_or("").trim().to_lowercase();
+            let name = author.name().unwrap_or("unknown").trim().to_string();
+            let committed_at = Utc.timestamp_opt(commit.time().seconds(), 0).single();
+
+            let entry = by_email.entry(email.clone()).or_insert_with(|| AuthorContribution {
+                author_name: name,
+                author_email: email.clone(),
+                student_id: None,
+                commits: 0,
+                lines_added: 0,
+                lines_deleted: 0,
+                files_touched: 0,
+                share: 0.0,
+                first_commit_at: None,
+                last_commit_at: None,
+            });
+
+            entry.commits += 1;
+            if let Some(committed_at) = committed_at {
+                entry.first_commit_at = Some(entry.first_commit_at.map_or(committed_at, |t| t.min(committed_at)));
+                entry.last_commit_at = Some(entry.last_commit_at.map_or(committed_at, |t| t.max(committed_at)));
+            }
+
+            let files = files_by_email.entry(email).or_default();
+            for index in 0..diff.deltas().len() {
+                let delta = match diff.get_delta(index) {
+                    Some(delta) => delta,
+                    None => continue,
+                };
+                let path = match delta.new_file().path().or_else(|| delta.old_file().path()) {
+                    Some(path) => path.to_string_lossy().replace('\\', "/"),
+                    None => continue,
+                };
+                if self.is_ignored_contribution_path(&path) {
+                    continue;
+                }
+
+                if let Ok(Some(patch)) = git2::Patch::from_diff(&diff, index) {
+                    if let Ok((_, additions, deletions)) = patch.line_stats() {
+                        entry.lines_added += additions as i64;
+                        entry.lines_deleted += deletions as i64;
+                    }
+                }
+                files.insert(path);
+            }
+        }
+
+        let mut contributions: Vec<AuthorContribution> = by_email
+            .into_iter()
+            .map(|(email, mut contribution)| {
+                contribution.files_touched = files_by_email.get(&email).map(|f| f.len() as i64).unwrap_or(0);
+                contribution
+            })
+            .collect();
+
+        let total_lines: i64 = contributions.iter().map(|c| c.lines_added + c.lines_deleted).sum();
+        let total_commits: i64 = contributions.iter().map(|c| c.commits).sum();
+        for contribution in &mut contributions {
+            // Fall back to commit counts for repositories made only of ignored files
+            contribution.share = if total_lines > 0 {
+                (contribution.lines_added + contribution.lines_deleted) as f64 / total_lines as f64
+            } else if total_commits > 0 {
+                contribution.commits as f64 / total_commits as f64
+            } else {
+                0.0
+            };
+        }
+
+        contributions.sort_by(|a, b| b.share.partial_cmp(&a.share).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(contributions)
+    }
+
+    // Matches git authors to students by email, GitHub noreply address, username or name
+    pub fn attribute_contributions(&self, contributions: &mut [AuthorContribution], students: &[Student]) {
+        let noreply_pattern = regex::Regex::new(r"^(?:\d+\+)?([^@]+)@users\.noreply\.github\.com$").unwrap();
+
+        for contribution in contributions.iter_mut() {
+            let noreply_username = noreply_pattern
+                .captures(&contribution.author_email)
+                .map(|cap| cap[1].to_lowercase());
+
+            contribution.student_id = students
+                .iter()
+                .find(|student| {
+                    student.email.as_deref()
+                        .map(|email| email.trim().eq_ignore_ascii_case(&contribution.author_email))
+                        .unwrap_or(false)
+                })
+                .or_else(|| {
+                    students.iter().find(|student| {
+                        student.github_username.as_deref()
+                            .map(|username| {
+                                let username = username.trim().to_lowercase();
+                                noreply_username.as_deref() == Some(username.as_str())
+                                    || contribution.author_name.eq_ignore_ascii_case(&username)
+                            })
+                            .unwrap_or(false)
+                    })
+                })
+                .or_else(|| {
+                    students
+                        .iter()
+                        .find(|student| student.name.trim().eq_ignore_ascii_case(&contribution.author_name))
+                })
+                .map(|student| student.id);
+        }
+    }
+
+    pub fn head_commit_sha(&self, repo_path: &Path) -> Result<String> {
+        let repo = GitRepository::open(repo_path)
+            .map_err(|e| anyhow!("Failed to open repository: {}", e))?;
+        let head = repo.head()?.peel_to_commit()?;
+        Ok(head.id().to_string())
+    }
+
+    // Committer time of HEAD, i.e. when the work was last pushed rather than when it was imported
+    pub fn head_commit_time(&self, repo_path: &Path) -> Result<DateTime<Utc>> {
+        let repo = GitRepository::open(repo_path)
+            .map_err(|e| anyhow!("Failed to open repository: {}", e))?;
+        let head = repo.head()?.peel_to_commit()?;
+        Utc.timestamp_opt(head.time().seconds(), 0)
+            .single()
+            .ok_or_else(|| anyhow!("HEAD has an invalid commit time"))
+    }
+
+    // Follows a line range from one commit to another, across renames.
+    // Returns None when every anchored line was removed or the old commit is gone (e.g. force push).
+    pub fn reanchor_line_range(
+        &self,
+        repo_path: &Path,
+        file_path: &str,
+        from_sha: &str,
+        to_sha: &str,
+        start_line: i64,
+        end_line: i64,
+    ) -> Result<Option<LineAnchor>> {
+        let repo = GitRepository::open(repo_path)
+            .map_err(|e| anyhow!("Failed to open repository: {}", e))?;
+
+        let old_commit = match git2::Oid::from_str(from_sha).and_then(|oid| repo.find_commit(oid)) {
+            Ok(commit) => commit,
+            Err(_) => return Ok(None),
+        };
+        let new_commit = repo.find_commit(git2::Oid::from_str(to_sha)?)?;
+
+        let mut diff = repo.diff_tree_to_tree(Some(&old_commit.tree()?), Some(&new_commit.tree()?), None)?;
+        diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+
+        let delta_index = (0..diff.deltas().len()).find(|&index| {
+            diff.get_delta(index)
+                .and_then(|delta| delta.old_file().path().map(|p| p.to_string_lossy().replace('\\', "/")))
+                .map(|path| path == file_path)
+                .unwrap_or(false)
+        });
+
+        // Untouched file, the anchor carries over as is
+        let Some(delta_index) = delta_index else {
+            return Ok(Some(LineAnchor {
+                file_path: file_path.to_string(),
+                start_line,
+                end_line,
+            }));
+        };
+
+        let delta = diff.get_delta(delta_index).ok_or_else(|| anyhow!("Missing diff delta"))?;
+        if delta.status() == git2::Delta::Deleted {
+            return Ok(None);
+        }
+        let new_path = delta.new_file()
+            .path()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|| file_path.to_string());
+
+        let patch = match git2::Patch::from_diff(&diff, delta_index)? {
+            Some(patch) => patch,
+            // Binary files have no line information
+            None => return Ok(None),
+        };
+
+        // (old_start, old_lines, new_lines, old line -> new line for lines inside the hunk)
+        let mut hunks = Vec::new();
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_index)?;
+            let mut mapping = HashMap::new();
+            for line_index in 0..line_count {
+                let line = patch.line_in_hunk(hunk_index, line_index)?;
+                if let Some(old_lineno) = line.old_lineno() {
+                    mapping.insert(old_lineno as i64, line.new_lineno().map(|n| n as i64));
+                }
+            }
+            hunks.push((hunk.old_start() as i64, hunk.old_lines() as i64, hunk.new_lines() as i64, mapping));
+        }
+
+        let map_line = |line: i64| -> Option<i64> {
+            let mut offset = 0;
+            for (old_start, old_lines, new_lines, mapping) in &hunks {
+                if *old_lines > 0 && line >= *old_start && line < old_start + old_lines {
+                    return mapping.get(&line).copied().flatten();
+                }
+                // A pure insertion hunk sits after line old_start
+                let hunk_end = if *old_lines == 0 { old_start + 1 } else { old_start + old_lines };
+                if line >= hunk_end {
+                    offset += new_lines - old_lines;
+                } else {
+                    break;
+                }
+            }
+            Some(line + offset)
+        };
+
+        let surviving: Vec<i64> = (start_line..=end_line).filter_map(map_line).collect();
+        match (surviving.iter().min(), surviving.iter().max()) {
+            (Some(&start), Some(&end)) => Ok(Some(LineAnchor {
+                file_path: new_path,
+                start_line: start,
+                end_line: end,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    fn is_ignored_contribution_path(&self, path: &str) -> bool {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        IGNORED_CONTRIBUTION_FILES.contains(&file_name)
+            || file_name.ends_with(".min.js")
+            || IGNORED_CONTRIBUTION_PATHS
+                .iter()
+                .any(|dir| path.starts_with(dir) || path.contains(&format!("/{}", dir)))
+    }
+
     pub fn validate_github_url(&self, url: &str) -> bool {
         regex::Regex::new(r"^https://github\.com/[^/]+/[^/]+/?(?:\.git)?$")
             .unwrap()
             .is_match(url)
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::AuthService;
+
+    fn service() -> GitHubService {
+        GitHubService::new(ApiClient::new(AuthService::new()))
+    }
+
+    // Writes `files` into the work tree and commits them as `author` at `seconds` since the epoch
+    fn commit(repo: &GitRepository, files: &[(&str, &str)], author: (&str, &str), seconds: i64) -> git2::Oid {
+        let workdir = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, contents) in files {
+            let full_path = workdir.join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(&full_path, contents).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::new(author.0, author.1, &git2::Time::new(seconds, 0)).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, "change", &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn head_commit_time_is_the_last_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        commit(&repo, &[("main.py", "print(1)\n")], ("Ada", "ada@example.com"), 1_700_000_000);
+        commit(&repo, &[("main.py", "print(2)\n")], ("Ada", "ada@example.com"), 1_700_003_600);
+
+        let submitted_at = service().head_commit_time(dir.path()).unwrap();
+        assert_eq!(submitted_at, Utc.timestamp_opt(1_700_003_600, 0).unwrap());
+    }
+
+    #[test]
+    fn contributions_skip_lockfiles_and_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        let ada = ("Ada", "Ada@Example.com");
+        let grace = ("Grace", "grace@example.com");
+        commit(&repo, &[("src/app.js", "a\nb\nc\n"), ("package-lock.json", "{}\n{}\n{}\n{}\n")], ada, 1_700_000_000);
+        commit(&repo, &[("src/app.js", "a\nb\nc\nd\n"), ("node_modules/x/index.js", "x\n")], grace, 1_700_000_100);
+        commit(&repo, &[("README.md", "hi\n")], ada, 1_700_000_200);
+
+        let contributions = service().collect_contributions(dir.path()).unwrap();
+        assert_eq!(contributions.len(), 2);
+
+        let first = &contributions[0];
+        assert_eq!(first.author_email, "ada@example.com");
+        assert_eq!((first.commits, first.lines_added, first.lines_deleted, first.files_touched), (2, 4, 0, 2));
+        assert_eq!(first.first_commit_at, Utc.timestamp_opt(1_700_000_000, 0).single());
+        assert_eq!(first.last_commit_at, Utc.timestamp_opt(1_700_000_200, 0).single());
+        assert!((first.share - 0.8).abs() < 1e-9);
+
+        let second = &contributions[1];
+        assert_eq!((second.commits, second.lines_added, second.files_touched), (1, 1, 1));
+        assert!((second.share - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn contributions_of_an_empty_repository_are_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        GitRepository::init(dir.path()).unwrap();
+
+        assert!(service().collect_contributions(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn anchors_shift_with_lines_inserted_above() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        let author = ("Ada", "ada@example.com");
+        let from = commit(&repo, &[("src/main.rs", "a\nb\nc\nd\n")], author, 1_700_000_000);
+        let to = commit(&repo, &[("src/main.rs", "new1\nnew2\na\nb\nc\nd\n")], author, 1_700_000_100);
+
+        let anchor = service()
+            .reanchor_line_range(dir.path(), "src/main.rs", &from.to_string(), &to.to_string(), 2, 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!((anchor.file_path.as_str(), anchor.start_line, anchor.end_line), ("src/main.rs", 4, 5));
+    }
+
+    #[test]
+    fn anchors_shrink_to_surviving_lines_and_follow_renames() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        let author = ("Ada", "ada@example.com");
+        let body = "fn one() {}\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() {}\nfn six() {}\n";
+        let from = commit(&repo, &[("old.rs", body)], author, 1_700_000_000);
+
+        // Rename old.rs to new.rs and drop its second line
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.rs")).unwrap();
+        index.write().unwrap();
+        fs::remove_file(dir.path().join("old.rs")).unwrap();
+        let to = commit(&repo, &[("new.rs", &body.replace("fn two() {}\n", ""))], author, 1_700_000_100);
+
+        let anchor = service()
+            .reanchor_line_range(dir.path(), "old.rs", &from.to_string(), &to.to_string(), 2, 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!((anchor.file_path.as_str(), anchor.start_line, anchor.end_line), ("new.rs", 2, 2));
+    }
+
+    #[test]
+    fn anchors_are_lost_with_their_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        let author = ("Ada", "ada@example.com");
+        let from = commit(&repo, &[("app.py", "keep\ndrop\ndrop\nkeep\n")], author, 1_700_000_000);
+        let to = commit(&repo, &[("app.py", "keep\nkeep\n")], author, 1_700_000_100);
+
+        let service = service();
+        assert!(service.reanchor_line_range(dir.path(), "app.py", &from.to_string(), &to.to_string(), 2, 3).unwrap().is_none());
+        // Force-pushed history: the commented commit no longer exists
+        let missing = "0123456789012345678901234567890123456789";
+        assert!(service.reanchor_line_range(dir.path(), "app.py", missing, &to.to_string(), 1, 1).unwrap().is_none());
+        // Untouched files keep their anchor
+        let anchor = service.reanchor_line_range(dir.path(), "README.md", &from.to_string(), &to.to_string(), 3, 7).unwrap().unwrap();
+        assert_eq!((anchor.start_line, anchor.end_line), (3, 7));
+    }
+
+    fn student(id: i64, name: &str, email: Option<&str>, github_username: Option<&str>) -> Student {
+        Student {
+            id,
+            name: name.to_string(),
+            email: email.map(str::to_string),
+            github_username: github_username.map(str::to_string),
+            cohort: None,
+            cohort_id: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn contribution(author_name: &str, author_email: &str) -> AuthorContribution {
+        AuthorContribution {
+            author_name: author_name.to_string(),
+            author_email: author_email.to_string(),
+            student_id: None,
+            commits: 1,
+            lines_added: 0,
+            lines_deleted: 0,
+            files_touched: 0,
+            share: 0.0,
+            first_commit_at: None,
+            last_commit_at: None,
+        }
+    }
+
+    #[test]
+    fn contributions_are_attributed_by_email_then_username_then_name() {
+        let students = vec![
+            student(1, "Ada Lovelace", Some(" ADA@example.com "), None),
+            student(2, "Grace Hopper", None, Some("ghopper")),
+            student(3, "Alan Turing", None, None),
+        ];
+        let mut contributions = vec![
+            contribution("ada-laptop", "ada@example.com"),
+            contribution("Grace", "12345+GHopper@users.noreply.github.com"),
+            contribution("ghopper", "grace@work.example"),
+            contribution("alan turing", "alan@home.example"),
+            contribution("Someone Else", "someone@example.com"),
+        ];
+
+        service().attribute_contributions(&mut contributions, &students);
+
+        let attributed: Vec<Option<i64>> = contributions.iter().map(|c| c.student_id).collect();
+        assert_eq!(attributed, vec![Some(1), Some(2), Some(2), Some(3), None]);
+    }
+}
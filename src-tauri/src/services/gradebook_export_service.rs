@@ -0,0 +1,545 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::services::ExportRow;
+
+const DEFAULT_POINTS_POSSIBLE: i32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GradebookFormat {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "xlsx")]
+    Xlsx,
+    #[serde(rename = "canvas")]
+    Canvas,
+    #[serde(rename = "moodle")]
+    Moodle,
+    #[serde(rename = "google_classroom")]
+    GoogleClassroom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportColumn {
+    #[serde(rename = "student_name")]
+    StudentName,
+    #[serde(rename = "student_email")]
+    StudentEmail,
+    #[serde(rename = "github_username")]
+    GitHubUsername,
+    #[serde(rename = "project_name")]
+    ProjectName,
+    #[serde(rename = "score")]
+    Score,
+    #[serde(rename = "total_score")]
+    TotalScore,
+    #[serde(rename = "final_score")]
+    FinalScore,
+    #[serde(rename = "code_quality_score")]
+    CodeQualityScore,
+    #[serde(rename = "structure_score")]
+    StructureScore,
+    #[serde(rename = "documentation_score")]
+    DocumentationScore,
+    #[serde(rename = "functionality_score")]
+    FunctionalityScore,
+    // Expands to one column per rubric criterion found in the rows
+    #[serde(rename = "criteria")]
+    Criteria,
+    #[serde(rename = "feedback")]
+    Feedback,
+    #[serde(rename = "comments")]
+    Comments,
+}
+
+const DEFAULT_COLUMNS: &[ExportColumn] = &[
+    ExportColumn::StudentName,
+    ExportColumn::StudentEmail,
+    ExportColumn::GitHubUsername,
+    ExportColumn::ProjectName,
+    ExportColumn::Score,
+    ExportColumn::TotalScore,
+    ExportColumn::FinalScore,
+    ExportColumn::Criteria,
+    ExportColumn::Feedback,
+    ExportColumn::Comments,
+];
+
+// How a row is matched to a student on the LMS side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StudentIdentifier {
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "github_username")]
+    GitHubUsername,
+    #[serde(rename = "name")]
+    Name,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradebookExportOptions {
+    pub format: GradebookFormat,
+    pub columns: Option<Vec<ExportColumn>>, // csv/xlsx only, defaults to DEFAULT_COLUMNS
+    pub identifier: Option<StudentIdentifier>, // LMS formats only, defaults to email
+    pub assignment_name: Option<String>, // LMS formats name the grade column after the assignment
+    pub points_possible: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradebookExportResult {
+    pub path: String,
+    pub rows_written: usize,
+    pub skipped: Vec<String>, // rows the LMS could not match, with the reason
+}
+
+pub struct GradebookExportService;
+
+impl GradebookExportService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn export(&self, rows: &[ExportRow], destination: &Path, options: &GradebookExportOptions) -> Result<GradebookExportResult> {
+        let (table, skipped) = match options.format {
+            GradebookFormat::Csv | GradebookFormat::Xlsx => (self.generic_table(rows, options), Vec::new()),
+            GradebookFormat::Canvas => self.canvas_table(rows, options)?,
+            GradebookFormat::Moodle => self.moodle_table(rows, options)?,
+            GradebookFormat::GoogleClassroom => self.classroom_table(rows, options)?,
+        };
+
+        match options.format {
+            GradebookFormat::Xlsx => write_xlsx(&table, destination)?,
+            _ => write_csv(&table, destination)?,
+        }
+
+        Ok(GradebookExportResult {
+            path: destination.display().to_string(),
+            rows_written: table.len().saturating_sub(table_header_rows(options.format)),
+            skipped,
+        })
+    }
+
+    fn generic_table(&self, rows: &[ExportRow], options: &GradebookExportOptions) -> Vec<Vec<Cell>> {
+        let columns = options.columns.as_deref().unwrap_or(DEFAULT_COLUMNS);
+        let criteria = criterion_names(rows);
+
+        let mut header = Vec::new();
+        for column in columns {
+            match column {
+                ExportColumn::Criteria => header.extend(criteria.iter().map(|c| Cell::Text(c.clone()))),
+                other => header.push(Cell::Text(column_title(*other).to_string())),
+            }
+        }
+
+        let mut table = vec![header];
+        for row in rows {
+            let mut cells = Vec::new();
+            for column in columns {
+                match column {
+                    ExportColumn::Criteria => cells.extend(criteria.iter().map(|name| {
+                        Cell::score(row.criteria.iter().find(|c| &c.criterion == name).and_then(|c| c.score))
+                    })),
+                    other => cells.push(column_value(row, *other)),
+                }
+            }
+            table.push(cells);
+        }
+
+        table
+    }
+
+    // Canvas gradebook CSV: fixed identity columns, then one column per assignment and a
+    // "Points Possible" row. Canvas matches on SIS Login ID, which institutions set to the
+    // student's email; names and GitHub usernames would silently match nobody.
+    fn canvas_table(&self, rows: &[ExportRow], options: &GradebookExportOptions) -> Result<(Vec<Vec<Cell>>, Vec<String>)> {
+        if let Some(identifier) = options.identifier.filter(|i| *i != StudentIdentifier::Email) {
+            return Err(anyhow!("Canvas matches students by SIS Login ID (email), not {:?}", identifier));
+        }
+
+        let assignment = assignment_column(options);
+        let points = options.points_possible.unwrap_or(DEFAULT_POINTS_POSSIBLE);
+
+        let mut table = vec![
+            ["Student", "ID", "SIS User ID", "SIS Login ID", "Section", assignment.as_str()]
+                .iter()
+                .map(|title| Cell::Text(title.to_string()))
+                .collect(),
+            vec![
+                Cell::Text("    Points Possible".to_string()),
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Number(points as f64),
+            ],
+        ];
+
+        let mut skipped = Vec::new();
+        for row in rows {
+            let Some(email) = identifier_value(row, StudentIdentifier::Email) else {
+                skipped.push(missing_identifier(row, StudentIdentifier::Email));
+                continue;
+            };
+
+            table.push(vec![
+                Cell::Text(canvas_sortable_name(&row.student_name)),
+                Cell::Empty,
+                Cell::Empty,
+                Cell::Text(email),
+                Cell::Empty,
+                scaled_score(row, points),
+            ]);
+        }
+
+        Ok((table, skipped))
+    }
+
+    // Moodle's grade import maps the identifier column by hand. Its "Username" field is the LMS
+    // login and names are not a mapping field, so only the email address is safe to offer.
+    fn moodle_table(&self, rows: &[ExportRow], options: &GradebookExportOptions) -> Result<(Vec<Vec<Cell>>, Vec<String>)> {
+        if let Some(identifier) = options.identifier.filter(|i| *i != StudentIdentifier::Email) {
+            return Err(anyhow!("Moodle matches students by email address here, not {:?}", identifier));
+        }
+
+        let assignment = assignment_column(options);
+        let points = options.points_possible.unwrap_or(DEFAULT_POINTS_POSSIBLE);
+
+        let mut table = vec![vec![
+            Cell::Text("Email address".to_string()),
+            Cell::Text(assignment.clone()),
+            Cell::Text(format!("Feedback: {}", assignment)),
+        ]];
+
+        let mut skipped = Vec::new();
+        for row in rows {
+            let Some(email) = identifier_value(row, StudentIdentifier::Email) else {
+                skipped.push(missing_identifier(row, StudentIdentifier::Email));
+                continue;
+            };
+
+            table.push(vec![
+                Cell::Text(email),
+                scaled_score(row, points),
+                Cell::Text(row.feedback_with_comments().unwrap_or_default()),
+            ]);
+        }
+
+        Ok((table, skipped))
+    }
+
+    // Google Classroom only matches students by email address
+    fn classroom_table(&self, rows: &[ExportRow], options: &GradebookExportOptions) -> Result<(Vec<Vec<Cell>>, Vec<String>)> {
+        if let Some(identifier) = options.identifier.filter(|i| *i != StudentIdentifier::Email) {
+            return Err(anyhow!("Google Classroom matches students by email only, not {:?}", identifier));
+        }
+
+        let assignment = assignment_column(options);
+        let points = options.points_possible.unwrap_or(DEFAULT_POINTS_POSSIBLE);
+
+        let mut table = vec![vec![
+            Cell::Text("Last Name".to_string()),
+            Cell::Text("First Name".to_string()),
+            Cell::Text("Email Address".to_string()),
+            Cell::Text(assignment),
+        ]];
+
+        let mut skipped = Vec::new();
+        for row in rows {
+            let Some(email) = identifier_value(row, StudentIdentifier::Email) else {
+                skipped.push(missing_identifier(row, StudentIdentifier::Email));
+                continue;
+            };
+
+            let (first, last) = split_name(&row.student_name);
+            table.push(vec![
+                Cell::Text(last),
+                Cell::Text(first),
+                Cell::Text(email),
+                scaled_score(row, points),
+            ]);
+        }
+
+        Ok((table, skipped))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+}
+
+impl Cell {
+    fn score(score: Option<i32>) -> Self {
+        score.map(|s| Cell::Number(s as f64)).unwrap_or(Cell::Empty)
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            Cell::Empty => String::new(),
+            Cell::Text(value) => value.clone(),
+            Cell::Number(value) if value.fract() == 0.0 => format!("{}", *value as i64),
+            Cell::Number(value) => format!("{:.2}", value),
+        }
+    }
+}
+
+fn table_header_rows(format: GradebookFormat) -> usize {
+    match format {
+        GradebookFormat::Canvas => 2,
+        _ => 1,
+    }
+}
+
+fn column_title(column: ExportColumn) -> &'static str {
+    match column {
+        ExportColumn::StudentName => "Student Name",
+        ExportColumn::StudentEmail => "Email",
+        ExportColumn::GitHubUsername => "GitHub Username",
+        ExportColumn::ProjectName => "Project",
+        ExportColumn::Score => "Score",
+        ExportColumn::TotalScore => "Automated Score",
+        ExportColumn::FinalScore => "Final Score",
+        ExportColumn::CodeQualityScore => "Code Quality",
+        ExportColumn::StructureScore => "Structure",
+        ExportColumn::DocumentationScore => "Documentation",
+        ExportColumn::FunctionalityScore => "Functionality",
+        ExportColumn::Criteria => "Criteria",
+        ExportColumn::Feedback => "Feedback",
+        ExportColumn::Comments => "Review Comments",
+    }
+}
+
+fn column_value(row: &ExportRow, column: ExportColumn) -> Cell {
+    let text = |value: Option<&String>| value.map(|v| Cell::Text(v.clone())).unwrap_or(Cell::Empty);
+
+    match column {
+        ExportColumn::StudentName => Cell::Text(row.student_name.clone()),
+        ExportColumn::StudentEmail => text(row.student_email.as_ref()),
+        ExportColumn::GitHubUsername => text(row.github_username.as_ref()),
+        ExportColumn::ProjectName => Cell::Text(row.project_name.clone()),
+        ExportColumn::Score => Cell::score(row.score()),
+        ExportColumn::TotalScore => Cell::score(row.total_score),
+        ExportColumn::FinalScore => Cell::score(row.released_score()),
+        ExportColumn::CodeQualityScore => Cell::score(row.code_quality_score),
+        ExportColumn::StructureScore => Cell::score(row.structure_score),
+        ExportColumn::DocumentationScore => Cell::score(row.documentation_score),
+        ExportColumn::FunctionalityScore => Cell::score(row.functionality_score),
+        ExportColumn::Criteria => Cell::Empty,
+        ExportColumn::Feedback => text(row.feedback.as_ref()),
+        ExportColumn::Comments => text(row.comments.as_ref()),
+    }
+}
+
+// In first-seen order so the analyzer's criteria come before rubric additions
+fn criterion_names(rows: &[ExportRow]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for criterion in rows.iter().flat_map(|row| &row.criteria) {
+        if !names.contains(&criterion.criterion) {
+            names.push(criterion.criterion.clone());
+        }
+    }
+    names
+}
+
+fn identifier_value(row: &ExportRow, identifier: StudentIdentifier) -> Option<String> {
+    let value = match identifier {
+        StudentIdentifier::Email => row.student_email.clone(),
+        StudentIdentifier::GitHubUsername => row.github_username.clone(),
+        StudentIdentifier::Name => Some(row.student_name.clone()),
+    };
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn missing_identifier(row: &ExportRow, identifier: StudentIdentifier) -> String {
+    format!("{} ({}): no {:?} to match on", row.student_name, row.project_name, identifier)
+}
+
+fn assignment_column(options: &GradebookExportOptions) -> String {
+    options
+        .assignment_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("r3viewer Project")
+        .to_string()
+}
+
+// Scores are out of 100 internally; LMS columns use the assignment's point value
+fn scaled_score(row: &ExportRow, points_possible: i32) -> Cell {
+    match row.score() {
+        Some(score) if points_possible == 100 => Cell::Number(score as f64),
+        Some(score) => Cell::Number((score as f64 * points_possible as f64 / 100.0 * 100.0).round() / 100.0),
+        None => Cell::Empty,
+    }
+}
+
+fn split_name(name: &str) -> (String, String) {
+    let name = name.trim();
+    match name.rsplit_once(' ') {
+        Some((first, last)) => (first.trim().to_string(), last.trim().to_string()),
+        None => (name.to_string(), String::new()),
+    }
+}
+
+// Canvas displays and exports students as "Last, First"
+fn canvas_sortable_name(name: &str) -> String {
+    match split_name(name) {
+        (first, last) if !last.is_empty() => format!("{}, {}", last, first),
+        (first, _) => first,
+    }
+}
+
+fn write_csv(table: &[Vec<Cell>], destination: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(destination)?;
+    for row in table {
+        writer.write_record(row.iter().map(Cell::to_text))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_xlsx(table: &[Vec<Cell>], destination: &Path) -> Result<()> {
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Grades")?;
+    let header = rust_xlsxwriter::Format::new().set_bold();
+
+    for (row_index, row) in table.iter().enumerate() {
+        for (column_index, cell) in row.iter().enumerate() {
+            let (r, c) = (row_index as u32, column_index as u16);
+            match cell {
+                Cell::Empty => {}
+                Cell::Text(value) if row_index == 0 => {
+                    sheet.write_string_with_format(r, c, value, &header)?;
+                }
+                Cell::Text(value) => {
+                    sheet.write_string(r, c, value)?;
+                }
+                Cell::Number(value) => {
+                    sheet.write_number(r, c, *value)?;
+                }
+            }
+        }
+    }
+
+    sheet.set_freeze_panes(1, 0)?;
+    workbook.save(destination)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn row(name: &str, email: Option<&str>, total: Option<i32>, final_score: Option<i32>, released: bool) -> ExportRow {
+        ExportRow {
+            student_name: name.to_string(),
+            project_name: "Capstone".to_string(),
+            total_score: total,
+            code_quality_score: None,
+            structure_score: None,
+            documentation_score: None,
+            functionality_score: None,
+            feedback: None,
+            student_id: Some(7),
+            student_email: email.map(str::to_string),
+            github_username: Some("octocat".to_string()),
+            final_score,
+            criteria: Vec::new(),
+            comments: None,
+            released_at: released.then(Utc::now),
+        }
+    }
+
+    fn options(format: GradebookFormat, identifier: Option<StudentIdentifier>, points: Option<i32>) -> GradebookExportOptions {
+        GradebookExportOptions {
+            format,
+            columns: None,
+            identifier,
+            assignment_name: Some("Project 1".to_string()),
+            points_possible: points,
+        }
+    }
+
+    fn texts(row: &[Cell]) -> Vec<String> {
+        row.iter().map(Cell::to_text).collect()
+    }
+
+    #[test]
+    fn scaled_score_uses_the_assignment_points() {
+        let graded = row("Ada Lovelace", None, Some(80), None, false);
+        assert_eq!(scaled_score(&graded, 100).to_text(), "80");
+        assert_eq!(scaled_score(&graded, 50).to_text(), "40");
+        assert_eq!(scaled_score(&row("Ada Lovelace", None, Some(77), None, false), 30).to_text(), "23.10");
+        assert!(matches!(scaled_score(&row("Ada Lovelace", None, None, None, false), 100), Cell::Empty));
+    }
+
+    #[test]
+    fn scaled_score_ignores_unreleased_final_grades() {
+        assert_eq!(scaled_score(&row("Ada Lovelace", None, Some(80), Some(95), false), 100).to_text(), "80");
+        assert_eq!(scaled_score(&row("Ada Lovelace", None, Some(80), Some(95), true), 100).to_text(), "95");
+        assert!(matches!(scaled_score(&row("Ada Lovelace", None, None, Some(95), false), 100), Cell::Empty));
+    }
+
+    #[test]
+    fn canvas_table_writes_email_as_sis_login_id() {
+        let service = GradebookExportService::new();
+        let rows = vec![
+            row("Ada Lovelace", Some(" ada@example.edu "), Some(90), None, false),
+            row("Grace Hopper", None, Some(70), None, false),
+        ];
+
+        let (table, skipped) = service.canvas_table(&rows, &options(GradebookFormat::Canvas, None, Some(10))).unwrap();
+
+        assert_eq!(texts(&table[0]), ["Student", "ID", "SIS User ID", "SIS Login ID", "Section", "Project 1"]);
+        assert_eq!(texts(&table[1]), ["    Points Possible", "", "", "", "", "10"]);
+        assert_eq!(texts(&table[2]), ["Lovelace, Ada", "", "", "ada@example.edu", "", "9"]);
+        assert_eq!(table.len(), 3);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("Grace Hopper"));
+    }
+
+    #[test]
+    fn canvas_table_rejects_identifiers_canvas_cannot_match() {
+        let service = GradebookExportService::new();
+        let rows = vec![row("Ada Lovelace", Some("ada@example.edu"), Some(90), None, false)];
+
+        for identifier in [StudentIdentifier::Name, StudentIdentifier::GitHubUsername] {
+            assert!(service.canvas_table(&rows, &options(GradebookFormat::Canvas, Some(identifier), None)).is_err());
+        }
+        assert!(service.canvas_table(&rows, &options(GradebookFormat::Canvas, Some(StudentIdentifier::Email), None)).is_ok());
+    }
+
+    #[test]
+    fn moodle_table_only_matches_by_email() {
+        let service = GradebookExportService::new();
+        let rows = vec![row("Ada Lovelace", Some("ada@example.edu"), Some(90), None, false)];
+
+        for identifier in [StudentIdentifier::Name, StudentIdentifier::GitHubUsername] {
+            assert!(service.moodle_table(&rows, &options(GradebookFormat::Moodle, Some(identifier), None)).is_err());
+        }
+        let (table, skipped) = service.moodle_table(&rows, &options(GradebookFormat::Moodle, None, None)).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(texts(&table[0])[0], "Email address");
+        assert_eq!(texts(&table[1])[0], "ada@example.edu");
+    }
+
+    #[test]
+    fn generic_final_score_column_only_shows_released_grades() {
+        let service = GradebookExportService::new();
+        let mut options = options(GradebookFormat::Csv, None, None);
+        options.columns = Some(vec![ExportColumn::StudentName, ExportColumn::FinalScore, ExportColumn::Score]);
+        let rows = vec![
+            row("Ada Lovelace", None, Some(80), Some(95), false),
+            row("Grace Hopper", None, Some(60), Some(65), true),
+        ];
+
+        let table = service.generic_table(&rows, &options);
+
+        assert_eq!(texts(&table[1]), ["Ada Lovelace", "", "80"]);
+        assert_eq!(texts(&table[2]), ["Grace Hopper", "65", "65"]);
+    }
+}
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use crate::database::models::{CreateStudent, Student};
+
+// Names at or above this similarity are reported as possible duplicates, never merged automatically
+const FUZZY_NAME_THRESHOLD: f64 = 0.88;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchReason {
+    #[serde(rename = "email")]
+    Email,
+    #[serde(rename = "github_username")]
+    GitHubUsername,
+    #[serde(rename = "exact_name")]
+    ExactName,
+    #[serde(rename = "fuzzy_name")]
+    FuzzyName,
+}
+
+impl MatchReason {
+    // Names are shared by different people, so a name match on its own needs a human to confirm
+    pub fn is_confident(&self) -> bool {
+        matches!(self, MatchReason::Email | MatchReason::GitHubUsername)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudentMatch {
+    pub student_id: i64,
+    pub reason: MatchReason,
+    pub similarity: f64,
+    pub also_matched: Vec<i64>, // other students that matched just as well
+}
+
+impl StudentMatch {
+    fn new(reason: MatchReason, similarity: f64, student_ids: Vec<i64>) -> Option<Self> {
+        let (&student_id, also_matched) = student_ids.split_first()?;
+        Some(Self { student_id, reason, similarity, also_matched: also_matched.to_vec() })
+    }
+
+    pub fn is_ambiguous(&self) -> bool {
+        !self.also_matched.is_empty()
+    }
+
+    // Only an unambiguous email or username match may update an existing record
+    pub fn is_confident(&self) -> bool {
+        self.reason.is_confident() && !self.is_ambiguous()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
+    pub first: Student,
+    pub second: Student,
+    pub reason: MatchReason,
+    pub similarity: f64,
+}
+
+pub struct IdentityService;
+
+impl IdentityService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // Email wins, then GitHub username, then the name. Name matches are never confident, and a name
+    // match against a record whose email or username contradicts the candidate is only fuzzy.
+    pub fn match_student(&self, candidate: &CreateStudent, existing: &[Student]) -> Option<StudentMatch> {
+        let email = candidate.email.as_deref().map(normalize_identifier).filter(|e| !e.is_empty());
+        let username = candidate.github_username.as_deref().map(normalize_username).filter(|u| !u.is_empty());
+
+        if let Some(email) = &email {
+            let ids = existing
+                .iter()
+                .filter(|s| s.email.as_deref().map(normalize_identifier).as_ref() == Some(email))
+                .map(|s| s.id)
+                .collect();
+            if let Some(found) = StudentMatch::new(MatchReason::Email, 1.0, ids) {
+                return Some(found);
+            }
+        }
+
+        if let Some(username) = &username {
+            let ids = existing
+                .iter()
+                .filter(|s| s.github_username.as_deref().map(normalize_username).as_ref() == Some(username))
+                .map(|s| s.id)
+                .collect();
+            if let Some(found) = StudentMatch::new(MatchReason::GitHubUsername, 1.0, ids) {
+                return Some(found);
+            }
+        }
+
+        let name = normalize_name(&candidate.name);
+        let compatible = |student: &Student| {
+            let email_conflict = match (&email, student.email.as_deref().map(normalize_identifier)) {
+                (Some(a), Some(b)) => !b.is_empty() && *a != b,
+                _ => false,
+            };
+            let username_conflict = match (&username, student.github_username.as_deref().map(normalize_username)) {
+                (Some(a), Some(b)) => !b.is_empty() && *a != b,
+                _ => false,
+            };
+            !email_conflict && !username_conflict
+        };
+
+        // Everyone tied for the best similarity is reported, exact names ahead of contradicted ones
+        let mut best: Option<(f64, MatchReason, Vec<i64>)> = None;
+        for student in existing {
+            let similarity = name_similarity(&name, &normalize_name(&student.name));
+            if similarity < FUZZY_NAME_THRESHOLD {
+                continue;
+            }
+
+            let reason = if similarity >= 1.0 && compatible(student) {
+                MatchReason::ExactName
+            } else {
+                MatchReason::FuzzyName
+            };
+
+            match &mut best {
+                Some((best_similarity, best_reason, ids)) if similarity == *best_similarity => {
+                    if reason == MatchReason::ExactName && *best_reason != MatchReason::ExactName {
+                        *best_reason = reason;
+                        ids.insert(0, student.id);
+                    } else {
+                        ids.push(student.id);
+                    }
+                }
+                Some((best_similarity, _, _)) if similarity < *best_similarity => {}
+                _ => best = Some((similarity, reason, vec![student.id])),
+            }
+        }
+
+        best.and_then(|(similarity, reason, ids)| StudentMatch::new(reason, similarity, ids))
+    }
+
+    pub fn find_duplicates(&self, students: &[Student]) -> Vec<DuplicateCandidate> {
+        let mut candidates = Vec::new();
+
+        for (index, first) in students.iter().enumerate() {
+            for second in &students[index + 1..] {
+                let as_candidate = CreateStudent {
+                    name: second.name.clone(),
+                    email: second.email.clone(),
+                    github_username: second.github_username.clone(),
+                    cohort: second.cohort.clone(),
+                    cohort_id: second.cohort_id,
+                };
+
+                if let Some(found) = self.match_student(&as_candidate, std::slice::from_ref(first)) {
+                    candidates.push(DuplicateCandidate {
+                        first: first.clone(),
+                        second: second.clone(),
+                        reason: found.reason,
+                        similarity: found.similarity,
+                    });
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+        candidates
+    }
+}
+
+fn normalize_identifier(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+fn normalize_username(value: &str) -> String {
+    value.trim().trim_start_matches('@').to_lowercase()
+}
+
+// Lowercase, punctuation stripped, tokens sorted so "Lopez, Ana" equals "Ana Lopez"
+pub(crate) fn normalize_name(name: &str) -> String {
+    let cleaned: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut tokens: Vec<&str> = cleaned.split_whitespace().collect();
+    tokens.sort_unstable();
+    tokens.join(" ")
+}
+
+// 1.0 for identical strings, falling with edit distance relative to the longer one
+pub(crate) fn name_similarity(a: &str, b: &str) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+
+    let longest = a.chars().count().max(b.chars().count()) as f64;
+    1.0 - levenshtein(a, b) as f64 / longest
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b_chars.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{schema, test_support};
+
+    // Stored in a fresh database, so ids run from 1 in the order given
+    async fn students(rows: &[(&str, Option<&str>, Option<&str>)]) -> Vec<Student> {
+        let pool = test_support::pool().await;
+        let mut students = Vec::new();
+        for (name, email, github_username) in rows {
+            let id = test_support::github_student(&pool, name, *email, *github_username).await;
+            students.push(schema::get_student_by_id(&pool, id).await.unwrap().unwrap());
+        }
+        students
+    }
+
+    fn candidate(name: &str, email: Option<&str>, github_username: Option<&str>) -> CreateStudent {
+        CreateStudent {
+            name: name.to_string(),
+            email: email.map(str::to_string),
+            github_username: github_username.map(str::to_string),
+            cohort: None,
+            cohort_id: None,
+        }
+    }
+
+    #[test]
+    fn levenshtein_counts_single_character_edits() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", "abc"), 0);
+        assert_eq!(levenshtein("José", "Jose"), 1);
+    }
+
+    #[test]
+    fn names_compare_regardless_of_order_case_and_punctuation() {
+        assert_eq!(normalize_name("Lopez, Ana"), normalize_name("ana  LOPEZ"));
+        assert_eq!(name_similarity(&normalize_name("Lopez, Ana"), &normalize_name("Ana Lopez")), 1.0);
+        assert!(name_similarity("ana lopez", "ana lopes") >= FUZZY_NAME_THRESHOLD);
+        assert!(name_similarity("ana lopez", "bob smith") < FUZZY_NAME_THRESHOLD);
+        assert_eq!(name_similarity("", "ana"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn email_and_username_matches_are_confident() {
+        let service = IdentityService::new();
+        let existing = students(&[
+            ("Ada Lovelace", Some("ada@example.com"), None),
+            ("Grace Hopper", None, Some("ghopper")),
+        ]).await;
+
+        let by_email = service.match_student(&candidate("A. Lovelace", Some(" ADA@example.com"), None), &existing).unwrap();
+        assert_eq!((by_email.student_id, by_email.reason), (1, MatchReason::Email));
+        assert!(by_email.is_confident());
+
+        let by_username = service.match_student(&candidate("G. Hopper", None, Some("@GHopper")), &existing).unwrap();
+        assert_eq!((by_username.student_id, by_username.reason), (2, MatchReason::GitHubUsername));
+        assert!(by_username.is_confident());
+    }
+
+    #[tokio::test]
+    async fn name_matches_need_review() {
+        let service = IdentityService::new();
+        let existing = students(&[
+            ("Ada Lovelace", None, None),
+            ("Grace Hopper", Some("grace@example.com"), None),
+        ]).await;
+
+        let exact = service.match_student(&candidate("Lovelace, Ada", None, None), &existing).unwrap();
+        assert_eq!((exact.student_id, exact.reason), (1, MatchReason::ExactName));
+        assert!(!exact.is_confident());
+
+        // Same name, different email: probably a different person
+        let contradicted = service.match_student(&candidate("Grace Hopper", Some("g.hopper@other.example"), None), &existing).unwrap();
+        assert_eq!(contradicted.reason, MatchReason::FuzzyName);
+        assert!(!contradicted.is_confident());
+
+        assert!(service.match_student(&candidate("Alan Turing", None, None), &existing).is_none());
+    }
+
+    #[tokio::test]
+    async fn ties_are_reported_as_ambiguous() {
+        let service = IdentityService::new();
+        let existing = students(&[
+            ("Ana Lopez", Some("ana.lopez@other.example"), None),
+            ("Ana Lopez", None, None),
+            ("Ana Lopes", None, None),
+            ("Sam Lee", None, Some("samlee")),
+            ("Samuel Lee", None, Some("SamLee")),
+        ]).await;
+
+        let by_name = service.match_student(&candidate("Ana Lopez", Some("ana@example.com"), None), &existing).unwrap();
+        // The record without a contradicting email comes first, but the other one is still listed
+        assert_eq!((by_name.student_id, by_name.reason, by_name.also_matched.clone()), (2, MatchReason::ExactName, vec![1]));
+        assert!(by_name.is_ambiguous());
+
+        let by_username = service.match_student(&candidate("S. Lee", None, Some("samlee")), &existing).unwrap();
+        assert_eq!((by_username.reason, by_username.student_id, by_username.also_matched.clone()), (MatchReason::GitHubUsername, 4, vec![5]));
+        assert!(!by_username.is_confident());
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_listed_most_similar_first() {
+        let service = IdentityService::new();
+        let records = students(&[
+            ("Ana Lopes", None, None),
+            ("Ana Lopez", None, None),
+            ("Bob Smith", Some("bob@example.com"), None),
+            ("Robert Smith", Some("BOB@example.com"), None),
+        ]).await;
+
+        let duplicates = service.find_duplicates(&records);
+        let pairs: Vec<(i64, i64, MatchReason)> = duplicates.iter().map(|d| (d.first.id, d.second.id, d.reason)).collect();
+        assert_eq!(pairs, vec![(3, 4, MatchReason::Email), (1, 2, MatchReason::FuzzyName)]);
+    }
+}
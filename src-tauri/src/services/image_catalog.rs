@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use crate::database::models::TechnologyStack;
+
+pub const IMAGE_CATALOG_SETTING_KEY: &str = "image_catalog";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImageStack {
+    #[serde(rename = "node")]
+    Node,
+    #[serde(rename = "python")]
+    Python,
+    #[serde(rename = "java")]
+    Java,
+    #[serde(rename = "rust")]
+    Rust,
+    #[serde(rename = "go")]
+    Go,
+    #[serde(rename = "php")]
+    Php,
+    #[serde(rename = "ruby")]
+    Ruby,
+    #[serde(rename = "generic")]
+    Generic,
+}
+
+impl ImageStack {
+    pub fn from_technology(stack: &TechnologyStack) -> Self {
+        match stack {
+            TechnologyStack::NodeJS | TechnologyStack::React | TechnologyStack::Vue | TechnologyStack::Angular => ImageStack::Node,
+            TechnologyStack::Python | TechnologyStack::Django | TechnologyStack::Flask => ImageStack::Python,
+            TechnologyStack::Java | TechnologyStack::SpringBoot => ImageStack::Java,
+            TechnologyStack::Rust => ImageStack::Rust,
+            TechnologyStack::Go => ImageStack::Go,
+            TechnologyStack::PHP => ImageStack::Php,
+            TechnologyStack::Ruby => ImageStack::Ruby,
+            TechnologyStack::Generic => ImageStack::Generic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StackImage {
+    pub stack: ImageStack,
+    pub name: String,
+    pub tag: String,
+    pub digest: Option<String>,
+    // Tag used when the project pins its own runtime version, e.g. "{version}-alpine"
+    pub version_tag_template: Option<String>,
+}
+
+impl StackImage {
+    fn new(stack: ImageStack, name: &str, tag: &str, version_tag_template: Option<&str>) -> Self {
+        Self {
+            stack,
+            name: name.to_string(),
+            tag: tag.to_string(),
+            digest: None,
+            version_tag_template: version_tag_template.map(|t| t.to_string()),
+        }
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.digest.as_deref().is_some_and(|digest| !digest.trim().is_empty())
+    }
+
+    pub fn reference(&self) -> String {
+        match &self.digest {
+            Some(digest) if self.is_pinned() => format!("{}@{}", self.name, digest.trim()),
+            _ => format!("{}:{}", self.name, self.tag),
+        }
+    }
+
+    fn reference_for_version(&self, version: &str) -> Option<String> {
+        self.version_tag_template
+            .as_ref()
+            .map(|template| format!("{}:{}", self.name, template.replace("{version}", version)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignmentImageOverride {
+    pub assignment_id: i64,
+    pub image: StackImage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageCatalog {
+    pub images: Vec<StackImage>,
+    pub assignment_overrides: Vec<AssignmentImageOverride>,
+}
+
+impl Default for ImageCatalog {
+    fn default() -> Self {
+        Self {
+            images: vec![
+                StackImage::new(ImageStack::Node, "node", "22-alpine", Some("{version}-alpine")),
+                StackImage::new(ImageStack::Python, "python", "3.12-slim", Some("{version}-slim")),
+                StackImage::new(ImageStack::Java, "maven", "3.9-eclipse-temurin-21", Some("3.9-eclipse-temurin-{version}")),
+                StackImage::new(ImageStack::Rust, "rust", "1.85", None),
+                StackImage::new(ImageStack::Go, "golang", "1.23-alpine", None),
+                StackImage::new(ImageStack::Php, "php", "8.3-apache", None),
+                StackImage::new(ImageStack::Ruby, "ruby", "3.3", None),
+                StackImage::new(ImageStack::Generic, "alpine", "3.20", None),
+            ],
+            assignment_overrides: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedImage {
+    pub stack: ImageStack,
+    pub reference: String,
+    pub detected_version: Option<String>,
+    pub source: ImageSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ImageSource {
+    #[serde(rename = "assignment")]
+    AssignmentOverride,
+    #[serde(rename = "project")]
+    ProjectVersion,
+    #[serde(rename = "catalog")]
+    Catalog,
+}
+
+impl ImageCatalog {
+    pub fn image_for(&self, stack: ImageStack) -> StackImage {
+        self.images
+            .iter()
+            .find(|image| image.stack == stack)
+            .cloned()
+            .or_else(|| {
+                ImageCatalog::default()
+                    .images
+                    .into_iter()
+                    .find(|image| image.stack == stack)
+            })
+            .expect("default catalog covers every stack")
+    }
+
+    // Assignment overrides win, then a digest pinned in the catalog, then a runtime version pinned by the
+    // project, then the catalog default
+    pub fn resolve(&self, stack: ImageStack, project_path: &Path, assignment_id: Option<i64>) -> ResolvedImage {
+        if let Some(assignment_id) = assignment_id {
+            if let Some(entry) = self.assignment_overrides
+                .iter()
+                .find(|o| o.assignment_id == assignment_id && o.image.stack == stack)
+            {
+                return ResolvedImage {
+                    stack,
+                    reference: entry.image.reference(),
+                    detected_version: None,
+                    source: ImageSource::AssignmentOverride,
+                };
+            }
+        }
+
+        let image = self.image_for(stack);
+        let detected_version = detect_runtime_version(stack, project_path);
+
+        // A pinned digest is an explicit choice of image, so it is never swapped for a version tag
+        if image.is_pinned() {
+            return ResolvedImage {
+                stack,
+                reference: image.reference(),
+                detected_version,
+                source: ImageSource::Catalog,
+            };
+        }
+
+        if let Some(reference) = detected_version.as_deref().and_then(|v| image.reference_for_version(v)) {
+            return ResolvedImage {
+                stack,
+                reference,
+                detected_version,
+                source: ImageSource::ProjectVersion,
+            };
+        }
+
+        ResolvedImage {
+            stack,
+            reference: image.reference(),
+            detected_version,
+            source: ImageSource::Catalog,
+        }
+    }
+
+    pub fn references(&self, stacks: &[ImageStack]) -> Vec<String> {
+        self.images
+            .iter()
+            .filter(|image| stacks.is_empty() || stacks.contains(&image.stack))
+            .map(|image| image.reference())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagePullProgress {
+    pub image: String,
+    pub layer_id: Option<String>,
+    pub status: String,
+    pub current: Option<i64>,
+    pub total: Option<i64>,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagePullResult {
+    pub image: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+pub type PullProgressCallback = Arc<dyn Fn(ImagePullProgress) + Send + Sync>;
+
+// Runtime version detection from project metadata files
+pub fn detect_runtime_version(stack: ImageStack, project_path: &Path) -> Option<String> {
+    match stack {
+        ImageStack::Node => detect_node_version(project_path),
+        ImageStack::Python => detect_python_version(project_path),
+        ImageStack::Java => detect_java_version(project_path),
+        _ => None,
+    }
+}
+
+fn detect_node_version(project_path: &Path) -> Option<String> {
+    if let Ok(content) = fs::read_to_string(project_path.join(".nvmrc")) {
+        if let Some(major) = leading_number(content.trim().trim_start_matches('v')) {
+            return Some(major);
+        }
+    }
+
+    let content = fs::read_to_string(project_path.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let engines = package["engines"]["node"].as_str()?;
+
+    // ">=18", "^20.1.0", "18.x" and "18 || 20" all resolve to the first major mentioned
+    regex::Regex::new(r"(\d+)")
+        .unwrap()
+        .captures(engines)
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+fn detect_python_version(project_path: &Path) -> Option<String> {
+    let pattern = regex::Regex::new(r"(\d+)\.(\d+)").unwrap();
+
+    for file in [".python-version", "runtime.txt"] {
+        if let Ok(content) = fs::read_to_string(project_path.join(file)) {
+            // runtime.txt uses the Heroku style "python-3.11.4"
+            if let Some(cap) = pattern.captures(content.trim()) {
+                return Some(format!("{}.{}", &cap[1], &cap[2]));
+            }
+        }
+    }
+
+    None
+}
+
+fn detect_java_version(project_path: &Path) -> Option<String> {
+    let content = fs::read_to_string(project_path.join("pom.xml")).ok()?;
+
+    for property in ["java.version", "maven.compiler.release", "maven.compiler.source", "maven.compiler.target"] {
+        let pattern = regex::Regex::new(&format!(r"<{0}>\s*([\d.]+)\s*</{0}>", regex::escape(property))).unwrap();
+        if let Some(cap) = pattern.captures(&content) {
+            let version = cap[1].to_string();
+            // Legacy "1.8" style versions map to 8
+            let major = match version.strip_prefix("1.") {
+                Some(rest) => leading_number(rest),
+                None => leading_number(&version),
+            };
+            if major.is_some() {
+                return major;
+            }
+        }
+    }
+
+    None
+}
+
+fn leading_number(value: &str) -> Option<String> {
+    let digits: String = value.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() { None } else { Some(digits) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_with(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        dir
+    }
+
+    fn catalog_with_node_digest(digest: Option<&str>) -> ImageCatalog {
+        let mut catalog = ImageCatalog::default();
+        for image in catalog.images.iter_mut().filter(|image| image.stack == ImageStack::Node) {
+            image.digest = digest.map(str::to_string);
+        }
+        catalog
+    }
+
+    #[test]
+    fn detects_node_version_from_nvmrc_before_package_json() {
+        let project = project_with(&[(".nvmrc", "v20.11.1\n"), ("package.json", r#"{"engines":{"node":">=18"}}"#)]);
+        assert_eq!(detect_node_version(project.path()).as_deref(), Some("20"));
+
+        let project = project_with(&[("package.json", r#"{"engines":{"node":"^18.2.0 || 20"}}"#)]);
+        assert_eq!(detect_node_version(project.path()).as_deref(), Some("18"));
+
+        let project = project_with(&[("package.json", r#"{"name":"app"}"#)]);
+        assert_eq!(detect_node_version(project.path()), None);
+    }
+
+    #[test]
+    fn detects_python_version_from_runtime_txt() {
+        let project = project_with(&[("runtime.txt", "python-3.11.4")]);
+        assert_eq!(detect_python_version(project.path()).as_deref(), Some("3.11"));
+
+        let project = project_with(&[(".python-version", "3.10")]);
+        assert_eq!(detect_python_version(project.path()).as_deref(), Some("3.10"));
+    }
+
+    #[test]
+    fn detects_java_version_including_legacy_style() {
+        let project = project_with(&[("pom.xml", "<properties><java.version>17</java.version></properties>")]);
+        assert_eq!(detect_java_version(project.path()).as_deref(), Some("17"));
+
+        let project = project_with(&[("pom.xml", "<maven.compiler.source>1.8</maven.compiler.source>")]);
+        assert_eq!(detect_java_version(project.path()).as_deref(), Some("8"));
+    }
+
+    #[test]
+    fn resolve_uses_project_version_over_catalog_tag() {
+        let project = project_with(&[(".nvmrc", "18")]);
+        let resolved = ImageCatalog::default().resolve(ImageStack::Node, project.path(), None);
+
+        assert_eq!(resolved.reference, "node:18-alpine");
+        assert!(matches!(resolved.source, ImageSource::ProjectVersion));
+    }
+
+    #[test]
+    fn resolve_falls_back_to_catalog_without_version() {
+        let project = project_with(&[]);
+        let resolved = ImageCatalog::default().resolve(ImageStack::Rust, project.path(), None);
+
+        assert_eq!(resolved.reference, "rust:1.85");
+        assert!(matches!(resolved.source, ImageSource::Catalog));
+    }
+
+    #[test]
+    fn resolve_keeps_pinned_digest_when_version_is_detected() {
+        let project = project_with(&[(".nvmrc", "18")]);
+        let resolved = catalog_with_node_digest(Some("sha256:abc"))
+            .resolve(ImageStack::Node, project.path(), None);
+
+        assert_eq!(resolved.reference, "node@sha256:abc");
+        assert_eq!(resolved.detected_version.as_deref(), Some("18"));
+        assert!(matches!(resolved.source, ImageSource::Catalog));
+    }
+
+    #[test]
+    fn resolve_ignores_blank_digest() {
+        let project = project_with(&[(".nvmrc", "18")]);
+        let resolved = catalog_with_node_digest(Some("  ")).resolve(ImageStack::Node, project.path(), None);
+
+        assert_eq!(resolved.reference, "node:18-alpine");
+    }
+
+    #[test]
+    fn resolve_prefers_assignment_override() {
+        let mut catalog = ImageCatalog::default();
+        catalog.assignment_overrides.push(AssignmentImageOverride {
+            assignment_id: 7,
+            image: StackImage::new(ImageStack::Node, "node", "16", None),
+        });
+        let project = project_with(&[(".nvmrc", "20")]);
+
+        let resolved = catalog.resolve(ImageStack::Node, project.path(), Some(7));
+        assert_eq!(resolved.reference, "node:16");
+        assert!(matches!(resolved.source, ImageSource::AssignmentOverride));
+
+        let resolved = catalog.resolve(ImageStack::Node, project.path(), Some(8));
+        assert_eq!(resolved.reference, "node:20-alpine");
+    }
+}
@@ -1,11 +1,29 @@
 pub mod auth_service;
+pub mod api_client;
+pub mod github_auth;
 pub mod github_service;
 pub mod sheets_service;
+pub mod column_mapping;
 pub mod docker_service;
+pub mod container_runtime;
+pub mod image_catalog;
 pub mod analysis_service;
+pub mod smoke_test_service;
+pub mod identity_service;
+pub mod roster_file_service;
+pub mod gradebook_export_service;
 
 pub use auth_service::*;
+pub use api_client::*;
+pub use github_auth::*;
 pub use github_service::*;
 pub use sheets_service::*;
+pub use column_mapping::*;
 pub use docker_service::*;
-pub use analysis_service::*; 
\ No newline at end of file
+pub use container_runtime::*;
+pub use image_catalog::*;
+pub use analysis_service::*;
+pub use smoke_test_service::*;
+pub use identity_service::*;
+pub use roster_file_service::*;
+pub use gradebook_export_service::*; 
\ No newline at end of file
@@ -0,0 +1,295 @@
+use anyhow::{Result, anyhow};
+use calamine::{open_workbook_auto, Data, Reader};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::services::SheetData;
+
+// Checked in order; ties go to the earlier candidate
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b';', b'\t', b'|'];
+const DELIMITER_SAMPLE_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RosterFileFormat {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "xlsx")]
+    Xlsx,
+    #[serde(rename = "xls")]
+    Xls,
+    #[serde(rename = "ods")]
+    Ods,
+}
+
+impl RosterFileFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "csv" | "tsv" | "txt" => Ok(RosterFileFormat::Csv),
+            "xlsx" | "xlsm" => Ok(RosterFileFormat::Xlsx),
+            "xls" => Ok(RosterFileFormat::Xls),
+            "ods" => Ok(RosterFileFormat::Ods),
+            other => Err(anyhow!("Unsupported roster file type: .{}", other)),
+        }
+    }
+}
+
+// Every field is optional; anything left unset is detected from the file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RosterFileOptions {
+    pub delimiter: Option<char>,
+    pub encoding: Option<String>, // WHATWG label, e.g. "windows-1252"
+    pub sheet_name: Option<String>, // workbooks only, defaults to the first sheet
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RosterFilePreview {
+    pub format: RosterFileFormat,
+    pub sheet_names: Vec<String>,
+    pub delimiter: Option<char>,
+    pub encoding: Option<String>,
+    pub data: SheetData,
+}
+
+pub struct RosterFileService;
+
+impl RosterFileService {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // Produces the same SheetData as the Google Sheets API so SheetMapping and the import flow apply unchanged
+    pub fn read_roster_file(&self, path: &Path, options: &RosterFileOptions) -> Result<RosterFilePreview> {
+        let format = RosterFileFormat::from_path(path)?;
+
+        match format {
+            RosterFileFormat::Csv => {
+                let bytes = std::fs::read(path)?;
+                let (text, encoding) = decode_text(&bytes, options.encoding.as_deref())?;
+                let delimiter = match options.delimiter {
+                    Some(delimiter) if delimiter.is_ascii() => delimiter as u8,
+                    Some(delimiter) => return Err(anyhow!("Delimiter must be a single ASCII character, got {:?}", delimiter)),
+                    None => detect_delimiter(&text),
+                };
+
+                Ok(RosterFilePreview {
+                    format,
+                    sheet_names: Vec::new(),
+                    delimiter: Some(delimiter as char),
+                    encoding: Some(encoding),
+                    data: parse_csv(&text, delimiter)?,
+                })
+            }
+            _ => {
+                let mut workbook = open_workbook_auto(path)?;
+                let sheet_names = workbook.sheet_names().to_vec();
+                let sheet_name = match &options.sheet_name {
+                    Some(name) if sheet_names.contains(name) => name.clone(),
+                    Some(name) => return Err(anyhow!("Workbook has no sheet named {}", name)),
+                    None => sheet_names.first().cloned().ok_or_else(|| anyhow!("Workbook has no sheets"))?,
+                };
+
+                let range = workbook.worksheet_range(&sheet_name)?;
+                let rows = range
+                    .rows()
+                    .map(|row| row.iter().map(cell_to_string).collect())
+                    .collect();
+
+                Ok(RosterFilePreview {
+                    format,
+                    sheet_names,
+                    delimiter: None,
+                    encoding: None,
+                    data: into_sheet_data(rows)?,
+                })
+            }
+        }
+    }
+}
+
+// BOM first, then strict UTF-8, then a statistical guess (Excel on Windows still writes windows-1252)
+fn decode_text(bytes: &[u8], label: Option<&str>) -> Result<(String, String)> {
+    let encoding = match label {
+        Some(label) => encoding_rs::Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| anyhow!("Unknown text encoding: {}", label))?,
+        None => match encoding_rs::Encoding::for_bom(bytes) {
+            Some((encoding, _)) => encoding,
+            None if std::str::from_utf8(bytes).is_ok() => encoding_rs::UTF_8,
+            None => {
+                let mut detector = chardetng::EncodingDetector::new();
+                detector.feed(bytes, true);
+                detector.guess(None, true)
+            }
+        },
+    };
+
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        return Err(anyhow!("File is not valid {}; choose the encoding explicitly", encoding.name()));
+    }
+
+    Ok((text.into_owned(), encoding.name().to_string()))
+}
+
+// The delimiter that splits the sampled lines into the same number (> 1) of fields most often
+fn detect_delimiter(text: &str) -> u8 {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(DELIMITER_SAMPLE_LINES)
+        .collect();
+
+    let mut best = (b',', 0usize);
+    for candidate in DELIMITER_CANDIDATES {
+        let counts: Vec<usize> = lines.iter().map(|line| count_unquoted(line, candidate)).collect();
+        let Some(&first) = counts.first() else { continue };
+        if first == 0 {
+            continue;
+        }
+
+        let consistent = counts.iter().filter(|count| **count == first).count();
+        if consistent > best.1 {
+            best = (candidate, consistent);
+        }
+    }
+
+    best.0
+}
+
+fn count_unquoted(line: &str, delimiter: u8) -> usize {
+    let mut in_quotes = false;
+    line.bytes()
+        .filter(|byte| {
+            if *byte == b'"' {
+                in_quotes = !in_quotes;
+            }
+            !in_quotes && *byte == delimiter
+        })
+        .count()
+}
+
+fn parse_csv(text: &str, delimiter: u8) -> Result<SheetData> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(record.iter().map(|field| field.trim().to_string()).collect());
+    }
+
+    into_sheet_data(rows)
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(value) => value.trim().to_string(),
+        // Student numbers come back as floats; keep them from turning into "1234.0"
+        Data::Float(value) if value.fract() == 0.0 && value.abs() < 1e15 => format!("{}", *value as i64),
+        Data::DateTime(value) => value
+            .as_datetime()
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| value.to_string()),
+        other => other.to_string(),
+    }
+}
+
+// First non-empty row is the header, like the Sheets import; blank rows are dropped
+fn into_sheet_data(rows: Vec<Vec<String>>) -> Result<SheetData> {
+    let mut rows = rows
+        .into_iter()
+        .map(|mut row| {
+            while row.last().is_some_and(|cell| cell.is_empty()) {
+                row.pop();
+            }
+            row
+        })
+        .filter(|row| !row.is_empty());
+
+    let headers = rows.next().ok_or_else(|| anyhow!("Sheet is empty"))?;
+
+    Ok(SheetData { headers, rows: rows.collect() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delimiter_is_the_one_that_splits_lines_consistently() {
+        assert_eq!(detect_delimiter("name;email\nAda;ada@example.com\nGrace;grace@example.com\n"), b';');
+        assert_eq!(detect_delimiter("name\temail\nAda\tada@example.com\n"), b'\t');
+        // Commas inside quotes don't count, so the semicolons win
+        assert_eq!(detect_delimiter("\"Lovelace, Ada\";ada@example.com\n\"Hopper, Grace\";grace@example.com\n"), b';');
+        assert_eq!(detect_delimiter("a,b;c\nd,e;f\n"), b',');
+        assert_eq!(detect_delimiter("just one column\n"), b',');
+    }
+
+    #[test]
+    fn text_is_decoded_from_bom_utf8_or_the_given_label() {
+        let (text, encoding) = decode_text(b"\xEF\xBB\xBFname\nJos\xC3\xA9", None).unwrap();
+        assert_eq!((text.as_str(), encoding.as_str()), ("name\nJosé", "UTF-8"));
+
+        let (text, encoding) = decode_text(b"Jos\xE9", Some("windows-1252")).unwrap();
+        assert_eq!((text.as_str(), encoding.as_str()), ("José", "windows-1252"));
+
+        assert!(decode_text(b"Jos\xE9", Some("utf-8")).is_err());
+        assert!(decode_text(b"name", Some("klingon")).is_err());
+    }
+
+    #[test]
+    fn csv_rows_are_trimmed_and_blank_rows_dropped() {
+        let data = parse_csv("\n name , email ,\nAda, ada@example.com,\n,,\n\"Hopper, Grace\",\n", b',').unwrap();
+
+        assert_eq!(data.headers, vec!["name", "email"]);
+        assert_eq!(data.rows, vec![
+            vec!["Ada".to_string(), "ada@example.com".to_string()],
+            vec!["Hopper, Grace".to_string()],
+        ]);
+        assert!(parse_csv("\n,,\n", b',').is_err());
+    }
+
+    #[test]
+    fn whole_numbers_lose_their_decimal_point() {
+        assert_eq!(cell_to_string(&Data::Float(20231234.0)), "20231234");
+        assert_eq!(cell_to_string(&Data::Float(2.5)), "2.5");
+        assert_eq!(cell_to_string(&Data::String("  Ada ".to_string())), "Ada");
+        assert_eq!(cell_to_string(&Data::Empty), "");
+    }
+
+    #[test]
+    fn roster_files_are_read_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv_path = dir.path().join("roster.TSV");
+        std::fs::write(&csv_path, "name\temail\nAda\tada@example.com\n").unwrap();
+        let xlsx_path = dir.path().join("roster.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Fall").unwrap();
+        sheet.write(0, 0, "name").unwrap();
+        sheet.write(0, 1, "student number").unwrap();
+        sheet.write(1, 0, "Ada").unwrap();
+        sheet.write(1, 1, 1234).unwrap();
+        workbook.save(&xlsx_path).unwrap();
+
+        let service = RosterFileService::new();
+        let csv = service.read_roster_file(&csv_path, &RosterFileOptions::default()).unwrap();
+        assert_eq!((csv.format, csv.delimiter), (RosterFileFormat::Csv, Some('\t')));
+        assert_eq!(csv.data.rows, vec![vec!["Ada".to_string(), "ada@example.com".to_string()]]);
+
+        let xlsx = service.read_roster_file(&xlsx_path, &RosterFileOptions::default()).unwrap();
+        assert_eq!((xlsx.format, xlsx.sheet_names.clone()), (RosterFileFormat::Xlsx, vec!["Fall".to_string()]));
+        assert_eq!(xlsx.data.rows, vec![vec!["Ada".to_string(), "1234".to_string()]]);
+
+        let missing_sheet = RosterFileOptions { sheet_name: Some("Spring".to_string()), ..Default::default() };
+        assert!(service.read_roster_file(&xlsx_path, &missing_sheet).is_err());
+        assert!(RosterFileFormat::from_path(Path::new("roster.pdf")).is_err());
+    }
+}
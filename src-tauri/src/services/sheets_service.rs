@@ -1,8 +1,9 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use crate::services::AuthService;
-use crate::database::models::{CreateStudent, CreateProject, Student, Project};
+use crate::services::{ApiClient, ApiProvider};
+use crate::database::models::{Assignment, CreateStudent, CreateProject, Student, Project};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SheetData {
@@ -19,14 +20,120 @@ pub struct StudentData {
     pub project_name: Option<String>,
     pub project_description: Option<String>,
     pub cohort: Option<String>,
+    pub team: Option<String>,
+    #[serde(default)]
+    pub source_row: Option<usize>, // row number within the range, headers are row 1
+    #[serde(default)]
+    pub assignment: Option<String>, // assignment title or id from the assignment column
+    #[serde(default)]
+    pub extra_projects: Vec<ProjectData>, // from SheetMapping::extra_projects
 }
 
+// A repository from one of the additional repository columns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectData {
+    pub name: Option<String>, // derived from the repository when absent
+    pub description: Option<String>,
+    pub github_url: String,
+    pub assignment_id: Option<i64>,
+}
+
+impl StudentData {
+    // Sheet row number of the row at `index`, falling back to its position when the source row is unknown
+    pub fn row_number(&self, index: usize) -> usize {
+        self.source_row.unwrap_or(index + 2) // +2 because we start from row 2 (after headers)
+    }
+}
+
+// Rows sharing a team name collapse into one team owning a single project
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamData {
+    pub name: String,
+    pub member_rows: Vec<usize>, // indices into the imported rows
+    pub project_name: Option<String>,
+    pub project_description: Option<String>,
+    pub github_url: Option<String>,
+}
+
+// In a dry run the counts and outcomes describe what would happen; record ids of created rows are provisional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ImportResult {
     pub students_imported: usize,
+    pub students_updated: usize,
     pub projects_imported: usize,
+    pub teams_imported: usize,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    pub dry_run: bool,
+    pub committed: bool, // false on a dry run, or when any issue rolled the whole import back
+    pub rows: Vec<ImportRowOutcome>, // one per imported row, in sheet order
+    pub teams: Vec<TeamOutcome>,
+    pub issues: Vec<ImportIssue>, // structured form of `errors`
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportAction {
+    #[serde(rename = "create")]
+    Create,
+    #[serde(rename = "update")]
+    Update,
+    #[serde(rename = "skip")]
+    Skip, // already on record with nothing to change
+    #[serde(rename = "fail")]
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordChange {
+    pub action: ImportAction,
+    pub record_id: Option<i64>,
+    pub changes: Vec<FieldChange>,
+}
+
+impl RecordChange {
+    pub fn failed() -> Self {
+        Self { action: ImportAction::Fail, record_id: None, changes: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowOutcome {
+    pub row: usize, // sheet row number, headers are row 1
+    pub student_name: String,
+    pub student: RecordChange,
+    pub projects: Vec<RecordChange>, // empty for team rows, see TeamOutcome
+    pub team: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamOutcome {
+    pub name: String,
+    pub team: RecordChange,
+    pub member_rows: Vec<usize>,
+    pub project: Option<RecordChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportIssue {
+    pub row: Option<usize>,
+    pub column: Option<String>, // the mapped field, e.g. "email"
+    pub reason: String,
+}
+
+impl std::fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.row {
+            Some(row) => write!(f, "Row {}: {}", row, self.reason),
+            None => write!(f, "{}", self.reason),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +145,19 @@ pub struct SheetMapping {
     pub project_name_column: Option<String>,
     pub project_description_column: Option<String>,
     pub cohort_column: Option<String>,
+    pub team_column: Option<String>,
+    #[serde(default)]
+    pub assignment_column: Option<String>, // one row per submission: the assignment each row's project belongs to
+    #[serde(default)]
+    pub extra_projects: Vec<ProjectColumns>, // further repository columns, e.g. one per assignment
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectColumns {
+    pub github_url_column: String,
+    pub project_name_column: Option<String>,
+    pub project_description_column: Option<String>,
+    pub assignment_id: Option<i64>, // defaults to the row's assignment, then the import's
 }
 
 impl Default for SheetMapping {
@@ -50,53 +170,66 @@ impl Default for SheetMapping {
             project_name_column: Some("Project Name".to_string()),
             project_description_column: Some("Project Description".to_string()),
             cohort_column: Some("Cohort".to_string()),
+            team_column: Some("Team".to_string()),
+            assignment_column: None,
+            extra_projects: Vec::new(),
         }
     }
 }
 
+const SHEETS_API_BASE: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const DEFAULT_RESULTS_TAB: &str = "r3viewer Results";
+const RESULT_HEADERS: [&str; 12] = [
+    "Student Name",
+    "Email",
+    "GitHub Username",
+    "Project",
+    "Score",
+    "Automated Score",
+    "Code Quality",
+    "Structure",
+    "Documentation",
+    "Functionality",
+    "Feedback",
+    "Last Exported",
+];
+// Columns of RESULT_HEADERS holding 0-100 scores
+const SCORE_COLUMNS: std::ops::Range<usize> = 4..10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetExportSummary {
+    pub tab_name: String,
+    pub sheet_id: i64,
+    pub tab_created: bool,
+    pub rows_updated: usize,
+    pub rows_appended: usize,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+}
+
+// One cell written back into a synced roster; the column is located by its header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RosterCellWrite {
+    pub row: usize, // row number within the range, headers are row 1
+    pub column: String,
+    pub value: String,
+}
+
 pub struct SheetsService {
-    auth_service: AuthService,
-    client: Option<reqwest::Client>,
+    api: ApiClient,
 }
 
 impl SheetsService {
-    pub fn new(auth_service: AuthService) -> Self {
-        Self {
-            auth_service,
-            client: Some(reqwest::Client::new()),
-        }
+    pub fn new(api: ApiClient) -> Self {
+        Self { api }
     }
 
     pub async fn get_sheet_data(&self, spreadsheet_id: &str, range: &str) -> Result<SheetData> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("HTTP client not initialized"))?;
-
-        let credentials = self.auth_service.get_stored_credentials()?;
-        let access_token = credentials.google_access_token
-            .ok_or_else(|| anyhow!("No Google access token available"))?;
-
-        let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
-            spreadsheet_id, range
-        );
-
-        let response = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            if response.status() == 401 {
-                // Try to refresh the token
-                let new_token = self.auth_service.refresh_google_token().await?;
-                return self.get_sheet_data_with_token(spreadsheet_id, range, &new_token).await;
-            } else {
-                return Err(anyhow!("Failed to fetch sheet data: {}", response.status()));
-            }
-        }
+        let mut url = reqwest::Url::parse(&format!("{}/{}/values", SHEETS_API_BASE, spreadsheet_id))?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Invalid spreadsheet URL"))?
+            .push(range);
 
-        let response_data: serde_json::Value = response.json().await?;
+        let response_data = self.sheets_request(reqwest::Method::GET, url.as_str(), None).await?;
         let values = response_data["values"]
             .as_array()
             .ok_or_else(|| anyhow!("No values found in sheet response"))?;
@@ -128,57 +261,24 @@ impl SheetsService {
         Ok(SheetData { headers, rows })
     }
 
-    async fn get_sheet_data_with_token(&self, spreadsheet_id: &str, range: &str, token: &str) -> Result<SheetData> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("HTTP client not initialized"))?;
-
-        let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
-            spreadsheet_id, range
-        );
-
-        let response = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", token))
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("Failed to fetch sheet data: {}", response.status()));
-        }
-
-        let response_data: serde_json::Value = response.json().await?;
-        let values = response_data["values"]
-            .as_array()
-            .ok_or_else(|| anyhow!("No values found in sheet response"))?;
-
-        if values.is_empty() {
-            return Err(anyhow!("Sheet is empty"));
-        }
-
-        let headers: Vec<String> = values[0]
-            .as_array()
-            .ok_or_else(|| anyhow!("Invalid header row"))?
-            .iter()
-            .map(|v| v.as_str().unwrap_or("").to_string())
-            .collect();
-
-        let rows: Vec<Vec<String>> = values[1..]
-            .iter()
-            .map(|row| {
-                row.as_array()
-                    .unwrap_or(&vec![])
-                    .iter()
-                    .map(|v| v.as_str().unwrap_or("").to_string())
-                    .collect()
-            })
-            .collect();
-
-        Ok(SheetData { headers, rows })
+    // Proposes a SheetMapping from header synonyms and cell contents, see column_mapping
+    pub fn suggest_mapping(&self, sheet_data: &SheetData) -> crate::services::MappingSuggestion {
+        crate::services::column_mapping::suggest_mapping(sheet_data)
     }
 
     pub fn parse_student_data(&self, sheet_data: &SheetData, mapping: &SheetMapping) -> Result<Vec<StudentData>> {
         let header_indices = self.build_header_indices(&sheet_data.headers, mapping)?;
+        let extra_indices = mapping.extra_projects
+            .iter()
+            .map(|columns| {
+                let url_index = self.find_header_index(&sheet_data.headers, &columns.github_url_column)
+                    .ok_or_else(|| anyhow!("Repository column '{}' not found", columns.github_url_column))?;
+                let optional = |column: &Option<String>| {
+                    column.as_ref().and_then(|c| self.find_header_index(&sheet_data.headers, c))
+                };
+                Ok((columns, url_index, optional(&columns.project_name_column), optional(&columns.project_description_column)))
+            })
+            .collect::<Result<Vec<_>>>()?;
         let mut students = Vec::new();
 
         for (row_index, row) in sheet_data.rows.iter().enumerate() {
@@ -209,6 +309,25 @@ impl SheetsService {
             let cohort = self.get_cell_value(row, header_indices.get("cohort"))
                 .filter(|s| !s.trim().is_empty());
 
+            let team = self.get_cell_value(row, header_indices.get("team"))
+                .filter(|s| !s.trim().is_empty());
+
+            let assignment = self.get_cell_value(row, header_indices.get("assignment"))
+                .filter(|s| !s.trim().is_empty());
+
+            let extra_projects = extra_indices
+                .iter()
+                .filter_map(|(columns, url_index, name_index, description_index)| {
+                    let github_url = self.get_cell_value(row, Some(url_index)).filter(|s| !s.trim().is_empty())?;
+                    Some(ProjectData {
+                        name: self.get_cell_value(row, name_index.as_ref()).filter(|s| !s.trim().is_empty()),
+                        description: self.get_cell_value(row, description_index.as_ref()).filter(|s| !s.trim().is_empty()),
+                        github_url,
+                        assignment_id: columns.assignment_id,
+                    })
+                })
+                .collect();
+
             students.push(StudentData {
                 name,
                 email,
@@ -217,6 +336,10 @@ impl SheetsService {
                 project_name,
                 project_description,
                 cohort,
+                team,
+                source_row: Some(row_index + 2),
+                assignment,
+                extra_projects,
             });
         }
 
@@ -224,37 +347,52 @@ impl SheetsService {
     }
 
     pub fn validate_student_data(&self, students: &[StudentData]) -> Result<Vec<String>> {
-        let mut errors = Vec::new();
+        Ok(self.validate_student_rows(students).iter().map(|issue| issue.to_string()).collect())
+    }
+
+    pub fn validate_student_rows(&self, students: &[StudentData]) -> Vec<ImportIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |row: usize, column: &str, reason: &str| issues.push(ImportIssue {
+            row: Some(row),
+            column: Some(column.to_string()),
+            reason: reason.to_string(),
+        });
 
         for (index, student) in students.iter().enumerate() {
-            let row_num = index + 2; // +2 because we start from row 2 (after headers)
+            let row_num = student.row_number(index);
 
             // Validate name
             if student.name.trim().is_empty() {
-                errors.push(format!("Row {}: Name is required", row_num));
+                issue(row_num, "name", "Name is required");
             }
 
             // Validate email format if provided
             if let Some(email) = &student.email {
                 if !email.trim().is_empty() && !self.is_valid_email(email) {
-                    errors.push(format!("Row {}: Invalid email format", row_num));
+                    issue(row_num, "email", "Invalid email format");
                 }
             }
 
             // Validate GitHub URL format if provided
             if let Some(github_url) = &student.github_url {
                 if !github_url.trim().is_empty() && !self.is_valid_github_url(github_url) {
-                    errors.push(format!("Row {}: Invalid GitHub URL format", row_num));
+                    issue(row_num, "github_url", "Invalid GitHub URL format");
+                }
+            }
+
+            for project in &student.extra_projects {
+                if !self.is_valid_github_url(&project.github_url) {
+                    issue(row_num, "github_url", &format!("Invalid GitHub URL format: {}", project.github_url));
                 }
             }
 
             // Check if either GitHub username or URL is provided
-            if student.github_username.is_none() && student.github_url.is_none() {
-                errors.push(format!("Row {}: Either GitHub username or GitHub URL is required", row_num));
+            if student.github_username.is_none() && student.github_url.is_none() && student.extra_projects.is_empty() {
+                issue(row_num, "github_username", "Either GitHub username or GitHub URL is required");
             }
         }
 
-        Ok(errors)
+        issues
     }
 
     pub fn convert_to_create_students(&self, students: &[StudentData]) -> Vec<CreateStudent> {
@@ -265,86 +403,345 @@ impl SheetsService {
                 email: student.email.clone(),
                 github_username: student.github_username.clone(),
                 cohort: student.cohort.clone(),
+                cohort_id: None, // Resolved against the course's cohorts on import
             })
             .collect()
     }
 
-    pub fn convert_to_create_projects(&self, students: &[StudentData], student_ids: &HashMap<String, i64>) -> Vec<CreateProject> {
-        let mut projects = Vec::new();
+    // `student_ids` is parallel to `students`; rows whose student failed to import are None.
+    // Each project is paired with the index of the row it came from; a row can yield several.
+    // A row's assignment label must already be known to resolve, see resolve_assignment.
+    pub fn convert_to_create_projects(
+        &self,
+        students: &[StudentData],
+        student_ids: &[Option<i64>],
+        assignment_id: Option<i64>,
+        assignments: &[Assignment],
+    ) -> Vec<(usize, CreateProject)> {
+        let mut projects: Vec<(usize, CreateProject)> = Vec::new();
+
+        // Team rows are imported as one group project per team, see group_teams
+        for (row, student) in students.iter().enumerate().filter(|(_, s)| s.team.is_none()) {
+            let Some(student_id) = student_ids.get(row).copied().flatten() else {
+                continue;
+            };
+            let row_assignment_id = student.assignment
+                .as_deref()
+                .and_then(|label| resolve_assignment(label, assignments))
+                .or(assignment_id);
+
+            let github_url = student.github_url.clone().or_else(|| {
+                match (&student.github_username, &student.project_name) {
+                    (Some(username), Some(project_name)) => Some(format!("https://github.com/{}/{}", username, project_name)),
+                    _ => None,
+                }
+            });
+            let primary = github_url.map(|url| ProjectData {
+                name: student.project_name.clone(),
+                description: student.project_description.clone(),
+                github_url: url,
+                assignment_id: None,
+            });
+
+            for project in primary.iter().chain(&student.extra_projects) {
+                let Some(name) = project.name.clone().or_else(|| repository_name(&project.github_url)) else {
+                    continue;
+                };
+                let assignment_id = project.assignment_id.or(row_assignment_id);
+
+                // The same repository listed twice for one assignment is one project
+                let duplicate = projects.iter().any(|(r, p)| {
+                    *r == row && p.github_url.eq_ignore_ascii_case(&project.github_url) && p.assignment_id == assignment_id
+                });
+                if duplicate {
+                    continue;
+                }
 
-        for student in students {
-            if let (Some(project_name), Some(&student_id)) = (&student.project_name, student_ids.get(&student.name)) {
-                let github_url = student.github_url.as_ref()
-                    .or_else(|| {
-                        student.github_username.as_ref().map(|username| {
-                            format!("https://github.com/{}/{}", username, project_name)
-                        })
-                    });
+                projects.push((row, CreateProject {
+                    student_id,
+                    name,
+                    description: project.description.clone(),
+                    github_url: project.github_url.clone(),
+                    technology_stack: None, // Will be detected later
+                    assignment_id,
+                    team_id: None,
+                }));
+            }
+        }
+
+        projects
+    }
+
+    pub fn group_teams(&self, students: &[StudentData]) -> Vec<TeamData> {
+        let mut teams: Vec<TeamData> = Vec::new();
 
-                if let Some(url) = github_url {
-                    projects.push(CreateProject {
-                        student_id,
-                        name: project_name.clone(),
-                        description: student.project_description.clone(),
-                        github_url: url,
-                        technology_stack: None, // Will be detected later
+        for (row, student) in students.iter().enumerate() {
+            let Some(team_name) = student.team.as_ref() else {
+                continue;
+            };
+
+            let index = match teams.iter().position(|t| t.name.eq_ignore_ascii_case(team_name.trim())) {
+                Some(index) => index,
+                None => {
+                    teams.push(TeamData {
+                        name: team_name.trim().to_string(),
+                        member_rows: Vec::new(),
+                        project_name: None,
+                        project_description: None,
+                        github_url: None,
                     });
+                    teams.len() - 1
                 }
+            };
+
+            let team = &mut teams[index];
+            team.member_rows.push(row);
+
+            // The first row that names the project wins; later rows usually repeat it
+            if team.project_name.is_none() {
+                team.project_name = student.project_name.clone();
+            }
+            if team.project_description.is_none() {
+                team.project_description = student.project_description.clone();
+            }
+            if team.github_url.is_none() {
+                team.github_url = student.github_url.clone().or_else(|| {
+                    match (&student.github_username, &student.project_name) {
+                        (Some(username), Some(project_name)) => {
+                            Some(format!("https://github.com/{}/{}", username, project_name))
+                        }
+                        _ => None,
+                    }
+                });
             }
         }
 
-        projects
+        for team in &mut teams {
+            if team.project_name.is_none() {
+                team.project_name = team.github_url.as_deref().and_then(repository_name);
+            }
+        }
+
+        teams
     }
 
+    // Upserts results into a dedicated tab (created on first export) keyed by student email, or name
+    // when there is no email, plus project. Rows belonging to other students are left untouched.
     pub async fn export_results_to_sheet(
         &self,
         spreadsheet_id: &str,
-        range: &str,
+        tab_name: Option<&str>,
         results: &[ExportRow],
-    ) -> Result<()> {
-        let client = self.client.as_ref()
-            .ok_or_else(|| anyhow!("HTTP client not initialized"))?;
+    ) -> Result<SheetExportSummary> {
+        let tab_name = tab_name.map(str::trim).filter(|t| !t.is_empty()).unwrap_or(DEFAULT_RESULTS_TAB);
+        let exported_at = chrono::Utc::now();
+
+        // Tab id and existing conditional format rules, so re-exports replace our rules instead of stacking them
+        let metadata = self.sheets_request(
+            reqwest::Method::GET,
+            &format!("{}/{}?fields=sheets(properties(sheetId,title),conditionalFormats(ranges,booleanRule(condition(type))))", SHEETS_API_BASE, spreadsheet_id),
+            None,
+        ).await?;
+
+        let existing_sheet = metadata["sheets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|sheet| sheet["properties"]["title"].as_str() == Some(tab_name));
+
+        let (sheet_id, stale_rules, created) = match existing_sheet {
+            Some(sheet) => {
+                // The API leaves out a sheetId of 0, as it does every default value
+                let sheet_id = sheet["properties"]["sheetId"].as_i64().unwrap_or(0);
+                (sheet_id, export_rule_indices(sheet, sheet_id), false)
+            }
+            None => {
+                let reply = self.sheets_request(
+                    reqwest::Method::POST,
+                    &format!("{}/{}:batchUpdate", SHEETS_API_BASE, spreadsheet_id),
+                    Some(serde_json::json!({
+                        "requests": [{ "addSheet": { "properties": { "title": tab_name } } }]
+                    })),
+                ).await?;
+                let sheet_id = reply["replies"][0]["addSheet"]["properties"]["sheetId"]
+                    .as_i64()
+                    .ok_or_else(|| anyhow!("Sheets did not return the new tab's id"))?;
+                (sheet_id, Vec::new(), true)
+            }
+        };
+
+        let existing_rows = if created {
+            Vec::new()
+        } else {
+            let mut url = reqwest::Url::parse(&format!("{}/{}/values", SHEETS_API_BASE, spreadsheet_id))?;
+            url.path_segments_mut()
+                .map_err(|_| anyhow!("Invalid spreadsheet URL"))?
+                .push(&format!("'{}'", tab_name.replace('\'', "''")));
+            let values = self.sheets_request(reqwest::Method::GET, url.as_str(), None).await?;
+            values["values"]
+                .as_array()
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| {
+                            row.as_array()
+                                .map(|cells| cells.iter().map(|v| v.as_str().unwrap_or("").to_string()).collect())
+                                .unwrap_or_default()
+                        })
+                        .collect::<Vec<Vec<String>>>()
+                })
+                .unwrap_or_default()
+        };
+
+        // Only a tab an earlier export wrote (or an empty one) is written to; anything else is the user's data
+        if !existing_rows.is_empty() && !is_results_header(&existing_rows[0]) {
+            return Err(anyhow!(
+                "Tab '{}' already holds data that was not exported from r3viewer; choose another tab name",
+                tab_name
+            ));
+        }
 
-        let credentials = self.auth_service.get_stored_credentials()?;
-        let access_token = credentials.google_access_token
-            .ok_or_else(|| anyhow!("No Google access token available"))?;
+        let (name_col, email_col, project_col) = (0, 1, 3);
+        let row_key = |name: &str, email: &str, project: &str| {
+            let student = if email.trim().is_empty() { name } else { email };
+            format!("{}\u{1f}{}", student.trim().to_lowercase(), project.trim().to_lowercase())
+        };
 
-        let values: Vec<Vec<String>> = results
-            .iter()
-            .map(|row| vec![
-                row.student_name.clone(),
-                row.project_name.clone(),
-                row.total_score.map(|s| s.to_string()).unwrap_or_default(),
-                row.code_quality_score.map(|s| s.to_string()).unwrap_or_default(),
-                row.structure_score.map(|s| s.to_string()).unwrap_or_default(),
-                row.documentation_score.map(|s| s.to_string()).unwrap_or_default(),
-                row.functionality_score.map(|s| s.to_string()).unwrap_or_default(),
-                row.feedback.clone().unwrap_or_default(),
-            ])
-            .collect();
+        let mut existing_keys: HashMap<String, usize> = HashMap::new();
+        for (index, row) in existing_rows.iter().enumerate().skip(1) {
+            let cell = |i: usize| row.get(i).map(String::as_str).unwrap_or("");
+            existing_keys.entry(row_key(cell(name_col), cell(email_col), cell(project_col))).or_insert(index);
+        }
 
-        let update_data = serde_json::json!({
-            "values": values
-        });
+        let timestamp = exported_at.format("%Y-%m-%d %H:%M UTC").to_string();
+        let mut next_row = existing_rows.len().max(1);
+        let mut rows_updated = 0;
+        let mut rows_appended = 0;
+        let mut requests = vec![
+            update_cells_request(sheet_id, 0, vec![header_cells()]),
+            serde_json::json!({
+                "updateSheetProperties": {
+                    "properties": { "sheetId": sheet_id, "gridProperties": { "frozenRowCount": 1 } },
+                    "fields": "gridProperties.frozenRowCount"
+                }
+            }),
+        ];
+
+        for result in results {
+            let key = row_key(&result.student_name, result.student_email.as_deref().unwrap_or(""), &result.project_name);
+            let row_index = match existing_keys.get(&key) {
+                Some(index) => {
+                    rows_updated += 1;
+                    *index
+                }
+                None => {
+                    rows_appended += 1;
+                    existing_keys.insert(key, next_row);
+                    next_row += 1;
+                    next_row - 1
+                }
+            };
+            requests.push(update_cells_request(sheet_id, row_index, vec![result_cells(result, &timestamp)]));
+        }
+
+        // Score columns: integer format and red/amber/green bands
+        let score_range = score_range(sheet_id);
+        requests.push(serde_json::json!({
+            "repeatCell": {
+                "range": score_range,
+                "cell": { "userEnteredFormat": { "numberFormat": { "type": "NUMBER", "pattern": "0" } } },
+                "fields": "userEnteredFormat.numberFormat"
+            }
+        }));
+        for index in stale_rules.into_iter().rev() {
+            requests.push(serde_json::json!({ "deleteConditionalFormatRule": { "sheetId": sheet_id, "index": index } }));
+        }
+        for (condition, values, color) in score_bands() {
+            requests.push(serde_json::json!({
+                "addConditionalFormatRule": {
+                    "index": 0,
+                    "rule": {
+                        "ranges": [score_range],
+                        "booleanRule": {
+                            "condition": { "type": condition, "values": values },
+                            "format": { "backgroundColor": color }
+                        }
+                    }
+                }
+            }));
+        }
+        requests.push(serde_json::json!({
+            "autoResizeDimensions": {
+                "dimensions": { "sheetId": sheet_id, "dimension": "COLUMNS", "startIndex": 0, "endIndex": RESULT_HEADERS.len() }
+            }
+        }));
+
+        self.sheets_request(
+            reqwest::Method::POST,
+            &format!("{}/{}:batchUpdate", SHEETS_API_BASE, spreadsheet_id),
+            Some(serde_json::json!({ "requests": requests })),
+        ).await?;
+
+        Ok(SheetExportSummary {
+            tab_name: tab_name.to_string(),
+            sheet_id,
+            tab_created: created,
+            rows_updated,
+            rows_appended,
+            exported_at,
+        })
+    }
+
+    // Writes single cells of a roster range in place, leaving every other cell alone. Returns the number of cells written.
+    pub async fn write_roster_values(
+        &self,
+        spreadsheet_id: &str,
+        range: &str,
+        headers: &[String],
+        writes: &[RosterCellWrite],
+    ) -> Result<usize> {
+        if writes.is_empty() {
+            return Ok(0);
+        }
 
-        let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueInputOption=RAW",
-            spreadsheet_id, range
-        );
-
-        let response = client
-            .put(&url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("Content-Type", "application/json")
-            .json(&update_data)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("Failed to export results: {}", response.status()));
+        let (tab, start_column, start_row) = split_a1_range(range);
+        let mut data = Vec::new();
+        for write in writes {
+            let index = self.find_header_index(headers, &write.column)
+                .ok_or_else(|| anyhow!("Column '{}' not found in the roster", write.column))?;
+            let cell = format!("{}{}", column_letters(start_column + index), start_row + write.row - 1);
+            let target = match &tab {
+                Some(tab) => format!("{}!{}", tab, cell),
+                None => cell,
+            };
+            data.push(serde_json::json!({ "range": target, "values": [[write.value]] }));
+        }
+
+        self.sheets_request(
+            reqwest::Method::POST,
+            &format!("{}/{}/values:batchUpdate", SHEETS_API_BASE, spreadsheet_id),
+            Some(serde_json::json!({ "valueInputOption": "USER_ENTERED", "data": data })),
+        ).await?;
+
+        Ok(writes.len())
+    }
+
+    // Token refresh and the 401 retry are handled by ApiClient; this adds Sheets-specific error messages
+    async fn sheets_request(&self, method: reqwest::Method, url: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let response = self.api.send(ApiProvider::Google, method, url, body.as_ref()).await?;
+        let status = response.status();
+
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(anyhow!("Google rejected the stored credentials. Sign in to Google again."));
+        }
+        if status == reqwest::StatusCode::FORBIDDEN {
+            return Err(anyhow!("Google denied access to the spreadsheet. Sign in to Google again to grant write access, and check the sheet is shared with you."));
+        }
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Google Sheets request failed: {} {}", status, detail));
         }
 
-        Ok(())
+        Ok(response.json().await?)
     }
 
     fn build_header_indices(&self, headers: &[String], mapping: &SheetMapping) -> Result<HashMap<String, usize>> {
@@ -392,6 +789,18 @@ impl SheetsService {
             }
         }
 
+        if let Some(team_col) = &mapping.team_column {
+            if let Some(index) = self.find_header_index(headers, team_col) {
+                indices.insert("team".to_string(), index);
+            }
+        }
+
+        if let Some(assignment_col) = &mapping.assignment_column {
+            if let Some(index) = self.find_header_index(headers, assignment_col) {
+                indices.insert("assignment".to_string(), index);
+            }
+        }
+
         Ok(indices)
     }
 
@@ -434,4 +843,307 @@ pub struct ExportRow {
     pub documentation_score: Option<i32>,
     pub functionality_score: Option<i32>,
     pub feedback: Option<String>,
-} 
\ No newline at end of file
+    // Filled by export_project_results; defaulted so older callers can still send the eight fields above
+    #[serde(default)]
+    pub student_id: Option<i64>,
+    #[serde(default)]
+    pub student_email: Option<String>,
+    #[serde(default)]
+    pub github_username: Option<String>,
+    #[serde(default)]
+    pub final_score: Option<i32>,
+    #[serde(default)]
+    pub criteria: Vec<CriterionScore>,
+    #[serde(default)]
+    pub comments: Option<String>,
+    #[serde(default)]
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+impl ExportRow {
+    // The released grade when there is one, otherwise the automated total. A final grade the
+    // reviewer has not released yet never leaves the app.
+    pub fn score(&self) -> Option<i32> {
+        self.released_score().or(self.total_score)
+    }
+
+    pub fn released_score(&self) -> Option<i32> {
+        self.released_at.and(self.final_score)
+    }
+
+    pub fn feedback_with_comments(&self) -> Option<String> {
+        let parts: Vec<&str> = [self.feedback.as_deref(), self.comments.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.trim().is_empty())
+            .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("\n\n"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriterionScore {
+    pub criterion: String,
+    pub score: Option<i32>,
+    pub max_score: Option<i32>,
+}
+
+// "owner/repo" URLs name their project after the repository
+pub fn repository_name(github_url: &str) -> Option<String> {
+    regex::Regex::new(r"(?i)github\.com/[^/\s]+/([^/\s?#]+?)(?:\.git)?/?(?:[?#].*)?$")
+        .unwrap()
+        .captures(github_url.trim())
+        .and_then(|cap| cap.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+// Matches a roster's assignment label to an assignment by id ("12" or "#12") or by title, ignoring case
+pub fn resolve_assignment(label: &str, assignments: &[Assignment]) -> Option<i64> {
+    let label = label.trim();
+    if let Ok(id) = label.trim_start_matches('#').parse::<i64>() {
+        if assignments.iter().any(|a| a.id == id) {
+            return Some(id);
+        }
+    }
+
+    assignments
+        .iter()
+        .find(|a| a.title.trim().eq_ignore_ascii_case(label))
+        .map(|a| a.id)
+}
+
+// Splits A1 notation such as 'Roster'!B3:H into the tab (as written), the 0-based first column and the 1-based first row.
+// A bare tab name or an open range like A:H starts at column A, row 1.
+fn split_a1_range(range: &str) -> (Option<String>, usize, usize) {
+    let cell_ref = regex::Regex::new(r"^([A-Za-z]{1,3})?(\d+)?(:[A-Za-z]*\d*)?$").unwrap();
+    let (tab, cells) = match range.rsplit_once('!') {
+        Some((tab, cells)) => (Some(tab.to_string()), cells),
+        None if cell_ref.is_match(range) => (None, range),
+        None => return (Some(range.to_string()), 0, 1),
+    };
+
+    let Some(captures) = cell_ref.captures(cells) else {
+        return (tab, 0, 1);
+    };
+    let column = captures.get(1).map_or(0, |letters| {
+        letters.as_str().to_uppercase().bytes().fold(0, |acc, b| acc * 26 + (b - b'A' + 1) as usize) - 1
+    });
+    let row = captures.get(2).and_then(|digits| digits.as_str().parse().ok()).unwrap_or(1);
+
+    (tab, column, row)
+}
+
+fn column_letters(mut index: usize) -> String {
+    let mut letters = String::new();
+    loop {
+        letters.insert(0, (b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters
+}
+
+fn update_cells_request(sheet_id: i64, row_index: usize, rows: Vec<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({
+        "updateCells": {
+            "start": { "sheetId": sheet_id, "rowIndex": row_index, "columnIndex": 0 },
+            "rows": rows,
+            "fields": "userEnteredValue,userEnteredFormat.textFormat"
+        }
+    })
+}
+
+fn header_cells() -> serde_json::Value {
+    let values: Vec<serde_json::Value> = RESULT_HEADERS
+        .iter()
+        .map(|title| serde_json::json!({
+            "userEnteredValue": { "stringValue": title },
+            "userEnteredFormat": { "textFormat": { "bold": true } }
+        }))
+        .collect();
+    serde_json::json!({ "values": values })
+}
+
+fn result_cells(row: &ExportRow, timestamp: &str) -> serde_json::Value {
+    let text = |value: &str| serde_json::json!({ "userEnteredValue": { "stringValue": value } });
+    let number = |value: Option<i32>| match value {
+        Some(value) => serde_json::json!({ "userEnteredValue": { "numberValue": value } }),
+        None => serde_json::json!({}),
+    };
+
+    serde_json::json!({
+        "values": [
+            text(&row.student_name),
+            text(row.student_email.as_deref().unwrap_or("")),
+            text(row.github_username.as_deref().unwrap_or("")),
+            text(&row.project_name),
+            number(row.score()),
+            number(row.total_score),
+            number(row.code_quality_score),
+            number(row.structure_score),
+            number(row.documentation_score),
+            number(row.functionality_score),
+            text(row.feedback_with_comments().as_deref().unwrap_or("")),
+            text(timestamp),
+        ]
+    })
+}
+
+fn score_range(sheet_id: i64) -> serde_json::Value {
+    serde_json::json!({
+        "sheetId": sheet_id,
+        "startRowIndex": 1,
+        "startColumnIndex": SCORE_COLUMNS.start,
+        "endColumnIndex": SCORE_COLUMNS.end
+    })
+}
+
+// Positions of the rules an earlier export added: one of our band conditions over exactly the
+// score range. Rules the user added to the tab are left in place.
+fn export_rule_indices(sheet: &serde_json::Value, sheet_id: i64) -> Vec<usize> {
+    let ours = score_range(sheet_id);
+    let field = |range: &serde_json::Value, name: &str| range[name].as_i64().unwrap_or(0);
+    let same_range = |range: &serde_json::Value| {
+        ["sheetId", "startRowIndex", "startColumnIndex", "endColumnIndex"]
+            .iter()
+            .all(|name| field(range, name) == field(&ours, name))
+            && range.get("endRowIndex").is_none()
+    };
+    let bands: Vec<&str> = score_bands().iter().map(|(condition, _, _)| *condition).collect();
+
+    sheet["conditionalFormats"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, rule)| {
+            let ranges = rule["ranges"].as_array().map(Vec::as_slice).unwrap_or_default();
+            let condition = rule["booleanRule"]["condition"]["type"].as_str().unwrap_or("");
+            ranges.len() == 1 && same_range(&ranges[0]) && bands.contains(&condition)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn is_results_header(row: &[String]) -> bool {
+    row.len() >= RESULT_HEADERS.len()
+        && RESULT_HEADERS.iter().zip(row).all(|(ours, cell)| cell.trim().eq_ignore_ascii_case(ours))
+}
+
+// Red below 50, amber below 70, green from 70
+fn score_bands() -> Vec<(&'static str, serde_json::Value, serde_json::Value)> {
+    vec![
+        (
+            "NUMBER_LESS",
+            serde_json::json!([{ "userEnteredValue": "50" }]),
+            serde_json::json!({ "red": 0.96, "green": 0.8, "blue": 0.8 }),
+        ),
+        (
+            "NUMBER_BETWEEN",
+            serde_json::json!([{ "userEnteredValue": "50" }, { "userEnteredValue": "69.99" }]),
+            serde_json::json!({ "red": 1.0, "green": 0.95, "blue": 0.8 }),
+        ),
+        (
+            "NUMBER_GREATER_THAN_EQ",
+            serde_json::json!([{ "userEnteredValue": "70" }]),
+            serde_json::json!({ "red": 0.85, "green": 0.94, "blue": 0.83 }),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_rule_indices_only_picks_the_exports_own_rules() {
+        let rule = |range: serde_json::Value, condition: &str| serde_json::json!({
+            "ranges": [range],
+            "booleanRule": { "condition": { "type": condition } }
+        });
+        // Sheets omits a sheetId of 0 from the ranges it returns
+        let ours = serde_json::json!({ "startRowIndex": 1, "startColumnIndex": 4, "endColumnIndex": 10 });
+        let sheet = serde_json::json!({
+            "conditionalFormats": [
+                rule(ours.clone(), "NUMBER_LESS"),
+                rule(serde_json::json!({ "startRowIndex": 1, "startColumnIndex": 0, "endColumnIndex": 2 }), "NUMBER_LESS"),
+                rule(ours.clone(), "TEXT_CONTAINS"),
+                rule(serde_json::json!({ "startRowIndex": 1, "endRowIndex": 20, "startColumnIndex": 4, "endColumnIndex": 10 }), "NUMBER_BETWEEN"),
+                rule(ours, "NUMBER_GREATER_THAN_EQ"),
+            ]
+        });
+
+        assert_eq!(export_rule_indices(&sheet, 0), vec![0, 4]);
+        assert!(export_rule_indices(&sheet, 7).is_empty());
+        assert!(export_rule_indices(&serde_json::json!({}), 0).is_empty());
+    }
+
+    #[test]
+    fn is_results_header_requires_the_export_header() {
+        let ours: Vec<String> = RESULT_HEADERS.iter().map(|h| h.to_lowercase()).collect();
+        assert!(is_results_header(&ours));
+
+        let mut extended = ours.clone();
+        extended.push("Notes".to_string());
+        assert!(is_results_header(&extended));
+
+        assert!(!is_results_header(&ours[..3]));
+        assert!(!is_results_header(&["Name".to_string(), "Email".to_string()]));
+    }
+
+    #[test]
+    fn repository_name_takes_the_last_path_segment() {
+        assert_eq!(repository_name("https://github.com/ada/engine").as_deref(), Some("engine"));
+        assert_eq!(repository_name(" https://GitHub.com/ada/engine.git/ ").as_deref(), Some("engine"));
+        assert_eq!(repository_name("https://github.com/ada/engine?tab=readme").as_deref(), Some("engine"));
+        assert_eq!(repository_name("https://github.com/ada"), None);
+        assert_eq!(repository_name("engine"), None);
+    }
+
+    #[test]
+    fn resolve_assignment_matches_id_then_title() {
+        let assignment = |id: i64, title: &str| Assignment {
+            id,
+            course_id: 1,
+            cohort_id: None,
+            title: title.to_string(),
+            description: None,
+            due_at: None,
+            rubric: None,
+            starter_repo_url: None,
+            allowed_stacks: None,
+            created_at: chrono::Utc::now(),
+        };
+        let assignments = vec![assignment(4, "Capstone"), assignment(7, "2048")];
+
+        assert_eq!(resolve_assignment("#4", &assignments), Some(4));
+        assert_eq!(resolve_assignment(" capstone ", &assignments), Some(4));
+        // A number that is no assignment's id can still be a title
+        assert_eq!(resolve_assignment("2048", &assignments), Some(7));
+        assert_eq!(resolve_assignment("Midterm", &assignments), None);
+    }
+
+    #[test]
+    fn split_a1_range_finds_tab_and_start_cell() {
+        assert_eq!(split_a1_range("'Roster'!A1:H"), (Some("'Roster'".to_string()), 0, 1));
+        assert_eq!(split_a1_range("Roster!C3:H40"), (Some("Roster".to_string()), 2, 3));
+        assert_eq!(split_a1_range("A:H"), (None, 0, 1));
+        assert_eq!(split_a1_range("Roster"), (Some("Roster".to_string()), 0, 1));
+    }
+
+    #[test]
+    fn column_letters_round_trips_past_z() {
+        assert_eq!(column_letters(0), "A");
+        assert_eq!(column_letters(25), "Z");
+        assert_eq!(column_letters(26), "AA");
+        assert_eq!(column_letters(701), "ZZ");
+        assert_eq!(column_letters(702), "AAA");
+    }
+}
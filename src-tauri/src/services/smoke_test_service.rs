@@ -0,0 +1,319 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const DEFAULT_STEP_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeStep {
+    pub name: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub headers: Option<HashMap<String, String>>,
+    pub body: Option<serde_json::Value>,
+    pub expected_status: u16,
+    #[serde(default)] // optional in the request payload
+    pub json_assertions: Vec<JsonAssertion>,
+    pub max_response_time_ms: Option<u64>,
+}
+
+// `path` uses dotted notation with optional indices, e.g. "data.items[0].id".
+// Without `equals` the assertion only checks that the value is present and not null.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonAssertion {
+    pub path: String,
+    pub equals: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeStepResult {
+    pub name: String,
+    pub method: String,
+    pub path: String,
+    pub passed: bool,
+    pub status: Option<u16>,
+    pub response_time_ms: Option<u64>,
+    pub failures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmokeTestReport {
+    pub base_url: String,
+    pub total_steps: usize,
+    pub passed_steps: usize,
+    pub steps: Vec<SmokeStepResult>,
+}
+
+impl SmokeTestReport {
+    pub fn passed(&self) -> bool {
+        self.total_steps > 0 && self.passed_steps == self.total_steps
+    }
+
+    pub fn pass_rate(&self) -> f64 {
+        if self.total_steps == 0 {
+            return 0.0;
+        }
+        self.passed_steps as f64 / self.total_steps as f64
+    }
+
+    // One report over several scenarios' runs, e.g. the latest run of each, so feature completeness
+    // reflects every scenario rather than whichever ran last
+    pub fn combine(reports: Vec<SmokeTestReport>) -> Option<SmokeTestReport> {
+        let base_url = reports.first()?.base_url.clone();
+        Some(reports.into_iter().fold(
+            SmokeTestReport { base_url, total_steps: 0, passed_steps: 0, steps: Vec::new() },
+            |mut combined, report| {
+                combined.total_steps += report.total_steps;
+                combined.passed_steps += report.passed_steps;
+                combined.steps.extend(report.steps);
+                combined
+            },
+        ))
+    }
+}
+
+pub struct SmokeTestService {
+    client: reqwest::Client,
+}
+
+impl SmokeTestService {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(DEFAULT_STEP_TIMEOUT_SECS))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub async fn run_scenario(&self, base_url: &str, steps: &[SmokeStep]) -> Result<SmokeTestReport> {
+        if steps.is_empty() {
+            return Err(anyhow!("Smoke scenario has no steps"));
+        }
+
+        let mut results = Vec::new();
+        for step in steps {
+            results.push(self.run_step(base_url, step).await);
+        }
+
+        let passed_steps = results.iter().filter(|r| r.passed).count();
+
+        Ok(SmokeTestReport {
+            base_url: base_url.to_string(),
+            total_steps: results.len(),
+            passed_steps,
+            steps: results,
+        })
+    }
+
+    async fn run_step(&self, base_url: &str, step: &SmokeStep) -> SmokeStepResult {
+        let mut result = SmokeStepResult {
+            name: step.name.clone().unwrap_or_else(|| format!("{} {}", step.method, step.path)),
+            method: step.method.to_uppercase(),
+            path: step.path.clone(),
+            passed: false,
+            status: None,
+            response_time_ms: None,
+            failures: Vec::new(),
+        };
+
+        let method = match reqwest::Method::from_bytes(step.method.to_uppercase().as_bytes()) {
+            Ok(method) => method,
+            Err(_) => {
+                result.failures.push(format!("Unsupported HTTP method '{}'", step.method));
+                return result;
+            }
+        };
+
+        let url = format!("{}/{}", base_url.trim_end_matches('/'), step.path.trim_start_matches('/'));
+        let mut request = self.client.request(method, &url);
+
+        if let Some(headers) = &step.headers {
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+        }
+
+        if let Some(body) = &step.body {
+            request = request.json(body);
+        }
+
+        let started = Instant::now();
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                result.failures.push(format!("Request failed: {}", e));
+                return result;
+            }
+        };
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        let status = response.status().as_u16();
+        result.status = Some(status);
+        result.response_time_ms = Some(elapsed_ms);
+
+        if status != step.expected_status {
+            result.failures.push(format!("Expected status {}, got {}", step.expected_status, status));
+        }
+
+        if let Some(budget) = step.max_response_time_ms {
+            if elapsed_ms > budget {
+                result.failures.push(format!("Response took {}ms, budget is {}ms", elapsed_ms, budget));
+            }
+        }
+
+        if !step.json_assertions.is_empty() {
+            match response.json::<serde_json::Value>().await {
+                Ok(body) => {
+                    for assertion in &step.json_assertions {
+                        if let Some(failure) = self.check_assertion(&body, assertion) {
+                            result.failures.push(failure);
+                        }
+                    }
+                }
+                Err(e) => result.failures.push(format!("Response body is not valid JSON: {}", e)),
+            }
+        }
+
+        result.passed = result.failures.is_empty();
+        result
+    }
+
+    fn check_assertion(&self, body: &serde_json::Value, assertion: &JsonAssertion) -> Option<String> {
+        let actual = self.resolve_json_path(body, &assertion.path);
+
+        match (&assertion.equals, actual) {
+            (_, None) | (None, Some(serde_json::Value::Null)) => {
+                Some(format!("Expected a value at '{}'", assertion.path))
+            }
+            (Some(expected), Some(actual)) if expected != actual => {
+                Some(format!("Expected '{}' to equal {}, got {}", assertion.path, expected, actual))
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve_json_path<'a>(&self, body: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+        let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+        if path.is_empty() {
+            return Some(body);
+        }
+
+        static SEGMENT_PATTERN: OnceLock<regex::Regex> = OnceLock::new();
+        static INDEX_PATTERN: OnceLock<regex::Regex> = OnceLock::new();
+        let segment_pattern = SEGMENT_PATTERN.get_or_init(|| regex::Regex::new(r"^([^\[\]]*)((?:\[\d+\])*)$").unwrap());
+        let index_pattern = INDEX_PATTERN.get_or_init(|| regex::Regex::new(r"\[(\d+)\]").unwrap());
+
+        let mut current = body;
+        for segment in path.split('.') {
+            let captures = segment_pattern.captures(segment)?;
+
+            let key = captures.get(1).map(|m| m.as_str()).unwrap_or("");
+            if !key.is_empty() {
+                current = match current {
+                    serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+                    _ => current.get(key)?,
+                };
+            }
+
+            for index in index_pattern.captures_iter(captures.get(2).map(|m| m.as_str()).unwrap_or("")) {
+                current = current.get(index[1].parse::<usize>().ok()?)?;
+            }
+        }
+
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn body() -> serde_json::Value {
+        json!({
+            "data": {
+                "items": [{ "id": 1, "tags": ["a", "b"] }, { "id": 2, "tags": [] }],
+                "total": 2,
+                "cursor": null
+            }
+        })
+    }
+
+    #[test]
+    fn combined_reports_count_every_scenario() {
+        let report = |total_steps: usize, passed_steps: usize| SmokeTestReport {
+            base_url: "http://localhost:3000".to_string(),
+            total_steps,
+            passed_steps,
+            steps: Vec::new(),
+        };
+
+        let combined = SmokeTestReport::combine(vec![report(2, 2), report(3, 1)]).unwrap();
+        assert_eq!((combined.total_steps, combined.passed_steps), (5, 3));
+        assert!(!combined.passed());
+        assert!(SmokeTestReport::combine(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn resolves_dotted_paths_and_indices() {
+        let service = SmokeTestService::new();
+        let body = body();
+
+        assert_eq!(service.resolve_json_path(&body, "data.total"), Some(&json!(2)));
+        assert_eq!(service.resolve_json_path(&body, "data.items[1].id"), Some(&json!(2)));
+        assert_eq!(service.resolve_json_path(&body, "data.items[0].tags[1]"), Some(&json!("b")));
+        assert_eq!(service.resolve_json_path(&body, "data.items.0.id"), Some(&json!(1)));
+        assert_eq!(service.resolve_json_path(&body, "$.data.total"), Some(&json!(2)));
+        assert_eq!(service.resolve_json_path(&body, ""), Some(&body));
+    }
+
+    #[test]
+    fn missing_paths_resolve_to_none() {
+        let service = SmokeTestService::new();
+        let body = body();
+
+        assert_eq!(service.resolve_json_path(&body, "data.items[5].id"), None);
+        assert_eq!(service.resolve_json_path(&body, "data.missing"), None);
+        assert_eq!(service.resolve_json_path(&body, "data.items[x]"), None);
+    }
+
+    #[test]
+    fn assertions_require_present_non_null_values() {
+        let service = SmokeTestService::new();
+        let body = body();
+        let assertion = |path: &str, equals: Option<serde_json::Value>| JsonAssertion { path: path.to_string(), equals };
+
+        assert!(service.check_assertion(&body, &assertion("data.total", None)).is_none());
+        assert!(service.check_assertion(&body, &assertion("data.total", Some(json!(2)))).is_none());
+        assert!(service.check_assertion(&body, &assertion("data.total", Some(json!(3)))).is_some());
+        assert!(service.check_assertion(&body, &assertion("data.cursor", None)).is_some());
+        assert!(service.check_assertion(&body, &assertion("data.cursor", Some(json!(null)))).is_none());
+        assert!(service.check_assertion(&body, &assertion("data.nope", None)).is_some());
+    }
+
+    #[test]
+    fn steps_saved_without_assertions_still_deserialize() {
+        let step: SmokeStep = serde_json::from_value(json!({
+            "method": "GET",
+            "path": "/health",
+            "expected_status": 200
+        }))
+        .unwrap();
+
+        assert!(step.json_assertions.is_empty());
+    }
+
+    #[test]
+    fn pass_rate_counts_passed_steps() {
+        let report = SmokeTestReport { base_url: String::new(), total_steps: 4, passed_steps: 3, steps: Vec::new() };
+        assert_eq!(report.pass_rate(), 0.75);
+        assert!(!report.passed());
+
+        let empty = SmokeTestReport { base_url: String::new(), total_steps: 0, passed_steps: 0, steps: Vec::new() };
+        assert_eq!(empty.pass_rate(), 0.0);
+        assert!(!empty.passed());
+    }
+}